@@ -28,7 +28,7 @@ class HttpTransportException implements Exception {
 /// HTTP-based transport for server synchronization.
 ///
 /// This implements a simple sync protocol:
-/// - `GET /sync?since={token}&limit={n}` - Pull operations
+/// - `GET /sync?since={token}&limit={n}&nodeId={id}` - Pull operations
 /// - `POST /sync` - Push operations
 ///
 /// Expected server response format for pull:
@@ -36,7 +36,8 @@ class HttpTransportException implements Exception {
 /// {
 ///   "operations": [...],
 ///   "syncToken": "token_value",
-///   "hasMore": false
+///   "hasMore": false,
+///   "stableVector": {"node_a": 12}
 /// }
 /// ```
 ///
@@ -59,7 +60,7 @@ class HttpTransport implements Transport {
     this.pullLimit = 100,
   }) : _client = client ?? http.Client();
 
-  /// Node ID for this client (used in push requests).
+  /// Node ID for this client (used in push and pull requests).
   final String nodeId;
 
   /// Base URL for the sync API.
@@ -104,6 +105,7 @@ class HttpTransport implements Transport {
     try {
       final queryParams = <String, String>{
         'limit': pullLimit.toString(),
+        'nodeId': nodeId,
       };
       if (lastSyncToken != null && lastSyncToken.isNotEmpty) {
         queryParams['since'] = lastSyncToken;
@@ -156,6 +158,7 @@ class HttpTransport implements Transport {
         operations: operations,
         syncToken: json['syncToken'] as String?,
         hasMore: json['hasMore'] as bool? ?? false,
+        stableVector: PullResult.stableVectorFromJson(json['stableVector']),
       );
     } on HttpTransportException {
       rethrow;
@@ -6,6 +6,7 @@ class PullResult {
     required this.operations,
     this.syncToken,
     this.hasMore = false,
+    this.stableVector,
   });
 
   /// Operations received from the server.
@@ -16,6 +17,20 @@ class PullResult {
 
   /// Whether there are more operations to fetch.
   final bool hasMore;
+
+  /// Writes every known node has acknowledged, as node ID to clock counter.
+  ///
+  /// Tombstones older than this vector can be purged. Null if the server
+  /// did not send one.
+  final Map<String, int>? stableVector;
+
+  /// Parse [stableVector] from a pull response field.
+  static Map<String, int>? stableVectorFromJson(Object? json) {
+    if (json is! Map) return null;
+    return json.map(
+      (node, counter) => MapEntry(node as String, counter as int),
+    );
+  }
 }
 
 /// Result of pushing operations to the server.
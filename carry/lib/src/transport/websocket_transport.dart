@@ -455,6 +455,8 @@ class WebSocketTransport implements Transport {
         operations: operations,
         syncToken: response['sync_token'] as String?,
         hasMore: response['has_more'] as bool? ?? false,
+        stableVector:
+            PullResult.stableVectorFromJson(response['stable_vector']),
       );
     } on WebSocketTransportException {
       rethrow;
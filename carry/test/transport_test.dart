@@ -171,6 +171,7 @@ void main() {
           expect(request.method, equals('GET'));
           expect(request.url.path, equals('/sync'));
           expect(request.url.queryParameters['limit'], equals('100'));
+          expect(request.url.queryParameters['nodeId'], equals('test_node'));
 
           return http.Response(
             jsonEncode({
@@ -187,6 +188,7 @@ void main() {
               ],
               'syncToken': 'new_token_abc',
               'hasMore': false,
+              'stableVector': {'server': 1, 'test_node': 3},
             }),
             200,
           );
@@ -204,6 +206,7 @@ void main() {
         expect(result.operations[0], isA<CreateOp>());
         expect(result.syncToken, equals('new_token_abc'));
         expect(result.hasMore, isFalse);
+        expect(result.stableVector, equals({'server': 1, 'test_node': 3}));
       });
 
       test('includes since parameter when token provided', () async {
@@ -284,6 +287,7 @@ void main() {
         final result = await transport.pull(null);
 
         expect(result.syncToken, isNull);
+        expect(result.stableVector, isNull);
       });
 
       test('includes custom headers', () async {
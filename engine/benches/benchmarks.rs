@@ -2,7 +2,7 @@
 
 use carry_engine::{
     CollectionSchema, CreateOp, FieldDef, FieldType, LogicalClock, MergeStrategy, Operation,
-    Schema, Store,
+    Schema, Store, UpdateOp,
 };
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use serde_json::json;
@@ -139,6 +139,57 @@ fn bench_reconciliation(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_incremental_reconciliation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("incremental_reconciliation");
+
+    // Only the records a batch touches are loaded, so reconciling five
+    // remote edits should cost about the same whatever the store size
+    for size in [1_000u64, 10_000, 100_000].iter() {
+        let schema = create_test_schema();
+        let mut store = Store::new(schema, "local".to_string());
+        for i in 0..*size {
+            let op = Operation::Create(CreateOp::new(
+                format!("op_{}", i),
+                format!("user_{}", i),
+                "users",
+                json!({"name": format!("User {}", i)}),
+                1000,
+                LogicalClock::with_counter("local", i),
+            ));
+            let _ = store.apply(op, 1000);
+        }
+        store.clear_pending();
+
+        let mut counter = *size;
+        group.bench_with_input(
+            BenchmarkId::new("reconcile_5_remote_ops", size),
+            size,
+            |b, &size| {
+                b.iter(|| {
+                    let remote_ops: Vec<Operation> = (0..5)
+                        .map(|i| {
+                            counter += 1;
+                            Operation::Update(UpdateOp::new(
+                                format!("remote_op_{}", counter),
+                                format!("user_{}", i * size / 5),
+                                "users",
+                                json!({"name": format!("Remote User {}", counter)}),
+                                1,
+                                2000,
+                                LogicalClock::with_counter("remote", counter),
+                            ))
+                        })
+                        .collect();
+
+                    store.reconcile(black_box(remote_ops), black_box(MergeStrategy::ClockWins))
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn bench_snapshot(c: &mut Criterion) {
     let mut group = c.benchmark_group("snapshot");
 
@@ -221,6 +272,7 @@ criterion_group!(
     benches,
     bench_store_operations,
     bench_reconciliation,
+    bench_incremental_reconciliation,
     bench_snapshot,
     bench_serialization,
 );
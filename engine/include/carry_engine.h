@@ -60,6 +60,18 @@ extern "C"
      */
     char *carry_store_apply(CarryStore store, const char *op_json, int64_t timestamp);
 
+    /**
+     * Apply a group of operations atomically.
+     *
+     * If any operation fails, none of them are applied.
+     *
+     * @param store Pointer to store
+     * @param ops_json JSON array of Operations
+     * @param timestamp Current timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_apply_batch(CarryStore store, const char *ops_json, int64_t timestamp);
+
     /**
      * Get a record by collection and ID.
      *
@@ -70,6 +82,18 @@ extern "C"
      */
     char *carry_store_get(CarryStore store, const char *collection, const char *id);
 
+    /**
+     * Get a record by collection and ID, unless it is expired at `now`.
+     *
+     * @param store Pointer to store
+     * @param collection Collection name
+     * @param id Record ID
+     * @param now Current timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_get_at(CarryStore store, const char *collection, const char *id,
+                             uint64_t now);
+
     /**
      * Query records in a collection.
      *
@@ -80,6 +104,107 @@ extern "C"
      */
     char *carry_store_query(CarryStore store, const char *collection, int32_t include_deleted);
 
+    /**
+     * Query the records in a collection that are not expired at `now`.
+     *
+     * @param store Pointer to store
+     * @param collection Collection name
+     * @param include_deleted Whether to include deleted records (0 or 1)
+     * @param now Current timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_query_at(CarryStore store, const char *collection, int32_t include_deleted,
+                               uint64_t now);
+
+    /**
+     * Get records holding conflicting versions awaiting resolution.
+     *
+     * @param store Pointer to store
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_conflicted_records(CarryStore store);
+
+    /**
+     * Resolve a conflicted record with the chosen payload.
+     *
+     * @param store Pointer to store
+     * @param op_id ID for the resolving operation
+     * @param collection Collection name
+     * @param id Record ID
+     * @param payload_json JSON of the chosen payload
+     * @param timestamp Timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_resolve_conflict(CarryStore store, const char *op_id, const char *collection,
+                                       const char *id, const char *payload_json,
+                                       uint64_t timestamp);
+
+    /**
+     * Get the logged versions of a record, oldest first.
+     *
+     * @param store Pointer to store
+     * @param collection Collection name
+     * @param id Record ID
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_history(CarryStore store, const char *collection, const char *id);
+
+    /**
+     * Revert a record to a version from its history.
+     *
+     * @param store Pointer to store
+     * @param op_id ID for the reverting operation
+     * @param collection Collection name
+     * @param id Record ID
+     * @param version Version to revert to
+     * @param timestamp Timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_revert_to(CarryStore store, const char *op_id, const char *collection,
+                                const char *id, uint64_t version, uint64_t timestamp);
+
+    /**
+     * Undo the latest local change. An undone batch is written as one batch.
+     *
+     * @param store Pointer to store
+     * @param op_id ID for the undoing operation
+     * @param timestamp Timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_undo(CarryStore store, const char *op_id, uint64_t timestamp);
+
+    /**
+     * Redo the latest undone change. A redone batch is written as one batch.
+     *
+     * @param store Pointer to store
+     * @param op_id ID for the redoing operation
+     * @param timestamp Timestamp in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_redo(CarryStore store, const char *op_id, uint64_t timestamp);
+
+    /**
+     * Remove tombstones every known node has seen.
+     *
+     * @param store Pointer to store
+     * @param older_than Only tombstones deleted before this timestamp are removed
+     * @param stable_clock_json JSON version vector of the writes every known node
+     *                          has acknowledged, as sent in the server's pull
+     *                          response
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_purge_tombstones(CarryStore store, uint64_t older_than,
+                                       const char *stable_clock_json);
+
+    /**
+     * Delete every record expired at now.
+     *
+     * @param store Pointer to store
+     * @param now Current time in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_expire(CarryStore store, uint64_t now);
+
     /**
      * Get count of pending operations.
      *
@@ -96,6 +221,15 @@ extern "C"
      */
     char *carry_store_pending_ops(CarryStore store);
 
+    /**
+     * Fold consecutive pending operations on the same record.
+     *
+     * @param store Pointer to store
+     * @return JSON result string with the IDs of dropped operations
+     *         (caller must free with carry_string_free)
+     */
+    char *carry_store_compact_pending(CarryStore store);
+
     /**
      * Acknowledge operations as synced.
      *
@@ -113,6 +247,25 @@ extern "C"
      */
     char *carry_store_tick(CarryStore store);
 
+    /**
+     * Advance the clock as a hybrid logical clock and return it.
+     *
+     * @param store Pointer to store
+     * @param wall_time Current wall-clock time in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_tick_at(CarryStore store, uint64_t wall_time);
+
+    /**
+     * Set the maximum drift accepted from incoming clocks.
+     *
+     * @param store Pointer to store
+     * @param max_drift Milliseconds a clock may run ahead of wall time, or a
+     *                  negative value to accept any clock
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_set_max_drift(CarryStore store, int64_t max_drift);
+
     // ============================================================================
     // Reconciliation
     // ============================================================================
@@ -122,11 +275,38 @@ extern "C"
      *
      * @param store Pointer to store
      * @param remote_ops_json JSON array of remote operations
-     * @param strategy Merge strategy (0 = ClockWins, 1 = TimestampWins)
+     * @param strategy Default merge strategy for collections whose schema declares none
+     *                 (0 = ClockWins, 1 = TimestampWins, 2 = FieldLevel, 3 = DeleteWins,
+     *                 4 = UpdateWins, 5 = KeepBoth)
      * @return JSON result string (caller must free with carry_string_free)
      */
     char *carry_store_reconcile(CarryStore store, const char *remote_ops_json, int32_t strategy);
 
+    /**
+     * Reconcile with remote operations received at now, rejecting those whose
+     * clock drifts too far ahead.
+     *
+     * @param store Pointer to store
+     * @param remote_ops_json JSON array of remote operations
+     * @param strategy As for carry_store_reconcile
+     * @param now Current wall-clock time in milliseconds
+     * @return JSON result string (caller must free with carry_string_free)
+     */
+    char *carry_store_reconcile_at(CarryStore store, const char *remote_ops_json,
+                                   int32_t strategy, uint64_t now);
+
+    /**
+     * Preview a reconciliation without changing the store.
+     *
+     * @param store Pointer to store
+     * @param remote_ops_json JSON array of remote operations
+     * @param strategy As for carry_store_reconcile
+     * @return JSON result string with the reconcile result and the records it
+     *         would change (caller must free with carry_string_free)
+     */
+    char *carry_store_preview_reconcile(CarryStore store, const char *remote_ops_json,
+                                        int32_t strategy);
+
     // ============================================================================
     // Snapshots
     // ============================================================================
@@ -2,11 +2,22 @@
 //!
 //! The clock provides a total ordering across all nodes, which is essential
 //! for deterministic conflict resolution.
+//!
+//! A [`HybridClock`] produces [`LogicalClock`] values whose counter packs a
+//! physical wall-clock time with a logical counter, so ordering follows real
+//! time where clocks agree without trusting raw timestamps.
 
-use crate::NodeId;
+use crate::{error::Result, Error, NodeId, Timestamp};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
+/// Bits of a hybrid counter holding the logical component.
+const LOGICAL_BITS: u32 = 16;
+const LOGICAL_MASK: u64 = (1 << LOGICAL_BITS) - 1;
+
+/// Default maximum drift accepted from remote hybrid clocks (one minute).
+pub const DEFAULT_MAX_DRIFT: u64 = 60_000;
+
 /// A logical clock that provides causal ordering.
 ///
 /// Ordering rules:
@@ -60,9 +71,136 @@ impl LogicalClock {
 
     /// Check if two clocks are concurrent (neither happened before the other
     /// based on counter alone, but from different nodes).
+    ///
+    /// Counters alone cannot tell whether one write saw another; use a
+    /// [`VersionVector`](crate::VersionVector) for that.
     pub fn is_concurrent_with(&self, other: &LogicalClock) -> bool {
         self.counter == other.counter && self.node_id != other.node_id
     }
+
+    /// Create a clock from hybrid components.
+    pub fn hybrid(node_id: impl Into<NodeId>, physical: Timestamp, logical: u16) -> Self {
+        Self::with_counter(node_id, (physical << LOGICAL_BITS) | u64::from(logical))
+    }
+
+    /// Physical time (milliseconds) of a hybrid clock.
+    ///
+    /// Plain Lamport counters below 2^16 report a physical time of 0.
+    pub fn physical_time(&self) -> Timestamp {
+        self.counter >> LOGICAL_BITS
+    }
+
+    /// Logical component of a hybrid clock.
+    pub fn logical_count(&self) -> u16 {
+        (self.counter & LOGICAL_MASK) as u16
+    }
+}
+
+/// A hybrid logical clock (HLC).
+///
+/// Combines physical milliseconds with a logical counter that orders events
+/// within the same millisecond. Values are handed out as [`LogicalClock`]s,
+/// with the counter holding `physical << 16 | logical`, so they serialize to
+/// the existing `nodeId`/`counter` JSON and order correctly among operations.
+/// Plain counters sort before any hybrid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridClock {
+    /// Unique identifier for the node
+    node_id: NodeId,
+    /// Highest physical time seen, in milliseconds
+    physical: Timestamp,
+    /// Logical counter within `physical`
+    logical: u16,
+    /// Maximum milliseconds a remote clock may run ahead of wall time
+    max_drift: u64,
+}
+
+impl HybridClock {
+    /// Create a new hybrid clock for a node.
+    pub fn new(node_id: impl Into<NodeId>) -> Self {
+        Self {
+            node_id: node_id.into(),
+            physical: 0,
+            logical: 0,
+            max_drift: DEFAULT_MAX_DRIFT,
+        }
+    }
+
+    /// Resume a hybrid clock from its last issued value.
+    pub fn from_clock(clock: &LogicalClock) -> Self {
+        Self {
+            node_id: clock.node_id.clone(),
+            physical: clock.physical_time(),
+            logical: clock.logical_count(),
+            max_drift: DEFAULT_MAX_DRIFT,
+        }
+    }
+
+    /// Set the maximum drift (milliseconds) accepted from remote clocks.
+    pub fn with_max_drift(mut self, max_drift: u64) -> Self {
+        self.max_drift = max_drift;
+        self
+    }
+
+    /// Get the current value without advancing.
+    pub fn current(&self) -> LogicalClock {
+        LogicalClock::hybrid(self.node_id.clone(), self.physical, self.logical)
+    }
+
+    /// Advance the clock for a local event at `wall_time`.
+    pub fn now(&mut self, wall_time: Timestamp) -> LogicalClock {
+        if wall_time > self.physical {
+            self.physical = wall_time;
+            self.logical = 0;
+        } else {
+            self.increment_logical();
+        }
+        self.current()
+    }
+
+    /// Advance the clock past a remote clock received at `wall_time`.
+    ///
+    /// Fails with [`Error::ClockDrift`] if the remote clock is further ahead
+    /// of `wall_time` than the configured maximum drift; the clock is left
+    /// unchanged in that case.
+    pub fn receive(&mut self, remote: &LogicalClock, wall_time: Timestamp) -> Result<LogicalClock> {
+        let remote_physical = remote.physical_time();
+        let drift = remote_physical.saturating_sub(wall_time);
+        if drift > self.max_drift {
+            return Err(Error::ClockDrift {
+                drift,
+                max_drift: self.max_drift,
+            });
+        }
+
+        let physical = self.physical.max(remote_physical).max(wall_time);
+        if physical == self.physical && physical == remote_physical {
+            self.logical = self.logical.max(remote.logical_count());
+            self.increment_logical();
+        } else if physical == self.physical {
+            self.increment_logical();
+        } else if physical == remote_physical {
+            self.physical = physical;
+            self.logical = remote.logical_count();
+            self.increment_logical();
+        } else {
+            self.physical = physical;
+            self.logical = 0;
+        }
+
+        Ok(self.current())
+    }
+
+    fn increment_logical(&mut self) {
+        // On overflow, borrow the next millisecond rather than wrap around
+        match self.logical.checked_add(1) {
+            Some(logical) => self.logical = logical,
+            None => {
+                self.physical += 1;
+                self.logical = 0;
+            }
+        }
+    }
 }
 
 impl Ord for LogicalClock {
@@ -157,6 +295,90 @@ mod tests {
         assert_eq!(clock, parsed);
     }
 
+    #[test]
+    fn hybrid_components() {
+        let clock = LogicalClock::hybrid("node-1", 1_700_000_000_000, 3);
+        assert_eq!(clock.physical_time(), 1_700_000_000_000);
+        assert_eq!(clock.logical_count(), 3);
+
+        // Legacy counters have no physical component and sort first
+        let legacy = LogicalClock::with_counter("node-1", 42);
+        assert_eq!(legacy.physical_time(), 0);
+        assert!(legacy < clock);
+    }
+
+    #[test]
+    fn hybrid_now_is_monotonic() {
+        let mut hlc = HybridClock::new("node-1");
+
+        let first = hlc.now(1000);
+        assert_eq!(first, LogicalClock::hybrid("node-1", 1000, 0));
+
+        // Same or earlier wall time only advances the logical counter
+        let second = hlc.now(1000);
+        let third = hlc.now(900);
+        assert_eq!(third, LogicalClock::hybrid("node-1", 1000, 2));
+        assert!(first < second && second < third);
+
+        let fourth = hlc.now(2000);
+        assert_eq!(fourth, LogicalClock::hybrid("node-1", 2000, 0));
+    }
+
+    #[test]
+    fn hybrid_logical_overflow_borrows_millisecond() {
+        let mut hlc = HybridClock::from_clock(&LogicalClock::hybrid("node-1", 1000, u16::MAX));
+        assert_eq!(hlc.now(1000), LogicalClock::hybrid("node-1", 1001, 0));
+    }
+
+    #[test]
+    fn hybrid_receive() {
+        let mut hlc = HybridClock::new("node-1");
+        hlc.now(1000);
+
+        // Remote ahead of both local clock and wall time
+        let remote = LogicalClock::hybrid("node-2", 1500, 4);
+        let received = hlc.receive(&remote, 1200).unwrap();
+        assert_eq!(received, LogicalClock::hybrid("node-1", 1500, 5));
+        assert!(received > remote);
+
+        // Wall time ahead of everything resets the logical counter
+        let received = hlc.receive(&remote, 3000).unwrap();
+        assert_eq!(received, LogicalClock::hybrid("node-1", 3000, 0));
+
+        // Legacy remote counters never count as drift
+        let legacy = LogicalClock::with_counter("node-3", 7);
+        assert!(hlc.receive(&legacy, 3000).is_ok());
+    }
+
+    #[test]
+    fn hybrid_receive_rejects_drift() {
+        let mut hlc = HybridClock::new("node-1").with_max_drift(500);
+        hlc.now(1000);
+
+        let remote = LogicalClock::hybrid("node-2", 2000, 0);
+        assert_eq!(
+            hlc.receive(&remote, 1000),
+            Err(Error::ClockDrift {
+                drift: 1000,
+                max_drift: 500
+            })
+        );
+        assert_eq!(hlc.current(), LogicalClock::hybrid("node-1", 1000, 0));
+
+        assert!(hlc.receive(&remote, 1500).is_ok());
+    }
+
+    #[test]
+    fn hybrid_serializes_as_logical_clock() {
+        let mut hlc = HybridClock::new("node-1");
+        let clock = hlc.now(1000);
+        let json = serde_json::to_value(&clock).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"nodeId": "node-1", "counter": 1000 << 16})
+        );
+    }
+
     #[test]
     fn serialization_format() {
         let clock = LogicalClock::with_counter("node-1", 10);
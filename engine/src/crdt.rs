@@ -0,0 +1,689 @@
+//! Conflict-free replicated data types for individual payload fields.
+//!
+//! Full-payload writes replace a field wholesale, so concurrent edits to the
+//! same field can only be resolved by picking a winner. Fields declared with
+//! a CRDT type instead keep a replicated state on the [`Record`] that merges
+//! concurrent edits deterministically, whatever order they arrive in. The
+//! payload always holds the materialized value of the field.
+//!
+//! # Lists
+//!
+//! A [`ListCrdt`] is a replicated growable array (RGA). Every insert creates
+//! a *slot*, identified by the ID of the operation that created it, placed
+//! after an existing slot. Concurrent inserts after the same slot are ordered
+//! by clock, highest first, which gives every replica the same sequence.
+//!
+//! Elements live in slots. An element starts in the slot its insert created
+//! (so its element ID is also its first slot ID); a move places it in a new
+//! slot, and concurrent moves of one element resolve to the highest clock.
+//! Removed elements leave tombstones so later operations can still refer to
+//! their slots.
+//!
+//! # Text
+//!
+//! A [`TextCrdt`] is the same kind of sequence over characters, without
+//! moves. Each character inserted by a [`TextSplice`] gets the ID
+//! `"{op_id}:{index}"`, and a splice names the characters it removes, so
+//! concurrent edits to different parts of a string both survive.
+//!
+//! # Sets
+//!
+//! A [`SetCrdt`] is an observed-remove set. Every add tags the element with
+//! the ID of its operation, and a remove deletes only the tags its author had
+//! observed, so an add concurrent with a remove of the same element wins.
+//!
+//! [`Record`]: crate::Record
+
+use crate::{error::Result, Error, LogicalClock, OperationId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// ID of a list element or slot: the ID of the operation that created it.
+pub type ElementId = OperationId;
+
+/// Replicated state of a CRDT field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FieldCrdt {
+    /// Ordered sequence, see [`ListCrdt`]
+    List(ListCrdt),
+    /// Collaborative string, see [`TextCrdt`]
+    Text(TextCrdt),
+    /// Add-wins set, see [`SetCrdt`]
+    Set(SetCrdt),
+}
+
+/// A position in the list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Slot {
+    id: ElementId,
+    clock: LogicalClock,
+    element: ElementId,
+}
+
+/// A list value and the slot it currently occupies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Element {
+    value: Value,
+    slot: ElementId,
+    /// Clock of the insert or move that placed the element in its slot
+    placed: LogicalClock,
+    deleted: bool,
+}
+
+/// Ordered sequence CRDT (replicated growable array).
+///
+/// See the [module documentation](self) for how concurrent edits merge.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCrdt {
+    /// Slots in sequence order, including empty ones
+    slots: Vec<Slot>,
+    elements: BTreeMap<ElementId, Element>,
+}
+
+impl ListCrdt {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a list holding `values`, as written by a full-payload write at
+    /// `clock`.
+    ///
+    /// Element IDs are derived from the clock and index, so replicas that
+    /// saw the same write build the same list.
+    pub fn from_values(values: &[Value], clock: &LogicalClock) -> Self {
+        let mut list = Self::new();
+        for (index, value) in values.iter().enumerate() {
+            let id = format!("{}:{}:{}", clock.node_id, clock.counter, index);
+            list.slots.push(Slot {
+                id: id.clone(),
+                clock: clock.clone(),
+                element: id.clone(),
+            });
+            list.elements.insert(
+                id.clone(),
+                Element {
+                    value: value.clone(),
+                    slot: id,
+                    placed: clock.clone(),
+                    deleted: false,
+                },
+            );
+        }
+        list
+    }
+
+    /// Insert `value` as a new element after the slot `after` (`None` for
+    /// the head of the list).
+    ///
+    /// Returns `false` if an element with this ID already exists.
+    pub fn insert(
+        &mut self,
+        id: &ElementId,
+        clock: &LogicalClock,
+        after: Option<&ElementId>,
+        value: Value,
+    ) -> Result<bool> {
+        if self.elements.contains_key(id) {
+            return Ok(false);
+        }
+
+        self.integrate(id, clock, after, id)?;
+        self.elements.insert(
+            id.clone(),
+            Element {
+                value,
+                slot: id.clone(),
+                placed: clock.clone(),
+                deleted: false,
+            },
+        );
+        Ok(true)
+    }
+
+    /// Remove an element.
+    ///
+    /// Returns `false` if it was already removed.
+    pub fn remove(&mut self, element: &ElementId) -> Result<bool> {
+        let element = self
+            .elements
+            .get_mut(element)
+            .ok_or_else(|| invalid(format!("element not found: {}", element)))?;
+        if element.deleted {
+            return Ok(false);
+        }
+        element.deleted = true;
+        Ok(true)
+    }
+
+    /// Move an element into a new slot `id` after the slot `after`.
+    ///
+    /// Of concurrent moves of one element, the one with the highest clock
+    /// decides where it ends up. Returns `false` if the slot already exists.
+    pub fn move_element(
+        &mut self,
+        id: &ElementId,
+        clock: &LogicalClock,
+        element: &ElementId,
+        after: Option<&ElementId>,
+    ) -> Result<bool> {
+        if self.slots.iter().any(|slot| &slot.id == id) {
+            return Ok(false);
+        }
+        if !self.elements.contains_key(element) {
+            return Err(invalid(format!("element not found: {}", element)));
+        }
+
+        self.integrate(id, clock, after, element)?;
+        if let Some(entry) = self.elements.get_mut(element) {
+            if (clock, id) > (&entry.placed, &entry.slot) {
+                entry.slot = id.clone();
+                entry.placed = clock.clone();
+            }
+        }
+        Ok(true)
+    }
+
+    /// Get the slot an element currently occupies.
+    ///
+    /// Insert or move after this slot to place a value right after the
+    /// element.
+    pub fn slot_of(&self, element: &str) -> Option<&ElementId> {
+        self.elements.get(element).map(|e| &e.slot)
+    }
+
+    /// Visible elements in order, as (element ID, value) pairs.
+    pub fn elements(&self) -> impl Iterator<Item = (&ElementId, &Value)> {
+        self.slots.iter().filter_map(|slot| {
+            let element = self.elements.get(&slot.element)?;
+            (!element.deleted && element.slot == slot.id).then_some((&slot.element, &element.value))
+        })
+    }
+
+    /// Visible values in order.
+    pub fn values(&self) -> Vec<Value> {
+        self.elements().map(|(_, value)| value.clone()).collect()
+    }
+
+    /// Place a new slot after `after`, skipping past concurrent slots with
+    /// a higher clock.
+    fn integrate(
+        &mut self,
+        id: &ElementId,
+        clock: &LogicalClock,
+        after: Option<&ElementId>,
+        element: &ElementId,
+    ) -> Result<()> {
+        let mut index = match after {
+            None => 0,
+            Some(after) => {
+                self.slots
+                    .iter()
+                    .position(|slot| &slot.id == after)
+                    .ok_or_else(|| invalid(format!("slot not found: {}", after)))?
+                    + 1
+            }
+        };
+
+        while index < self.slots.len()
+            && (&self.slots[index].clock, &self.slots[index].id) > (clock, id)
+        {
+            index += 1;
+        }
+
+        self.slots.insert(
+            index,
+            Slot {
+                id: id.clone(),
+                clock: clock.clone(),
+                element: element.clone(),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// A character of a text field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Char {
+    id: ElementId,
+    clock: LogicalClock,
+    value: char,
+    deleted: bool,
+}
+
+/// Edit to a `Text` field: remove some characters, then insert a string
+/// after a character (`None` for the start of the text).
+///
+/// Use [`TextCrdt::splice_at`] to build one from a position in the string.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextSplice {
+    /// Character to insert after
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<ElementId>,
+    /// Characters to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<ElementId>,
+    /// Text to insert
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub insert: String,
+}
+
+/// Character sequence CRDT for collaborative text.
+///
+/// See the [module documentation](self) for how concurrent edits merge.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextCrdt {
+    /// Characters in order, including removed ones
+    chars: Vec<Char>,
+}
+
+impl TextCrdt {
+    /// Create an empty text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a text holding `text`, as written by a full-payload write at
+    /// `clock`.
+    ///
+    /// Character IDs are derived from the clock and index, like
+    /// [`ListCrdt::from_values`].
+    pub fn from_text(text: &str, clock: &LogicalClock) -> Self {
+        let chars = text
+            .chars()
+            .enumerate()
+            .map(|(index, value)| Char {
+                id: format!("{}:{}:{}", clock.node_id, clock.counter, index),
+                clock: clock.clone(),
+                value,
+                deleted: false,
+            })
+            .collect();
+        Self { chars }
+    }
+
+    /// Build a splice that removes `delete` characters at character
+    /// position `index` and inserts `insert` there.
+    pub fn splice_at(&self, index: usize, delete: usize, insert: impl Into<String>) -> TextSplice {
+        let visible: Vec<&ElementId> = self
+            .chars
+            .iter()
+            .filter(|c| !c.deleted)
+            .map(|c| &c.id)
+            .collect();
+        let index = index.min(visible.len());
+        let end = (index + delete).min(visible.len());
+
+        TextSplice {
+            after: index.checked_sub(1).map(|i| visible[i].clone()),
+            remove: visible[index..end].iter().map(|id| (*id).clone()).collect(),
+            insert: insert.into(),
+        }
+    }
+
+    /// Apply a splice made by operation `op_id` at `clock`.
+    ///
+    /// Returns `false` if the splice was already applied (or changes
+    /// nothing).
+    pub fn apply(
+        &mut self,
+        op_id: &OperationId,
+        clock: &LogicalClock,
+        splice: &TextSplice,
+    ) -> Result<bool> {
+        let first = format!("{}:0", op_id);
+        if self.chars.iter().any(|c| c.id == first) {
+            return Ok(false);
+        }
+
+        // Resolve every reference before changing anything
+        let mut index = match &splice.after {
+            None => 0,
+            Some(after) => self.position(after)? + 1,
+        };
+        let removed = splice
+            .remove
+            .iter()
+            .map(|id| self.position(id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut changed = false;
+        for position in removed {
+            changed |= !self.chars[position].deleted;
+            self.chars[position].deleted = true;
+        }
+
+        for (offset, value) in splice.insert.chars().enumerate() {
+            let id = format!("{}:{}", op_id, offset);
+            // Only the first character can meet concurrent inserts; the rest
+            // follow it directly
+            if offset == 0 {
+                while index < self.chars.len()
+                    && (&self.chars[index].clock, &self.chars[index].id) > (clock, &id)
+                {
+                    index += 1;
+                }
+            }
+            self.chars.insert(
+                index,
+                Char {
+                    id,
+                    clock: clock.clone(),
+                    value,
+                    deleted: false,
+                },
+            );
+            index += 1;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    /// Visible text.
+    pub fn text(&self) -> String {
+        self.chars
+            .iter()
+            .filter(|c| !c.deleted)
+            .map(|c| c.value)
+            .collect()
+    }
+
+    fn position(&self, id: &ElementId) -> Result<usize> {
+        self.chars
+            .iter()
+            .position(|c| &c.id == id)
+            .ok_or_else(|| invalid(format!("character not found: {}", id)))
+    }
+}
+
+/// A set element and the adds that currently hold it in the set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetEntry {
+    value: Value,
+    tags: BTreeSet<ElementId>,
+}
+
+/// Observed-remove set CRDT with add-wins semantics.
+///
+/// Elements are ordered by their JSON encoding. See the
+/// [module documentation](self) for how concurrent edits merge.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCrdt {
+    /// Elements keyed by their JSON encoding
+    entries: BTreeMap<String, SetEntry>,
+    /// Tags of removed adds, so a redelivered add stays removed
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    removed: BTreeSet<ElementId>,
+}
+
+impl SetCrdt {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a set holding `values`, as written by a full-payload write at
+    /// `clock`.
+    ///
+    /// Each element is tagged as if added at `clock`, like
+    /// [`ListCrdt::from_values`].
+    pub fn from_values(values: &[Value], clock: &LogicalClock) -> Self {
+        let mut set = Self::new();
+        for value in values {
+            let key = value.to_string();
+            let tag = format!("{}:{}:{}", clock.node_id, clock.counter, key);
+            set.add(&tag, value.clone());
+        }
+        set
+    }
+
+    /// Add `value` under the tag `tag`.
+    ///
+    /// Returns `false` if the tag was already added or removed.
+    pub fn add(&mut self, tag: &ElementId, value: Value) -> bool {
+        if self.removed.contains(tag) {
+            return false;
+        }
+        self.entries
+            .entry(value.to_string())
+            .or_insert_with(|| SetEntry {
+                value,
+                tags: BTreeSet::new(),
+            })
+            .tags
+            .insert(tag.clone())
+    }
+
+    /// Remove the `observed` tags of `value`.
+    ///
+    /// Returns `false` if none of them were present.
+    pub fn remove(&mut self, value: &Value, observed: &[ElementId]) -> bool {
+        self.removed.extend(observed.iter().cloned());
+
+        let key = value.to_string();
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return false;
+        };
+        let before = entry.tags.len();
+        entry.tags.retain(|tag| !observed.contains(tag));
+        let changed = entry.tags.len() != before;
+        if entry.tags.is_empty() {
+            self.entries.remove(&key);
+        }
+        changed
+    }
+
+    /// Tags currently holding `value` in the set.
+    ///
+    /// A remove of `value` should name these.
+    pub fn observed(&self, value: &Value) -> Vec<ElementId> {
+        self.entries
+            .get(&value.to_string())
+            .map(|entry| entry.tags.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if `value` is in the set.
+    pub fn contains(&self, value: &Value) -> bool {
+        self.entries.contains_key(&value.to_string())
+    }
+
+    /// Elements in order of their JSON encoding.
+    pub fn values(&self) -> Vec<Value> {
+        self.entries.values().map(|e| e.value.clone()).collect()
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidCrdt(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn clock(node: &str, counter: u64) -> LogicalClock {
+        LogicalClock::with_counter(node, counter)
+    }
+
+    fn id(s: &str) -> ElementId {
+        s.to_string()
+    }
+
+    #[test]
+    fn insert_in_order() {
+        let mut list = ListCrdt::new();
+        list.insert(&id("a"), &clock("n", 1), None, json!("a"))
+            .unwrap();
+        list.insert(&id("b"), &clock("n", 2), Some(&id("a")), json!("b"))
+            .unwrap();
+        list.insert(&id("c"), &clock("n", 3), None, json!("c"))
+            .unwrap();
+
+        assert_eq!(list.values(), vec![json!("c"), json!("a"), json!("b")]);
+
+        // Duplicate delivery is a no-op
+        assert!(!list
+            .insert(&id("a"), &clock("n", 1), None, json!("a"))
+            .unwrap());
+    }
+
+    #[test]
+    fn concurrent_inserts_converge() {
+        let base = ListCrdt::from_values(&[json!("x")], &clock("n", 1));
+        let anchor = id("n:1:0");
+
+        // Two nodes insert after the same element in either order
+        let mut left = base.clone();
+        left.insert(&id("p"), &clock("p", 2), Some(&anchor), json!("p"))
+            .unwrap();
+        left.insert(&id("q"), &clock("q", 3), Some(&anchor), json!("q"))
+            .unwrap();
+
+        let mut right = base;
+        right
+            .insert(&id("q"), &clock("q", 3), Some(&anchor), json!("q"))
+            .unwrap();
+        right
+            .insert(&id("p"), &clock("p", 2), Some(&anchor), json!("p"))
+            .unwrap();
+
+        assert_eq!(left.values(), right.values());
+        assert_eq!(left.values(), vec![json!("x"), json!("q"), json!("p")]);
+    }
+
+    #[test]
+    fn remove_and_move() {
+        let mut list = ListCrdt::from_values(&[json!(1), json!(2), json!(3)], &clock("n", 1));
+
+        assert!(list.remove(&id("n:1:1")).unwrap());
+        assert!(!list.remove(&id("n:1:1")).unwrap());
+        assert!(list.remove(&id("missing")).is_err());
+        assert_eq!(list.values(), vec![json!(1), json!(3)]);
+
+        // Move the first element to the end; its old slot stays an anchor
+        list.move_element(&id("m1"), &clock("n", 2), &id("n:1:0"), Some(&id("n:1:2")))
+            .unwrap();
+        assert_eq!(list.values(), vec![json!(3), json!(1)]);
+        assert_eq!(list.slot_of("n:1:0"), Some(&id("m1")));
+        list.insert(&id("i"), &clock("n", 3), Some(&id("n:1:0")), json!(0))
+            .unwrap();
+        assert_eq!(list.values(), vec![json!(0), json!(3), json!(1)]);
+    }
+
+    #[test]
+    fn concurrent_moves_pick_highest_clock() {
+        let base = ListCrdt::from_values(&[json!("a"), json!("b"), json!("c")], &clock("n", 1));
+        let (a, c) = (id("n:1:0"), id("n:1:2"));
+
+        let mut left = base.clone();
+        left.move_element(&id("m1"), &clock("p", 2), &a, Some(&c))
+            .unwrap();
+        left.move_element(&id("m2"), &clock("q", 3), &a, Some(&id("n:1:1")))
+            .unwrap();
+
+        let mut right = base;
+        right
+            .move_element(&id("m2"), &clock("q", 3), &a, Some(&id("n:1:1")))
+            .unwrap();
+        right
+            .move_element(&id("m1"), &clock("p", 2), &a, Some(&c))
+            .unwrap();
+
+        // The element appears once, where the later move put it
+        assert_eq!(left.values(), right.values());
+        assert_eq!(left.values(), vec![json!("b"), json!("a"), json!("c")]);
+    }
+
+    #[test]
+    fn splice_at_positions() {
+        let mut text = TextCrdt::from_text("held", &clock("n", 1));
+
+        let splice = text.splice_at(3, 1, "lo");
+        text.apply(&id("op-1"), &clock("n", 2), &splice).unwrap();
+        assert_eq!(text.text(), "hello");
+
+        let splice = text.splice_at(0, 0, "oh, ");
+        assert!(text.apply(&id("op-2"), &clock("n", 3), &splice).unwrap());
+        assert!(!text.apply(&id("op-2"), &clock("n", 3), &splice).unwrap());
+        assert_eq!(text.text(), "oh, hello");
+
+        // References to unknown characters fail without side effects
+        let bad = TextSplice {
+            after: None,
+            remove: vec![id("n:1:0"), id("missing")],
+            insert: String::new(),
+        };
+        assert!(text.apply(&id("op-3"), &clock("n", 4), &bad).is_err());
+        assert_eq!(text.text(), "oh, hello");
+    }
+
+    #[test]
+    fn concurrent_splices_converge() {
+        let base = TextCrdt::from_text("ab", &clock("n", 1));
+
+        // One node types after "a" while another replaces "b"
+        let typed = base.splice_at(1, 0, "xy");
+        let replaced = base.splice_at(1, 1, "B");
+
+        let mut left = base.clone();
+        left.apply(&id("p"), &clock("p", 2), &typed).unwrap();
+        left.apply(&id("q"), &clock("q", 2), &replaced).unwrap();
+
+        let mut right = base;
+        right.apply(&id("q"), &clock("q", 2), &replaced).unwrap();
+        right.apply(&id("p"), &clock("p", 2), &typed).unwrap();
+
+        assert_eq!(left.text(), right.text());
+        assert_eq!(left.text(), "aBxy");
+    }
+
+    #[test]
+    fn set_add_wins() {
+        let base = SetCrdt::from_values(&[json!("b"), json!("a")], &clock("n", 1));
+        assert_eq!(base.values(), vec![json!("a"), json!("b")]);
+
+        // One node removes "a" while another adds it again
+        let observed = base.observed(&json!("a"));
+        let mut left = base.clone();
+        assert!(left.remove(&json!("a"), &observed));
+        assert!(left.add(&id("p"), json!("a")));
+
+        let mut right = base;
+        assert!(right.add(&id("p"), json!("a")));
+        assert!(right.remove(&json!("a"), &observed));
+
+        assert_eq!(left, right);
+        assert!(left.contains(&json!("a")));
+
+        // A removed add stays removed when redelivered
+        let observed = left.observed(&json!("b"));
+        assert!(left.remove(&json!("b"), &observed));
+        assert!(!left.add(&observed[0], json!("b")));
+        assert_eq!(left.values(), vec![json!("a")]);
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let mut list = ListCrdt::from_values(&[json!("a")], &clock("n", 1));
+        list.insert(&id("b"), &clock("n", 2), Some(&id("n:1:0")), json!("b"))
+            .unwrap();
+        let crdt = FieldCrdt::List(list);
+
+        let json = serde_json::to_string(&crdt).unwrap();
+        let parsed: FieldCrdt = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, crdt);
+    }
+}
@@ -16,6 +16,12 @@ pub enum Error {
     #[error("invalid payload: {0}")]
     InvalidPayload(String),
 
+    #[error("invalid patch: {0}")]
+    InvalidPatch(String),
+
+    #[error("invalid CRDT operation: {0}")]
+    InvalidCrdt(String),
+
     #[error("missing required field: {0}")]
     MissingRequiredField(String),
 
@@ -36,6 +42,18 @@ pub enum Error {
     #[error("operation on deleted record: {0}")]
     OperationOnDeleted(RecordId),
 
+    #[error("record is not deleted: {0}")]
+    RecordNotDeleted(RecordId),
+
+    #[error("record has no conflict to resolve: {0}")]
+    RecordNotConflicted(RecordId),
+
+    #[error("version not found in record history: {0}")]
+    VersionNotInHistory(RecordId),
+
+    #[error("clock drift of {drift}ms exceeds maximum of {max_drift}ms")]
+    ClockDrift { drift: u64, max_drift: u64 },
+
     // State errors
     #[error("invalid snapshot: {0}")]
     InvalidSnapshot(String),
@@ -15,7 +15,7 @@
 //! - `{"ok": <result>}` on success
 //! - `{"error": "<message>"}` on failure
 
-use crate::{reconcile::MergeStrategy, Operation, Schema, Store, StoreSnapshot};
+use crate::{reconcile::MergeStrategy, Operation, Schema, Store, StoreSnapshot, VersionVector};
 use std::ffi::{c_char, CStr, CString};
 use std::ptr;
 
@@ -178,6 +178,51 @@ pub unsafe extern "C" fn carry_store_apply(
     }
 }
 
+/// Apply a group of operations to the store atomically.
+///
+/// If any operation fails, none of them are applied.
+///
+/// # Arguments
+/// - `store`: Store pointer
+/// - `ops_json`: JSON array of Operations
+/// - `timestamp`: Timestamp in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": [ApplyResult]}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `ops_json` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_apply_batch(
+    store: *mut Store,
+    ops_json: *const c_char,
+    timestamp: u64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let ops_str = match from_c_string(ops_json) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid operations JSON").to_json()),
+    };
+
+    let ops: Vec<Operation> = match serde_json::from_str(&ops_str) {
+        Ok(o) => o,
+        Err(e) => {
+            return to_c_string(FfiResult::<()>::err(format!("parse error: {}", e)).to_json())
+        }
+    };
+
+    match store.apply_batch(ops, timestamp) {
+        Ok(results) => to_c_string(FfiResult::ok(results).to_json()),
+        Err(e) => to_c_string(FfiResult::<()>::err(e.to_string()).to_json()),
+    }
+}
+
 /// Get a record by collection and ID.
 ///
 /// # Returns
@@ -212,6 +257,41 @@ pub unsafe extern "C" fn carry_store_get(
     to_c_string(FfiResult::ok(record).to_json())
 }
 
+/// Get a record by collection and ID, unless it is expired at `now`.
+///
+/// # Returns
+/// JSON string: `{"ok": Record}` or `{"ok": null}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `collection` and `id` must be valid null-terminated C strings or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_get_at(
+    store: *const Store,
+    collection: *const c_char,
+    id: *const c_char,
+    now: u64,
+) -> *mut c_char {
+    let store = match store.as_ref() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let collection_str = match from_c_string(collection) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid collection").to_json()),
+    };
+
+    let id_str = match from_c_string(id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid id").to_json()),
+    };
+
+    let record = store.get_at(&collection_str, &id_str, now);
+    to_c_string(FfiResult::ok(record).to_json())
+}
+
 /// Query all records in a collection.
 ///
 /// # Arguments
@@ -245,13 +325,355 @@ pub unsafe extern "C" fn carry_store_query(
         None => return to_c_string(FfiResult::<()>::err("collection not found").to_json()),
     };
 
-    let records: Vec<_> = if include_deleted != 0 {
-        query.include_deleted().all()
-    } else {
-        query.all()
+    let records: Vec<_> = if include_deleted != 0 {
+        query.include_deleted().all()
+    } else {
+        query.all()
+    };
+
+    to_c_string(FfiResult::ok(records).to_json())
+}
+
+/// Query the records in a collection that are not expired at `now`.
+///
+/// # Arguments
+/// - `include_deleted`: 0 for active only, non-zero for all
+///
+/// # Returns
+/// JSON string: `{"ok": [Record, ...]}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `collection` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_query_at(
+    store: *const Store,
+    collection: *const c_char,
+    include_deleted: i32,
+    now: u64,
+) -> *mut c_char {
+    let store = match store.as_ref() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let collection_str = match from_c_string(collection) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid collection").to_json()),
+    };
+
+    let query = match store.query(&collection_str) {
+        Some(q) => q.at(now),
+        None => return to_c_string(FfiResult::<()>::err("collection not found").to_json()),
+    };
+
+    let records: Vec<_> = if include_deleted != 0 {
+        query.include_deleted().all()
+    } else {
+        query.all()
+    };
+
+    to_c_string(FfiResult::ok(records).to_json())
+}
+
+/// Get records holding conflicting versions awaiting resolution.
+///
+/// # Returns
+/// JSON string: `{"ok": [Record, ...]}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_conflicted_records(store: *const Store) -> *mut c_char {
+    let store = match store.as_ref() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    to_c_string(FfiResult::ok(store.conflicted_records()).to_json())
+}
+
+/// Resolve a conflicted record with the chosen payload.
+///
+/// # Arguments
+/// - `op_id`: ID for the resolving operation
+/// - `payload_json`: JSON of the chosen payload
+/// - `timestamp`: Timestamp in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": ApplyResult}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `op_id`, `collection`, `id` and `payload_json` must be valid
+///   null-terminated C strings or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_resolve_conflict(
+    store: *mut Store,
+    op_id: *const c_char,
+    collection: *const c_char,
+    id: *const c_char,
+    payload_json: *const c_char,
+    timestamp: u64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let op_id_str = match from_c_string(op_id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid op_id").to_json()),
+    };
+
+    let collection_str = match from_c_string(collection) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid collection").to_json()),
+    };
+
+    let id_str = match from_c_string(id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid id").to_json()),
+    };
+
+    let payload_str = match from_c_string(payload_json) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid payload JSON").to_json()),
+    };
+
+    let payload: serde_json::Value = match serde_json::from_str(&payload_str) {
+        Ok(p) => p,
+        Err(e) => {
+            return to_c_string(FfiResult::<()>::err(format!("parse error: {}", e)).to_json())
+        }
+    };
+
+    match store.resolve_conflict(op_id_str, &collection_str, &id_str, payload, timestamp) {
+        Ok(result) => to_c_string(FfiResult::ok(result).to_json()),
+        Err(e) => to_c_string(FfiResult::<()>::err(e.to_string()).to_json()),
+    }
+}
+
+/// Get the logged versions of a record, oldest first.
+///
+/// # Returns
+/// JSON string: `{"ok": [RecordVersion, ...]}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `collection` and `id` must be valid null-terminated C strings or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_history(
+    store: *const Store,
+    collection: *const c_char,
+    id: *const c_char,
+) -> *mut c_char {
+    let store = match store.as_ref() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let collection_str = match from_c_string(collection) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid collection").to_json()),
+    };
+
+    let id_str = match from_c_string(id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid id").to_json()),
+    };
+
+    to_c_string(FfiResult::ok(store.history(&collection_str, &id_str)).to_json())
+}
+
+/// Revert a record to a version from its history.
+///
+/// # Arguments
+/// - `op_id`: ID for the reverting operation
+/// - `version`: Version to revert to
+/// - `timestamp`: Timestamp in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": ApplyResult}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `op_id`, `collection` and `id` must be valid null-terminated C strings
+///   or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_revert_to(
+    store: *mut Store,
+    op_id: *const c_char,
+    collection: *const c_char,
+    id: *const c_char,
+    version: u64,
+    timestamp: u64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let op_id_str = match from_c_string(op_id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid op_id").to_json()),
+    };
+
+    let collection_str = match from_c_string(collection) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid collection").to_json()),
+    };
+
+    let id_str = match from_c_string(id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid id").to_json()),
+    };
+
+    match store.revert_to(op_id_str, &collection_str, &id_str, version, timestamp) {
+        Ok(result) => to_c_string(FfiResult::ok(result).to_json()),
+        Err(e) => to_c_string(FfiResult::<()>::err(e.to_string()).to_json()),
+    }
+}
+
+/// Undo the latest local change.
+///
+/// # Arguments
+/// - `op_id`: ID for the undoing operation; an undone batch uses
+///   `op_id:1`, `op_id:2`... for its further operations
+/// - `timestamp`: Timestamp in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": [ApplyResult]}`, `{"ok": null}` if there is nothing
+/// to undo, or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `op_id` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_undo(
+    store: *mut Store,
+    op_id: *const c_char,
+    timestamp: u64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let op_id_str = match from_c_string(op_id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid op_id").to_json()),
+    };
+
+    match store.undo(op_id_str, timestamp) {
+        Ok(result) => to_c_string(FfiResult::ok(result).to_json()),
+        Err(e) => to_c_string(FfiResult::<()>::err(e.to_string()).to_json()),
+    }
+}
+
+/// Redo the latest undone change.
+///
+/// # Arguments
+/// - `op_id`: ID for the redoing operation; a redone batch uses
+///   `op_id:1`, `op_id:2`... for its further operations
+/// - `timestamp`: Timestamp in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": [ApplyResult]}`, `{"ok": null}` if there is nothing
+/// to redo, or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `op_id` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_redo(
+    store: *mut Store,
+    op_id: *const c_char,
+    timestamp: u64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let op_id_str = match from_c_string(op_id) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid op_id").to_json()),
+    };
+
+    match store.redo(op_id_str, timestamp) {
+        Ok(result) => to_c_string(FfiResult::ok(result).to_json()),
+        Err(e) => to_c_string(FfiResult::<()>::err(e.to_string()).to_json()),
+    }
+}
+
+/// Remove tombstones every known node has seen.
+///
+/// # Arguments
+/// - `older_than`: Only tombstones deleted before this timestamp are removed
+/// - `stable_clock_json`: JSON version vector of the writes every known node
+///   has acknowledged, as sent in the server's pull response
+///
+/// # Returns
+/// JSON string: `{"ok": <number removed>}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `stable_clock_json` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_purge_tombstones(
+    store: *mut Store,
+    older_than: u64,
+    stable_clock_json: *const c_char,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let stable_str = match from_c_string(stable_clock_json) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid stable clock JSON").to_json()),
+    };
+
+    let stable_clock: VersionVector = match serde_json::from_str(&stable_str) {
+        Ok(v) => v,
+        Err(e) => {
+            return to_c_string(FfiResult::<()>::err(format!("parse error: {}", e)).to_json())
+        }
+    };
+
+    let purged = store.purge_tombstones(older_than, &stable_clock);
+    to_c_string(FfiResult::ok(purged).to_json())
+}
+
+/// Delete every record expired at `now`.
+///
+/// # Returns
+/// JSON string: `{"ok": [ApplyResult]}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_expire(store: *mut Store, now: u64) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
     };
 
-    to_c_string(FfiResult::ok(records).to_json())
+    match store.expire(now) {
+        Ok(results) => to_c_string(FfiResult::ok(results).to_json()),
+        Err(e) => to_c_string(FfiResult::<()>::err(e.to_string()).to_json()),
+    }
 }
 
 /// Get pending operations count.
@@ -284,6 +706,24 @@ pub unsafe extern "C" fn carry_store_pending_ops(store: *const Store) -> *mut c_
     to_c_string(FfiResult::ok(store.pending_ops()).to_json())
 }
 
+/// Fold consecutive pending operations on the same record.
+///
+/// # Returns
+/// JSON string: `{"ok": [dropped op IDs]}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_compact_pending(store: *mut Store) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    to_c_string(FfiResult::ok(store.compact_pending()).to_json())
+}
+
 /// Acknowledge operations as synced.
 ///
 /// # Arguments
@@ -338,6 +778,54 @@ pub unsafe extern "C" fn carry_store_tick(store: *mut Store) -> *mut c_char {
     to_c_string(FfiResult::ok(clock).to_json())
 }
 
+/// Advance the store clock as a hybrid logical clock and return the new value.
+///
+/// # Arguments
+/// - `wall_time`: current wall-clock time in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": LogicalClock}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_tick_at(store: *mut Store, wall_time: u64) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let clock = store.tick_at(wall_time);
+    to_c_string(FfiResult::ok(clock).to_json())
+}
+
+/// Set the maximum drift accepted from incoming clocks.
+///
+/// # Arguments
+/// - `max_drift`: milliseconds a clock may run ahead of wall time, or a
+///   negative value to accept any clock
+///
+/// # Returns
+/// JSON string: `{"ok": null}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_set_max_drift(
+    store: *mut Store,
+    max_drift: i64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    store.set_max_drift(u64::try_from(max_drift).ok());
+    to_c_string(FfiResult::ok(()).to_json())
+}
+
 // ============================================================================
 // Reconciliation
 // ============================================================================
@@ -346,7 +834,9 @@ pub unsafe extern "C" fn carry_store_tick(store: *mut Store) -> *mut c_char {
 ///
 /// # Arguments
 /// - `remote_ops_json`: JSON array of remote Operations
-/// - `strategy`: 0 for ClockWins (default), 1 for TimestampWins
+/// - `strategy`: default for collections whose schema declares none.
+///   0 for ClockWins (default), 1 for TimestampWins, 2 for FieldLevel,
+///   3 for DeleteWins, 4 for UpdateWins, 5 for KeepBoth
 ///
 /// # Returns
 /// JSON string: `{"ok": ReconcileResult}` or `{"error": "message"}`
@@ -378,16 +868,105 @@ pub unsafe extern "C" fn carry_store_reconcile(
         }
     };
 
-    let merge_strategy = if strategy == 1 {
-        MergeStrategy::TimestampWins
-    } else {
-        MergeStrategy::ClockWins
+    let result = store.reconcile(remote_ops, merge_strategy(strategy));
+    to_c_string(FfiResult::ok(result).to_json())
+}
+
+/// Reconcile with remote operations received at `now`, rejecting those
+/// whose clock drifts too far ahead.
+///
+/// # Arguments
+/// - `remote_ops_json`: JSON array of remote Operations
+/// - `strategy`: as for `carry_store_reconcile`
+/// - `now`: current wall-clock time in milliseconds
+///
+/// # Returns
+/// JSON string: `{"ok": ReconcileResult}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `remote_ops_json` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_reconcile_at(
+    store: *mut Store,
+    remote_ops_json: *const c_char,
+    strategy: i32,
+    now: u64,
+) -> *mut c_char {
+    let store = match store.as_mut() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let remote_ops_str = match from_c_string(remote_ops_json) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid remote_ops JSON").to_json()),
+    };
+
+    let remote_ops: Vec<Operation> = match serde_json::from_str(&remote_ops_str) {
+        Ok(ops) => ops,
+        Err(e) => {
+            return to_c_string(FfiResult::<()>::err(format!("parse error: {}", e)).to_json())
+        }
     };
 
-    let result = store.reconcile(remote_ops, merge_strategy);
+    let result = store.reconcile_at(remote_ops, merge_strategy(strategy), now);
     to_c_string(FfiResult::ok(result).to_json())
 }
 
+/// Preview a reconciliation without changing the store.
+///
+/// # Arguments
+/// - `remote_ops_json`: JSON array of remote Operations
+/// - `strategy`: as for `carry_store_reconcile`
+///
+/// # Returns
+/// JSON string: `{"ok": ReconcilePreview}` or `{"error": "message"}`
+///
+/// # Safety
+/// - `store` must be a valid pointer from `carry_store_new` or null
+/// - `remote_ops_json` must be a valid null-terminated C string or null
+/// - Caller must free the returned string with `carry_string_free`
+#[no_mangle]
+pub unsafe extern "C" fn carry_store_preview_reconcile(
+    store: *const Store,
+    remote_ops_json: *const c_char,
+    strategy: i32,
+) -> *mut c_char {
+    let store = match store.as_ref() {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("null store pointer").to_json()),
+    };
+
+    let remote_ops_str = match from_c_string(remote_ops_json) {
+        Some(s) => s,
+        None => return to_c_string(FfiResult::<()>::err("invalid remote_ops JSON").to_json()),
+    };
+
+    let remote_ops: Vec<Operation> = match serde_json::from_str(&remote_ops_str) {
+        Ok(ops) => ops,
+        Err(e) => {
+            return to_c_string(FfiResult::<()>::err(format!("parse error: {}", e)).to_json())
+        }
+    };
+
+    let preview = store.preview_reconcile(remote_ops, merge_strategy(strategy));
+    to_c_string(FfiResult::ok(preview).to_json())
+}
+
+/// Map an FFI strategy code to a merge strategy.
+fn merge_strategy(strategy: i32) -> MergeStrategy {
+    match strategy {
+        1 => MergeStrategy::TimestampWins,
+        2 => MergeStrategy::FieldLevel,
+        3 => MergeStrategy::DeleteWins,
+        4 => MergeStrategy::UpdateWins,
+        5 => MergeStrategy::KeepBoth,
+        _ => MergeStrategy::ClockWins,
+    }
+}
+
 // ============================================================================
 // Snapshots
 // ============================================================================
@@ -574,6 +1153,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ffi_store_apply_batch() {
+        unsafe {
+            let schema = test_schema_json();
+            let node_id = test_node_id();
+            let store = carry_store_new(schema.as_ptr(), node_id.as_ptr());
+
+            // The second create is invalid, so neither is applied
+            let ops = CString::new(
+                r#"[
+                    {
+                        "type": "create",
+                        "opId": "op-1",
+                        "id": "user-1",
+                        "collection": "users",
+                        "payload": {"name": "Alice"},
+                        "timestamp": 1000,
+                        "clock": {"nodeId": "test-node", "counter": 1}
+                    },
+                    {
+                        "type": "create",
+                        "opId": "op-2",
+                        "id": "user-2",
+                        "collection": "users",
+                        "payload": {},
+                        "timestamp": 1000,
+                        "clock": {"nodeId": "test-node", "counter": 2}
+                    }
+                ]"#,
+            )
+            .unwrap();
+
+            let result = carry_store_apply_batch(store, ops.as_ptr(), 1000);
+            let result_json = CStr::from_ptr(result).to_str().unwrap();
+            assert!(result_json.contains("\"error\""));
+            carry_string_free(result);
+
+            let collection = CString::new("users").unwrap();
+            let id = CString::new("user-1").unwrap();
+            let get_result = carry_store_get(store, collection.as_ptr(), id.as_ptr());
+            let get_json = CStr::from_ptr(get_result).to_str().unwrap();
+            assert!(!get_json.contains("Alice"));
+            carry_string_free(get_result);
+
+            carry_store_free(store);
+        }
+    }
+
     #[test]
     fn ffi_store_query() {
         unsafe {
@@ -608,6 +1235,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ffi_store_get_and_query_at() {
+        unsafe {
+            let schema = CString::new(
+                r#"{
+                    "version": 1,
+                    "collections": {
+                        "sessions": {"name": "sessions", "fields": [], "ttl": 10000}
+                    }
+                }"#,
+            )
+            .unwrap();
+            let node_id = test_node_id();
+            let store = carry_store_new(schema.as_ptr(), node_id.as_ptr());
+
+            let op = CString::new(
+                r#"{
+                    "type": "create",
+                    "opId": "op-1",
+                    "id": "s1",
+                    "collection": "sessions",
+                    "payload": {"user": "alice"},
+                    "timestamp": 1000,
+                    "clock": {"nodeId": "test-node", "counter": 1}
+                }"#,
+            )
+            .unwrap();
+            let result = carry_store_apply(store, op.as_ptr(), 1000);
+            carry_string_free(result);
+
+            let collection = CString::new("sessions").unwrap();
+            let id = CString::new("s1").unwrap();
+            for (now, visible) in [(10_999, true), (11_000, false)] {
+                let get_result = carry_store_get_at(store, collection.as_ptr(), id.as_ptr(), now);
+                let get_json = CStr::from_ptr(get_result).to_str().unwrap();
+                assert_eq!(get_json.contains("alice"), visible);
+                carry_string_free(get_result);
+
+                let query_result = carry_store_query_at(store, collection.as_ptr(), 0, now);
+                let query_json = CStr::from_ptr(query_result).to_str().unwrap();
+                assert_eq!(query_json.contains("alice"), visible);
+                carry_string_free(query_result);
+            }
+
+            carry_store_free(store);
+        }
+    }
+
     #[test]
     fn ffi_store_pending() {
         unsafe {
@@ -648,6 +1323,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ffi_store_compact_pending() {
+        unsafe {
+            let schema = test_schema_json();
+            let node_id = test_node_id();
+            let store = carry_store_new(schema.as_ptr(), node_id.as_ptr());
+
+            // Create and delete the same record
+            let create = CString::new(
+                r#"{
+                    "type": "create",
+                    "opId": "op-1",
+                    "id": "user-1",
+                    "collection": "users",
+                    "payload": {"name": "Alice"},
+                    "timestamp": 1000,
+                    "clock": {"nodeId": "test-node", "counter": 1}
+                }"#,
+            )
+            .unwrap();
+            let delete = CString::new(
+                r#"{
+                    "type": "delete",
+                    "opId": "op-2",
+                    "id": "user-1",
+                    "collection": "users",
+                    "baseVersion": 1,
+                    "timestamp": 1000,
+                    "clock": {"nodeId": "test-node", "counter": 2}
+                }"#,
+            )
+            .unwrap();
+            carry_string_free(carry_store_apply(store, create.as_ptr(), 1000));
+            carry_string_free(carry_store_apply(store, delete.as_ptr(), 1000));
+            assert_eq!(carry_store_pending_count(store), 2);
+
+            // Both are dropped
+            let result = carry_store_compact_pending(store);
+            let result_json = CStr::from_ptr(result).to_str().unwrap();
+            assert_eq!(result_json, r#"{"ok":["op-1","op-2"]}"#);
+            carry_string_free(result);
+
+            assert_eq!(carry_store_pending_count(store), 0);
+
+            carry_store_free(store);
+        }
+    }
+
+    #[test]
+    fn ffi_store_preview_reconcile() {
+        unsafe {
+            let schema = test_schema_json();
+            let node_id = test_node_id();
+            let store = carry_store_new(schema.as_ptr(), node_id.as_ptr());
+
+            let remote_ops = CString::new(
+                r#"[{
+                    "type": "create",
+                    "opId": "op-r1",
+                    "id": "user-1",
+                    "collection": "users",
+                    "payload": {"name": "Alice"},
+                    "timestamp": 1000,
+                    "clock": {"nodeId": "remote", "counter": 1}
+                }]"#,
+            )
+            .unwrap();
+            let result = carry_store_preview_reconcile(store, remote_ops.as_ptr(), 0);
+            let result_json = CStr::from_ptr(result).to_str().unwrap();
+            assert!(result_json.contains("\"ok\""));
+            assert!(result_json.contains("\"records\""));
+            assert!(result_json.contains("Alice"));
+            carry_string_free(result);
+
+            // The record was not stored
+            let collection = CString::new("users").unwrap();
+            let id = CString::new("user-1").unwrap();
+            let get_result = carry_store_get(store, collection.as_ptr(), id.as_ptr());
+            let get_json = CStr::from_ptr(get_result).to_str().unwrap();
+            assert!(!get_json.contains("Alice"));
+            carry_string_free(get_result);
+
+            carry_store_free(store);
+        }
+    }
+
     #[test]
     fn ffi_store_export_import() {
         unsafe {
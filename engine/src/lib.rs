@@ -25,17 +25,47 @@
 //! - Metadata (timestamps, origin, logical clock)
 //! - Soft delete flag (tombstone)
 //!
+//! Collections that set a history limit keep each record's recent versions
+//! as [`RecordVersion`]s; see [`Store::record_at`] and [`Store::revert_to`].
+//!
+//! Records expire after their collection's TTL or at their own expiry time.
+//! [`Store::get_at`] and [`QueryBuilder::at`] hide expired records, and
+//! [`Store::expire`] deletes them with operations every node derives alike.
+//!
 //! ### Operations
 //!
 //! Changes are expressed as operations, not direct mutations:
 //! - [`CreateOp`] - Create a new record
 //! - [`UpdateOp`] - Update an existing record (version checked)
+//! - [`PatchOp`] - Partially update a record with a [`Patch`] document
 //! - [`DeleteOp`] - Soft-delete a record (tombstone)
+//! - [`RestoreOp`] - Revive a deleted record (version checked)
+//! - [`UpsertOp`] - Create a record or replace its payload, whatever its state
+//! - [`IncrementOp`] - Add to a numeric field; concurrent increments all apply
+//! - [`ListOp`] - Insert, remove or move an element of a `List` field
+//!   ([`ListCrdt`]); concurrent edits all apply
+//! - [`TextOp`] - Splice a `Text` field ([`TextCrdt`]); concurrent edits all
+//!   apply
+//! - [`SetOp`] - Add to or remove from a `Set` field ([`SetCrdt`]); adds win
+//!   over concurrent removes
+//!
+//! [`Store::apply_batch`] applies several operations all-or-nothing; they
+//! share a transaction ID and win or lose together during reconciliation.
+//! [`Store::compact_pending`] folds runs of pending edits to one record into
+//! a single operation before they are pushed.
+//! [`Store::undo`] and [`Store::redo`] revert local changes with new
+//! operations, so they work after the originals have synced.
 //!
 //! ### Logical Clock
 //!
 //! The [`LogicalClock`] provides causal ordering across distributed nodes.
 //! It combines a counter with a node ID for total ordering.
+//! A [`HybridClock`] issues logical clocks that also track physical time.
+//! [`Store::set_max_drift`] makes the store reject operations whose clock runs
+//! too far ahead of wall time.
+//!
+//! A [`VersionVector`] records which writes a record or operation has seen,
+//! so writes that causally follow each other are not treated as conflicts.
 //!
 //! ### Reconciliation
 //!
@@ -43,6 +73,18 @@
 //! Conflicts are resolved using configurable strategies:
 //! - [`MergeStrategy::ClockWins`] - Higher logical clock wins (default)
 //! - [`MergeStrategy::TimestampWins`] - Higher timestamp wins
+//! - [`MergeStrategy::DeleteWins`] - Deletes beat concurrent writes
+//! - [`MergeStrategy::UpdateWins`] - Writes beat concurrent deletes
+//! - [`MergeStrategy::FieldLevel`] - Latest writer wins per top-level field
+//! - [`MergeStrategy::KeepBoth`] - Both versions are kept as [`Sibling`]s
+//!   until [`Store::resolve_conflict`] picks one
+//!
+//! A [`CollectionSchema`] can declare its own strategy, overriding the one
+//! passed to the reconciler, and a [`DeletePolicy`] for deletes racing
+//! writes. Each [`Conflict`] reports the [`ConflictRule`] that decided it.
+//!
+//! A [`ConflictResolver`] registered for a collection overrides the strategy
+//! with application-specific logic.
 //!
 //! ## Quick Start
 //!
@@ -93,26 +135,42 @@
 //! for persistence. Snapshots are serializable to JSON with deterministic ordering.
 
 pub mod clock;
+pub mod crdt;
 pub mod error;
 pub mod ffi;
 pub mod operation;
+pub mod patch;
 pub mod reconcile;
 pub mod record;
+pub mod resolver;
 pub mod schema;
 pub mod snapshot;
 pub mod store;
+pub mod undo;
+pub mod version_vector;
 
 // Re-export main types at crate root
-pub use clock::LogicalClock;
+pub use clock::{HybridClock, LogicalClock};
+pub use crdt::{ElementId, FieldCrdt, ListCrdt, SetCrdt, TextCrdt, TextSplice};
 pub use error::Error;
-pub use operation::{CreateOp, DeleteOp, Operation, OperationId, UpdateOp};
+pub use operation::{
+    CreateOp, DeleteOp, IncrementOp, ListAction, ListOp, Operation, OperationId, PatchOp,
+    RestoreOp, SetAction, SetOp, TextOp, TransactionId, UpdateOp, UpsertOp,
+};
+pub use patch::{JsonPatchOperation, Patch};
 pub use reconcile::{
-    Conflict, ConflictResolution, MergeStrategy, OpSource, ReconcileResult, Reconciler,
+    CollectionChanges, Conflict, ConflictResolution, ConflictRule, DeletePolicy, MergeStrategy,
+    OpSource, ReconcileResult, Reconciler, RecordChange,
 };
-pub use record::{Metadata, Origin, Record};
+pub use record::{HistoryPoint, LastOp, Metadata, Origin, Record, RecordVersion, Sibling};
+pub use resolver::{ConflictResolver, Resolution, ResolverRegistry};
 pub use schema::{CollectionSchema, FieldDef, FieldType, Schema};
 pub use snapshot::{SnapshotMetadata, StoreSnapshot, SNAPSHOT_FORMAT_VERSION};
-pub use store::{ApplyResult, Collection, PendingOp, QueryBuilder, Store};
+pub use store::{
+    ApplyResult, Collection, PendingOp, QueryBuilder, ReconcilePreview, RecordDiff, Store,
+};
+pub use undo::{UndoChange, UndoManager, UndoStep, DEFAULT_UNDO_LIMIT};
+pub use version_vector::VersionVector;
 
 /// Type aliases for clarity
 pub type RecordId = String;
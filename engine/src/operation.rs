@@ -3,12 +3,19 @@
 //! Changes are expressed as operations, not direct mutations.
 //! This enables offline-first behavior with operation logging and reconciliation.
 
-use crate::{CollectionName, LogicalClock, RecordId, Timestamp, Version};
+use crate::{
+    CollectionName, ElementId, LogicalClock, Patch, RecordId, TextSplice, Timestamp, Version,
+    VersionVector,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// Unique identifier for an operation.
 pub type OperationId = String;
 
+/// Identifier shared by operations applied as one atomic unit.
+pub type TransactionId = String;
+
 /// A create operation.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -25,6 +32,15 @@ pub struct CreateOp {
     pub timestamp: Timestamp,
     /// Logical clock at operation time
     pub clock: LogicalClock,
+    /// When the record expires (milliseconds since epoch), overriding the
+    /// collection's TTL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
 }
 
 /// An update operation.
@@ -45,6 +61,57 @@ pub struct UpdateOp {
     pub timestamp: Timestamp,
     /// Logical clock at operation time
     pub clock: LogicalClock,
+    /// Top-level fields this update changed relative to its base version.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` means the
+    /// update claims every field it differs on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_fields: Option<BTreeSet<String>>,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// A patch operation.
+///
+/// Unlike [`UpdateOp`], only the parts of the payload named by the patch
+/// document are changed, so concurrent edits to different fields compose.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to patch
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// Patch document applied to the current payload
+    pub patch: Patch,
+    /// Version this patch is based on
+    pub base_version: Version,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
 }
 
 /// A delete operation.
@@ -63,6 +130,269 @@ pub struct DeleteOp {
     pub timestamp: Timestamp,
     /// Logical clock at operation time
     pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// A restore operation.
+///
+/// Revives a deleted record (tombstone) with a new payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to restore
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// Payload of the restored record
+    pub payload: serde_json::Value,
+    /// Version of the tombstone this restore is based on
+    pub base_version: Version,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// An upsert operation.
+///
+/// Creates the record if it is missing and replaces its payload otherwise,
+/// reviving it if it was deleted. No base version is checked.
+///
+/// During reconciliation, concurrent upserts conflict like updates and the
+/// merge strategy picks the winner; an upsert racing a delete counts as a
+/// write, so [`MergeStrategy::DeleteWins`](crate::MergeStrategy::DeleteWins)
+/// keeps the tombstone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to create or update
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// New payload (full replacement)
+    pub payload: serde_json::Value,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// When the record expires (milliseconds since epoch), overriding the
+    /// collection's TTL. Replaces any expiry the record had.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// An increment operation.
+///
+/// Adds `delta` (which may be negative) to an `Int` or `Float` field. A
+/// missing or null field counts as zero. Increments commute, so concurrent
+/// increments from different nodes all apply during reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to increment
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// Top-level numeric field to increment
+    pub field: String,
+    /// Amount to add
+    pub delta: serde_json::Number,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// An edit to a `List` field.
+///
+/// See [`ListCrdt`](crate::ListCrdt) for how concurrent edits merge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ListAction {
+    /// Insert a value after a slot (`None` for the head of the list).
+    /// The new element's ID is the operation ID.
+    Insert {
+        after: Option<ElementId>,
+        value: serde_json::Value,
+    },
+    /// Remove an element
+    Remove { element: ElementId },
+    /// Move an element after a slot (`None` for the head of the list)
+    Move {
+        element: ElementId,
+        after: Option<ElementId>,
+    },
+}
+
+/// A list operation.
+///
+/// List operations commute, so concurrent edits from different nodes all
+/// apply during reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to edit
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// Top-level `List` field to edit
+    pub field: String,
+    /// Edit to apply
+    pub action: ListAction,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// A text operation: a splice of a `Text` field.
+///
+/// Text operations commute, so concurrent edits from different nodes all
+/// apply during reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to edit
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// Top-level `Text` field to edit
+    pub field: String,
+    /// Splice to apply
+    pub splice: TextSplice,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
+}
+
+/// An edit to a `Set` field.
+///
+/// See [`SetCrdt`](crate::SetCrdt) for how concurrent edits merge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SetAction {
+    /// Add a value, tagged with the operation ID
+    Add { value: serde_json::Value },
+    /// Remove a value.
+    ///
+    /// Only the `observed` add tags are removed, so concurrent adds win.
+    /// [`Store::apply`](crate::Store::apply) fills in the tags the local
+    /// record holds when the list is empty.
+    Remove {
+        value: serde_json::Value,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        observed: Vec<ElementId>,
+    },
+}
+
+/// A set operation.
+///
+/// Set operations commute, so concurrent edits from different nodes all
+/// apply during reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetOp {
+    /// Operation ID
+    pub op_id: OperationId,
+    /// Record ID to edit
+    pub id: RecordId,
+    /// Target collection
+    pub collection: CollectionName,
+    /// Top-level `Set` field to edit
+    pub field: String,
+    /// Edit to apply
+    pub action: SetAction,
+    /// Timestamp of operation
+    pub timestamp: Timestamp,
+    /// Logical clock at operation time
+    pub clock: LogicalClock,
+    /// Version vector of the record this operation was made against.
+    ///
+    /// Stamped by [`Store::apply`](crate::Store::apply); `None` for
+    /// operations from peers that do not track causality.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VersionVector>,
+    /// Transaction this operation belongs to.
+    ///
+    /// Operations sharing a transaction are accepted or rejected together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<TransactionId>,
 }
 
 /// An operation that can be applied to the store.
@@ -71,7 +401,14 @@ pub struct DeleteOp {
 pub enum Operation {
     Create(CreateOp),
     Update(UpdateOp),
+    Patch(PatchOp),
     Delete(DeleteOp),
+    Restore(RestoreOp),
+    Upsert(UpsertOp),
+    Increment(IncrementOp),
+    List(ListOp),
+    Text(TextOp),
+    Set(SetOp),
 }
 
 impl Operation {
@@ -80,7 +417,14 @@ impl Operation {
         match self {
             Operation::Create(op) => &op.op_id,
             Operation::Update(op) => &op.op_id,
+            Operation::Patch(op) => &op.op_id,
             Operation::Delete(op) => &op.op_id,
+            Operation::Restore(op) => &op.op_id,
+            Operation::Upsert(op) => &op.op_id,
+            Operation::Increment(op) => &op.op_id,
+            Operation::List(op) => &op.op_id,
+            Operation::Text(op) => &op.op_id,
+            Operation::Set(op) => &op.op_id,
         }
     }
 
@@ -89,7 +433,14 @@ impl Operation {
         match self {
             Operation::Create(op) => &op.id,
             Operation::Update(op) => &op.id,
+            Operation::Patch(op) => &op.id,
             Operation::Delete(op) => &op.id,
+            Operation::Restore(op) => &op.id,
+            Operation::Upsert(op) => &op.id,
+            Operation::Increment(op) => &op.id,
+            Operation::List(op) => &op.id,
+            Operation::Text(op) => &op.id,
+            Operation::Set(op) => &op.id,
         }
     }
 
@@ -98,7 +449,14 @@ impl Operation {
         match self {
             Operation::Create(op) => &op.collection,
             Operation::Update(op) => &op.collection,
+            Operation::Patch(op) => &op.collection,
             Operation::Delete(op) => &op.collection,
+            Operation::Restore(op) => &op.collection,
+            Operation::Upsert(op) => &op.collection,
+            Operation::Increment(op) => &op.collection,
+            Operation::List(op) => &op.collection,
+            Operation::Text(op) => &op.collection,
+            Operation::Set(op) => &op.collection,
         }
     }
 
@@ -107,7 +465,14 @@ impl Operation {
         match self {
             Operation::Create(op) => &op.clock,
             Operation::Update(op) => &op.clock,
+            Operation::Patch(op) => &op.clock,
             Operation::Delete(op) => &op.clock,
+            Operation::Restore(op) => &op.clock,
+            Operation::Upsert(op) => &op.clock,
+            Operation::Increment(op) => &op.clock,
+            Operation::List(op) => &op.clock,
+            Operation::Text(op) => &op.clock,
+            Operation::Set(op) => &op.clock,
         }
     }
 
@@ -116,9 +481,117 @@ impl Operation {
         match self {
             Operation::Create(op) => op.timestamp,
             Operation::Update(op) => op.timestamp,
+            Operation::Patch(op) => op.timestamp,
             Operation::Delete(op) => op.timestamp,
+            Operation::Restore(op) => op.timestamp,
+            Operation::Upsert(op) => op.timestamp,
+            Operation::Increment(op) => op.timestamp,
+            Operation::List(op) => op.timestamp,
+            Operation::Text(op) => op.timestamp,
+            Operation::Set(op) => op.timestamp,
         }
     }
+
+    /// Get the causal context of this operation, if any.
+    ///
+    /// Creates carry no context: they start a record's history.
+    pub fn context(&self) -> Option<&VersionVector> {
+        match self {
+            Operation::Create(_) => None,
+            Operation::Update(op) => op.context.as_ref(),
+            Operation::Patch(op) => op.context.as_ref(),
+            Operation::Delete(op) => op.context.as_ref(),
+            Operation::Restore(op) => op.context.as_ref(),
+            Operation::Upsert(op) => op.context.as_ref(),
+            Operation::Increment(op) => op.context.as_ref(),
+            Operation::List(op) => op.context.as_ref(),
+            Operation::Text(op) => op.context.as_ref(),
+            Operation::Set(op) => op.context.as_ref(),
+        }
+    }
+
+    /// Whether this operation commutes with every other write.
+    ///
+    /// Increments and CRDT field operations never conflict, and do not
+    /// replace a record's last operation.
+    pub fn is_commutative(&self) -> bool {
+        matches!(
+            self,
+            Operation::Increment(_) | Operation::List(_) | Operation::Text(_) | Operation::Set(_)
+        )
+    }
+
+    /// Get the transaction this operation belongs to, if any.
+    pub fn transaction_id(&self) -> Option<&TransactionId> {
+        match self {
+            Operation::Create(op) => op.transaction_id.as_ref(),
+            Operation::Update(op) => op.transaction_id.as_ref(),
+            Operation::Patch(op) => op.transaction_id.as_ref(),
+            Operation::Delete(op) => op.transaction_id.as_ref(),
+            Operation::Restore(op) => op.transaction_id.as_ref(),
+            Operation::Upsert(op) => op.transaction_id.as_ref(),
+            Operation::Increment(op) => op.transaction_id.as_ref(),
+            Operation::List(op) => op.transaction_id.as_ref(),
+            Operation::Text(op) => op.transaction_id.as_ref(),
+            Operation::Set(op) => op.transaction_id.as_ref(),
+        }
+    }
+
+    /// Assign this operation to a transaction.
+    pub fn with_transaction_id(mut self, transaction_id: impl Into<TransactionId>) -> Self {
+        let transaction_id = Some(transaction_id.into());
+        match &mut self {
+            Operation::Create(op) => op.transaction_id = transaction_id,
+            Operation::Update(op) => op.transaction_id = transaction_id,
+            Operation::Patch(op) => op.transaction_id = transaction_id,
+            Operation::Delete(op) => op.transaction_id = transaction_id,
+            Operation::Restore(op) => op.transaction_id = transaction_id,
+            Operation::Upsert(op) => op.transaction_id = transaction_id,
+            Operation::Increment(op) => op.transaction_id = transaction_id,
+            Operation::List(op) => op.transaction_id = transaction_id,
+            Operation::Text(op) => op.transaction_id = transaction_id,
+            Operation::Set(op) => op.transaction_id = transaction_id,
+        }
+        self
+    }
+
+    /// Set the record version this operation was made against.
+    ///
+    /// Has no effect on operations without a base version.
+    pub fn with_base_version(mut self, base_version: Version) -> Self {
+        match &mut self {
+            Operation::Update(op) => op.base_version = base_version,
+            Operation::Patch(op) => op.base_version = base_version,
+            Operation::Delete(op) => op.base_version = base_version,
+            Operation::Restore(op) => op.base_version = base_version,
+            Operation::Create(_)
+            | Operation::Upsert(_)
+            | Operation::Increment(_)
+            | Operation::List(_)
+            | Operation::Text(_)
+            | Operation::Set(_) => {}
+        }
+        self
+    }
+
+    /// Set the causal context of this operation.
+    ///
+    /// Has no effect on creates.
+    pub fn with_context(mut self, context: VersionVector) -> Self {
+        match &mut self {
+            Operation::Create(_) => {}
+            Operation::Update(op) => op.context = Some(context),
+            Operation::Patch(op) => op.context = Some(context),
+            Operation::Delete(op) => op.context = Some(context),
+            Operation::Restore(op) => op.context = Some(context),
+            Operation::Upsert(op) => op.context = Some(context),
+            Operation::Increment(op) => op.context = Some(context),
+            Operation::List(op) => op.context = Some(context),
+            Operation::Text(op) => op.context = Some(context),
+            Operation::Set(op) => op.context = Some(context),
+        }
+        self
+    }
 }
 
 impl CreateOp {
@@ -138,8 +611,16 @@ impl CreateOp {
             payload,
             timestamp,
             clock,
+            expires_at: None,
+            transaction_id: None,
         }
     }
+
+    /// Set when the created record expires.
+    pub fn with_expires_at(mut self, expires_at: Timestamp) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
 }
 
 impl UpdateOp {
@@ -161,6 +642,40 @@ impl UpdateOp {
             base_version,
             timestamp,
             clock,
+            changed_fields: None,
+            context: None,
+            transaction_id: None,
+        }
+    }
+
+    /// Declare the top-level fields this update changed.
+    pub fn with_changed_fields(mut self, fields: impl IntoIterator<Item = String>) -> Self {
+        self.changed_fields = Some(fields.into_iter().collect());
+        self
+    }
+}
+
+impl PatchOp {
+    /// Create a new patch operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        patch: Patch,
+        base_version: Version,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            patch,
+            base_version,
+            timestamp,
+            clock,
+            context: None,
+            transaction_id: None,
         }
     }
 }
@@ -182,6 +697,163 @@ impl DeleteOp {
             base_version,
             timestamp,
             clock,
+            context: None,
+            transaction_id: None,
+        }
+    }
+}
+
+impl RestoreOp {
+    /// Create a new restore operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        payload: serde_json::Value,
+        base_version: Version,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            payload,
+            base_version,
+            timestamp,
+            clock,
+            context: None,
+            transaction_id: None,
+        }
+    }
+}
+
+impl UpsertOp {
+    /// Create a new upsert operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        payload: serde_json::Value,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            payload,
+            timestamp,
+            clock,
+            expires_at: None,
+            context: None,
+            transaction_id: None,
+        }
+    }
+
+    /// Set when the record expires.
+    pub fn with_expires_at(mut self, expires_at: Timestamp) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+}
+
+impl IncrementOp {
+    /// Create a new increment operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        field: impl Into<String>,
+        delta: impl Into<serde_json::Number>,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            field: field.into(),
+            delta: delta.into(),
+            timestamp,
+            clock,
+            context: None,
+            transaction_id: None,
+        }
+    }
+}
+
+impl ListOp {
+    /// Create a new list operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        field: impl Into<String>,
+        action: ListAction,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            field: field.into(),
+            action,
+            timestamp,
+            clock,
+            context: None,
+            transaction_id: None,
+        }
+    }
+}
+
+impl TextOp {
+    /// Create a new text operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        field: impl Into<String>,
+        splice: TextSplice,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            field: field.into(),
+            splice,
+            timestamp,
+            clock,
+            context: None,
+            transaction_id: None,
+        }
+    }
+}
+
+impl SetOp {
+    /// Create a new set operation.
+    pub fn new(
+        op_id: impl Into<OperationId>,
+        id: impl Into<RecordId>,
+        collection: impl Into<CollectionName>,
+        field: impl Into<String>,
+        action: SetAction,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+    ) -> Self {
+        Self {
+            op_id: op_id.into(),
+            id: id.into(),
+            collection: collection.into(),
+            field: field.into(),
+            action,
+            timestamp,
+            clock,
+            context: None,
+            transaction_id: None,
         }
     }
 }
@@ -334,6 +1006,28 @@ mod tests {
 
         let json = serde_json::to_string(&op).unwrap();
         assert!(json.contains("\"type\":\"update\""));
+        assert!(!json.contains("changedFields"));
+
+        let parsed: Operation = serde_json::from_str(&json).unwrap();
+        assert_eq!(op, parsed);
+    }
+
+    #[test]
+    fn serialization_patch() {
+        let clock = LogicalClock::with_counter("node-1", 2);
+        let op = Operation::Patch(PatchOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            Patch::MergePatch(json!({"name": "Bob"})),
+            1,
+            2000,
+            clock,
+        ));
+
+        let json = serde_json::to_string(&op).unwrap();
+        assert!(json.contains("\"type\":\"patch\""));
+        assert!(json.contains("\"format\":\"mergePatch\""));
 
         let parsed: Operation = serde_json::from_str(&json).unwrap();
         assert_eq!(op, parsed);
@@ -0,0 +1,401 @@
+//! Patch documents for field-level changes.
+//!
+//! A patch describes a partial change to a record payload, as opposed to the
+//! full replacement carried by an [`UpdateOp`](crate::UpdateOp). Two standard
+//! formats are supported:
+//!
+//! - [RFC 7396](https://www.rfc-editor.org/rfc/rfc7396) JSON Merge Patch
+//! - [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902) JSON Patch
+
+use crate::{error::Result, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A patch document applied to a record payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "format", content = "document", rename_all = "camelCase")]
+pub enum Patch {
+    /// RFC 7396 merge patch: an object whose keys replace (or, when `null`,
+    /// remove) the corresponding keys of the target
+    MergePatch(Value),
+    /// RFC 6902 JSON Patch: an ordered list of pointer-addressed operations
+    JsonPatch(Vec<JsonPatchOperation>),
+}
+
+/// A single RFC 6902 JSON Patch operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+impl Patch {
+    /// Check that the patch document is well-formed without applying it.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Patch::MergePatch(_) => Ok(()),
+            Patch::JsonPatch(ops) => {
+                for op in ops {
+                    match op {
+                        JsonPatchOperation::Add { path, .. }
+                        | JsonPatchOperation::Remove { path }
+                        | JsonPatchOperation::Replace { path, .. }
+                        | JsonPatchOperation::Test { path, .. } => {
+                            parse_pointer(path)?;
+                        }
+                        JsonPatchOperation::Move { from, path }
+                        | JsonPatchOperation::Copy { from, path } => {
+                            parse_pointer(from)?;
+                            parse_pointer(path)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply the patch to a target value, returning the patched value.
+    ///
+    /// The target is left untouched; JSON Patch documents are applied
+    /// atomically, so a failing operation discards all earlier ones.
+    pub fn apply(&self, target: &Value) -> Result<Value> {
+        match self {
+            Patch::MergePatch(patch) => {
+                let mut result = target.clone();
+                merge_patch(&mut result, patch);
+                Ok(result)
+            }
+            Patch::JsonPatch(ops) => {
+                let mut result = target.clone();
+                for op in ops {
+                    apply_json_patch_op(&mut result, op)?;
+                }
+                Ok(result)
+            }
+        }
+    }
+}
+
+/// RFC 7396 section 2 `MergePatch(Target, Patch)`.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_obj) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+
+    if let Value::Object(target_obj) = target {
+        for (key, value) in patch_obj {
+            if value.is_null() {
+                target_obj.remove(key);
+            } else {
+                merge_patch(target_obj.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+fn apply_json_patch_op(target: &mut Value, op: &JsonPatchOperation) -> Result<()> {
+    match op {
+        JsonPatchOperation::Add { path, value } => add(target, path, value.clone()),
+        JsonPatchOperation::Remove { path } => remove(target, path).map(|_| ()),
+        JsonPatchOperation::Replace { path, value } => {
+            let slot = resolve_mut(target, &parse_pointer(path)?)
+                .ok_or_else(|| invalid(format!("path not found: {}", path)))?;
+            *slot = value.clone();
+            Ok(())
+        }
+        JsonPatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(invalid(format!(
+                    "cannot move '{}' into its own child '{}'",
+                    from, path
+                )));
+            }
+            let value = remove(target, from)?;
+            add(target, path, value)
+        }
+        JsonPatchOperation::Copy { from, path } => {
+            let value = resolve_mut(target, &parse_pointer(from)?)
+                .ok_or_else(|| invalid(format!("path not found: {}", from)))?
+                .clone();
+            add(target, path, value)
+        }
+        JsonPatchOperation::Test { path, value } => {
+            let actual = resolve_mut(target, &parse_pointer(path)?)
+                .ok_or_else(|| invalid(format!("path not found: {}", path)))?;
+            if actual == value {
+                Ok(())
+            } else {
+                Err(invalid(format!("test failed at '{}'", path)))
+            }
+        }
+    }
+}
+
+fn add(target: &mut Value, path: &str, value: Value) -> Result<()> {
+    let tokens = parse_pointer(path)?;
+    let Some((last, parent_tokens)) = tokens.split_last() else {
+        *target = value;
+        return Ok(());
+    };
+
+    let parent = resolve_mut(target, parent_tokens)
+        .ok_or_else(|| invalid(format!("parent not found: {}", path)))?;
+
+    match parent {
+        Value::Object(obj) => {
+            obj.insert(last.clone(), value);
+            Ok(())
+        }
+        Value::Array(arr) => {
+            if last == "-" {
+                arr.push(value);
+                return Ok(());
+            }
+            let index = parse_index(last, arr.len() + 1, path)?;
+            arr.insert(index, value);
+            Ok(())
+        }
+        _ => Err(invalid(format!("parent is not a container: {}", path))),
+    }
+}
+
+fn remove(target: &mut Value, path: &str) -> Result<Value> {
+    let tokens = parse_pointer(path)?;
+    let Some((last, parent_tokens)) = tokens.split_last() else {
+        return Err(invalid("cannot remove the document root"));
+    };
+
+    let parent = resolve_mut(target, parent_tokens)
+        .ok_or_else(|| invalid(format!("parent not found: {}", path)))?;
+
+    match parent {
+        Value::Object(obj) => obj
+            .remove(last)
+            .ok_or_else(|| invalid(format!("path not found: {}", path))),
+        Value::Array(arr) => {
+            let index = parse_index(last, arr.len(), path)?;
+            Ok(arr.remove(index))
+        }
+        _ => Err(invalid(format!("parent is not a container: {}", path))),
+    }
+}
+
+fn resolve_mut<'v>(target: &'v mut Value, tokens: &[String]) -> Option<&'v mut Value> {
+    let mut current = target;
+    for token in tokens {
+        current = match current {
+            Value::Object(obj) => obj.get_mut(token)?,
+            Value::Array(arr) => {
+                let index = parse_index(token, arr.len(), "").ok()?;
+                arr.get_mut(index)?
+            }
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// Parse an array index token, which must be strictly less than `bound`.
+fn parse_index(token: &str, bound: usize, path: &str) -> Result<usize> {
+    let valid_format = token == "0" || (!token.starts_with('0') && !token.starts_with('+'));
+    match token.parse::<usize>() {
+        Ok(index) if valid_format && index < bound => Ok(index),
+        _ => Err(invalid(format!(
+            "invalid array index '{}' in {}",
+            token, path
+        ))),
+    }
+}
+
+/// Parse an RFC 6901 JSON Pointer into its unescaped reference tokens.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(invalid(format!("invalid JSON pointer: {}", pointer)));
+    }
+    Ok(pointer[1..]
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidPatch(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_rfc_example() {
+        let target = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let patch = Patch::MergePatch(json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": {"familyName": null},
+            "tags": ["example"]
+        }));
+
+        let result = patch.apply(&target).unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "title": "Hello!",
+                "author": {"givenName": "John"},
+                "tags": ["example"],
+                "content": "This will be unchanged",
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+    }
+
+    #[test]
+    fn merge_patch_leaves_untouched_fields() {
+        let target = json!({"name": "Alice", "age": 30});
+        let patch = Patch::MergePatch(json!({"age": 31}));
+        assert_eq!(
+            patch.apply(&target).unwrap(),
+            json!({"name": "Alice", "age": 31})
+        );
+    }
+
+    #[test]
+    fn json_patch_operations() {
+        let target = json!({"name": "Alice", "tags": ["a", "b"], "meta": {"x": 1}});
+        let patch = Patch::JsonPatch(vec![
+            JsonPatchOperation::Test {
+                path: "/name".into(),
+                value: json!("Alice"),
+            },
+            JsonPatchOperation::Replace {
+                path: "/name".into(),
+                value: json!("Bob"),
+            },
+            JsonPatchOperation::Add {
+                path: "/tags/1".into(),
+                value: json!("c"),
+            },
+            JsonPatchOperation::Add {
+                path: "/tags/-".into(),
+                value: json!("d"),
+            },
+            JsonPatchOperation::Remove {
+                path: "/tags/0".into(),
+            },
+            JsonPatchOperation::Copy {
+                from: "/meta/x".into(),
+                path: "/meta/y".into(),
+            },
+            JsonPatchOperation::Move {
+                from: "/meta/x".into(),
+                path: "/x".into(),
+            },
+        ]);
+
+        let result = patch.apply(&target).unwrap();
+        assert_eq!(
+            result,
+            json!({"name": "Bob", "tags": ["c", "b", "d"], "meta": {"y": 1}, "x": 1})
+        );
+    }
+
+    #[test]
+    fn json_patch_is_atomic() {
+        let target = json!({"name": "Alice"});
+        let patch = Patch::JsonPatch(vec![
+            JsonPatchOperation::Replace {
+                path: "/name".into(),
+                value: json!("Bob"),
+            },
+            JsonPatchOperation::Test {
+                path: "/name".into(),
+                value: json!("Alice"),
+            },
+        ]);
+
+        assert!(matches!(patch.apply(&target), Err(Error::InvalidPatch(_))));
+    }
+
+    #[test]
+    fn json_patch_errors() {
+        let target = json!({"list": [1, 2]});
+
+        let missing = Patch::JsonPatch(vec![JsonPatchOperation::Remove {
+            path: "/nope".into(),
+        }]);
+        assert!(missing.apply(&target).is_err());
+
+        let out_of_bounds = Patch::JsonPatch(vec![JsonPatchOperation::Add {
+            path: "/list/5".into(),
+            value: json!(3),
+        }]);
+        assert!(out_of_bounds.apply(&target).is_err());
+
+        let leading_zero = Patch::JsonPatch(vec![JsonPatchOperation::Remove {
+            path: "/list/01".into(),
+        }]);
+        assert!(leading_zero.apply(&target).is_err());
+    }
+
+    #[test]
+    fn pointer_escaping() {
+        let target = json!({"a/b": 1, "c~d": 2});
+        let patch = Patch::JsonPatch(vec![
+            JsonPatchOperation::Remove {
+                path: "/a~1b".into(),
+            },
+            JsonPatchOperation::Replace {
+                path: "/c~0d".into(),
+                value: json!(3),
+            },
+        ]);
+        assert_eq!(patch.apply(&target).unwrap(), json!({"c~d": 3}));
+    }
+
+    #[test]
+    fn validate_rejects_bad_pointer() {
+        let patch = Patch::JsonPatch(vec![JsonPatchOperation::Remove {
+            path: "name".into(),
+        }]);
+        assert!(matches!(patch.validate(), Err(Error::InvalidPatch(_))));
+    }
+
+    #[test]
+    fn serialization_format() {
+        let patch = Patch::JsonPatch(vec![JsonPatchOperation::Add {
+            path: "/name".into(),
+            value: json!("Alice"),
+        }]);
+        let json = serde_json::to_value(&patch).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "format": "jsonPatch",
+                "document": [{"op": "add", "path": "/name", "value": "Alice"}]
+            })
+        );
+
+        let parsed: Patch = serde_json::from_value(json).unwrap();
+        assert_eq!(patch, parsed);
+    }
+}
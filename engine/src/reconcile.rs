@@ -11,9 +11,14 @@
 //! 4. Resolve conflicts using merge strategy
 //! 5. Return new state and conflict details
 
-use crate::{record::Origin, CollectionName, Operation, OperationId, Record, RecordId, Schema};
+use crate::{
+    record::{LastOp, Origin, Sibling},
+    CollectionName, ConflictResolver, CreateOp, DeleteOp, Operation, OperationId, Record, RecordId,
+    Resolution, ResolverRegistry, Schema, TransactionId, UpdateOp, Version,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
 
 /// Merge strategy for conflict resolution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -22,8 +27,64 @@ pub enum MergeStrategy {
     /// Higher clock wins, ties broken by node_id (default)
     #[default]
     ClockWins,
-    /// Later timestamp wins (use with caution - clock skew issues)
+    /// Later timestamp wins (use with caution - clock skew issues).
+    /// Clock-wins over [`HybridClock`](crate::HybridClock) values orders by
+    /// time without trusting raw timestamps.
     TimestampWins,
+    /// A delete beats a concurrent write; other conflicts use
+    /// [`MergeStrategy::ClockWins`]
+    DeleteWins,
+    /// A write beats a concurrent delete, reviving the record; other
+    /// conflicts use [`MergeStrategy::ClockWins`]
+    UpdateWins,
+    /// Each top-level payload field goes to its latest writer by clock.
+    /// Deletes fall back to [`MergeStrategy::ClockWins`].
+    FieldLevel,
+    /// Nothing is discarded: both versions are kept on the record as
+    /// [`Sibling`](crate::Sibling)s until the application resolves the
+    /// conflict. Meanwhile the record shows the version
+    /// [`MergeStrategy::UpdateWins`] would pick.
+    KeepBoth,
+}
+
+/// How a delete racing a write to the same record is decided.
+///
+/// Set per collection with
+/// [`CollectionSchema::delete_policy`](crate::CollectionSchema::delete_policy).
+/// Without one, [`MergeStrategy::DeleteWins`] implies
+/// [`DeletePolicy::DeleteWins`], [`MergeStrategy::UpdateWins`] and
+/// [`MergeStrategy::KeepBoth`] imply [`DeletePolicy::UpdateWins`], and other
+/// strategies decide such conflicts like any other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeletePolicy {
+    /// The delete wins, keeping the record deleted
+    DeleteWins,
+    /// The write wins, resurrecting the record
+    UpdateWins,
+    /// Higher clock wins, as for [`MergeStrategy::ClockWins`]
+    ClockWins,
+}
+
+/// The rule that decided a conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictRule {
+    /// Higher clock won
+    #[default]
+    ClockWins,
+    /// Later timestamp won
+    TimestampWins,
+    /// A delete beat a concurrent write
+    DeleteWins,
+    /// A write beat a concurrent delete
+    UpdateWins,
+    /// Fields were merged by their latest writers
+    FieldLevel,
+    /// Both versions were kept as siblings
+    KeepBoth,
+    /// A custom [`ConflictResolver`] decided
+    Resolver,
 }
 
 /// How a conflict was resolved.
@@ -34,6 +95,10 @@ pub enum ConflictResolution {
     LocalWins,
     /// Remote operation won
     RemoteWins,
+    /// Both operations contributed fields to the result
+    Merged,
+    /// Both versions were kept as siblings for manual resolution
+    KeptBoth,
 }
 
 /// A detected conflict between operations.
@@ -46,8 +111,45 @@ pub struct Conflict {
     pub remote_op: Operation,
     /// How the conflict was resolved
     pub resolution: ConflictResolution,
-    /// The winning operation ID
+    /// The winning operation ID (for merged conflicts, the incoming one)
     pub winner_op_id: OperationId,
+    /// Per-field outcomes of a [`MergeStrategy::FieldLevel`] merge
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_outcomes: BTreeMap<String, ConflictResolution>,
+    /// The rule that decided the conflict
+    #[serde(default)]
+    pub rule: ConflictRule,
+}
+
+/// A record changed by reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordChange {
+    /// Record ID
+    pub id: RecordId,
+    /// Version before reconciliation (`None` if the record was new)
+    pub old_version: Option<Version>,
+    /// Version after reconciliation
+    pub new_version: Version,
+}
+
+/// Records of one collection changed by reconciliation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionChanges {
+    /// Records that became visible: new ones and restored tombstones
+    pub inserted: Vec<RecordChange>,
+    /// Visible records whose payload changed
+    pub updated: Vec<RecordChange>,
+    /// Records that were deleted
+    pub deleted: Vec<RecordChange>,
+}
+
+impl CollectionChanges {
+    /// Check if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
 }
 
 /// Result of reconciliation.
@@ -64,6 +166,14 @@ pub struct ReconcileResult {
     pub rejected_remote: Vec<OperationId>,
     /// Detected conflicts with resolution details
     pub conflicts: Vec<Conflict>,
+    /// Accepted local operations dropped from pending because they no
+    /// longer apply to the reconciled state
+    pub invalidated_local: Vec<OperationId>,
+    /// Records changed in the store, by collection.
+    ///
+    /// Filled in by [`Store::reconcile`](crate::Store::reconcile).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub changes: BTreeMap<CollectionName, CollectionChanges>,
     /// Debug: pending ops count before retain (v2 marker)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub debug_pending_before: Option<usize>,
@@ -80,6 +190,8 @@ impl ReconcileResult {
             applied_remote: Vec::new(),
             rejected_remote: Vec::new(),
             conflicts: Vec::new(),
+            invalidated_local: Vec::new(),
+            changes: BTreeMap::new(),
             debug_pending_before: None,
             debug_pending_after: None,
         }
@@ -126,38 +238,158 @@ struct RecordState {
     last_source: OpSource,
 }
 
+/// Outcome of merging an operation into a record field by field.
+struct FieldMerge {
+    /// Payload with the fields the incoming operation won
+    payload: serde_json::Value,
+    /// Fields the operation wrote, and whether it won each of them
+    outcomes: BTreeMap<String, bool>,
+}
+
+fn origin_of(source: OpSource) -> Origin {
+    match source {
+        OpSource::Local => Origin::Local,
+        OpSource::Remote => Origin::Remote,
+    }
+}
+
+/// The operation that wrote a stored record's current state.
+///
+/// Its ID, clock and timestamp come from the record's tracked last
+/// operation; records stored before it was tracked fall back to their last
+/// update with an empty operation ID. The kind of operation follows from the
+/// record's state, so delete-aware strategies still see a tombstone as a
+/// delete.
+fn stored_op(record: &Record) -> Operation {
+    let (op_id, clock, timestamp) = match &record.metadata.last_op {
+        Some(last) => (last.op_id.clone(), last.clock.clone(), last.timestamp),
+        None => (
+            OperationId::new(),
+            record.metadata.clock.clone(),
+            record.metadata.updated_at,
+        ),
+    };
+    let (id, collection) = (record.id.clone(), record.collection.clone());
+    let base_version = record.version.saturating_sub(1);
+
+    if record.deleted {
+        Operation::Delete(DeleteOp::new(
+            op_id,
+            id,
+            collection,
+            base_version,
+            timestamp,
+            clock,
+        ))
+    } else if record.version <= 1 {
+        Operation::Create(CreateOp::new(
+            op_id,
+            id,
+            collection,
+            record.payload.clone(),
+            timestamp,
+            clock,
+        ))
+    } else {
+        Operation::Update(UpdateOp::new(
+            op_id,
+            id,
+            collection,
+            record.payload.clone(),
+            base_version,
+            timestamp,
+            clock,
+        ))
+    }
+}
+
+/// Copy `fields` from `source` onto `target`, removing those absent from it.
+fn overlay_fields(
+    target: &serde_json::Value,
+    source: &serde_json::Value,
+    fields: &BTreeSet<String>,
+) -> serde_json::Value {
+    let (Some(target_obj), Some(source_obj)) = (target.as_object(), source.as_object()) else {
+        return source.clone();
+    };
+
+    let mut result = target_obj.clone();
+    for field in fields {
+        match source_obj.get(field) {
+            Some(value) => result.insert(field.clone(), value.clone()),
+            None => result.remove(field),
+        };
+    }
+    serde_json::Value::Object(result)
+}
+
 /// The reconciler applies operations and resolves conflicts.
 pub struct Reconciler<'a> {
-    #[allow(dead_code)]
     schema: &'a Schema,
     strategy: MergeStrategy,
+    /// Custom resolvers overriding the strategy per collection
+    resolvers: ResolverRegistry,
     /// Current state of records during reconciliation
     records: HashMap<(CollectionName, RecordId), RecordState>,
+    /// Local operations being reconciled, by ID, for resolving the
+    /// operation behind a loaded record
+    local_ops: HashMap<OperationId, Operation>,
     /// Result being built
     result: ReconcileResult,
 }
 
 impl<'a> Reconciler<'a> {
     /// Create a new reconciler.
+    ///
+    /// `strategy` applies to collections whose schema does not declare a
+    /// [`CollectionSchema::merge_strategy`](crate::CollectionSchema::merge_strategy).
     pub fn new(schema: &'a Schema, strategy: MergeStrategy) -> Self {
         Self {
             schema,
             strategy,
+            resolvers: ResolverRegistry::new(),
             records: HashMap::new(),
+            local_ops: HashMap::new(),
             result: ReconcileResult::new(),
         }
     }
 
+    /// Use a custom resolver for conflicts on a collection.
+    pub fn with_resolver(
+        mut self,
+        collection: impl Into<CollectionName>,
+        resolver: Arc<dyn ConflictResolver>,
+    ) -> Self {
+        self.resolvers.register(collection, resolver);
+        self
+    }
+
+    /// Use a set of custom resolvers, replacing any registered before.
+    pub fn with_resolvers(mut self, resolvers: ResolverRegistry) -> Self {
+        self.resolvers = resolvers;
+        self
+    }
+
     /// Load existing records into the reconciler.
-    pub fn load_records(&mut self, records: impl Iterator<Item = (Record, Operation, OpSource)>) {
-        for (record, last_op, source) in records {
+    ///
+    /// Each record's last operation is rebuilt from its
+    /// [`Metadata::last_op`](crate::Metadata::last_op), so conflicts with it
+    /// report the operation that actually wrote it. The record's origin is
+    /// the source of that operation.
+    pub fn load_records(&mut self, records: impl Iterator<Item = Record>) {
+        for record in records {
             let key = (record.collection.clone(), record.id.clone());
+            let last_op = stored_op(&record);
+            let last_source = match record.metadata.origin {
+                Origin::Local => OpSource::Local,
+                Origin::Remote => OpSource::Remote,
+            };
             self.records.insert(
                 key,
                 RecordState {
                     record,
                     last_op,
-                    last_source: source,
+                    last_source,
                 },
             );
         }
@@ -173,6 +405,10 @@ impl<'a> Reconciler<'a> {
     ) -> (ReconcileResult, HashMap<(CollectionName, RecordId), Record>) {
         // Track which local ops we've seen
         let local_op_ids: HashSet<_> = local_ops.iter().map(|op| op.op_id().clone()).collect();
+        self.local_ops = local_ops
+            .iter()
+            .map(|op| (op.op_id().clone(), op.clone()))
+            .collect();
 
         // Combine and sort all operations
         let mut all_ops: Vec<TrackedOp> = Vec::with_capacity(local_ops.len() + remote_ops.len());
@@ -182,32 +418,133 @@ impl<'a> Reconciler<'a> {
         // Sort by (clock, timestamp, op_id) for deterministic total ordering
         all_ops.sort_by(|a, b| a.operation.cmp(&b.operation));
 
-        // Apply operations in order
-        for tracked in all_ops {
-            self.apply_tracked_op(tracked, &local_op_ids);
+        // Transactions win or lose as a unit: whenever one is split, it is
+        // dropped entirely and the remaining operations are replayed from
+        // the initial state until no transaction is split
+        let initial = all_ops
+            .iter()
+            .any(|t| t.operation.transaction_id().is_some())
+            .then(|| self.records.clone());
+        let mut excluded: HashSet<TransactionId> = HashSet::new();
+        loop {
+            for tracked in &all_ops {
+                let in_excluded = tracked
+                    .operation
+                    .transaction_id()
+                    .is_some_and(|id| excluded.contains(id));
+                if !in_excluded {
+                    self.apply_tracked_op(tracked.clone(), &local_op_ids);
+                }
+            }
+
+            let Some(initial) = &initial else { break };
+            let split = self.split_transactions(&all_ops, &excluded);
+            if split.is_empty() {
+                break;
+            }
+            excluded.extend(split);
+            self.records = initial.clone();
+            self.result = ReconcileResult::new();
+        }
+
+        // Every operation of a dropped transaction is rejected
+        for tracked in &all_ops {
+            let op = &tracked.operation;
+            if op.transaction_id().is_some_and(|id| excluded.contains(id)) {
+                match tracked.source {
+                    OpSource::Local => self.result.rejected_local.push(op.op_id().clone()),
+                    OpSource::Remote => self.result.rejected_remote.push(op.op_id().clone()),
+                }
+            }
         }
 
-        // Extract final records
+        // Extract final records, remembering the operation that last wrote
+        // each so later reconciliations can load it again
         let final_records: HashMap<_, _> = self
             .records
             .into_iter()
-            .map(|(k, v)| (k, v.record))
+            .map(|(k, mut v)| {
+                if !v.last_op.op_id().is_empty() {
+                    v.record.metadata.last_op = Some(LastOp::of(&v.last_op));
+                }
+                (k, v.record)
+            })
             .collect();
 
         (self.result, final_records)
     }
 
+    /// Transactions with both accepted and rejected operations.
+    fn split_transactions(
+        &self,
+        ops: &[TrackedOp],
+        excluded: &HashSet<TransactionId>,
+    ) -> HashSet<TransactionId> {
+        let rejected: HashSet<&OperationId> = self
+            .result
+            .rejected_local
+            .iter()
+            .chain(&self.result.rejected_remote)
+            .collect();
+
+        let mut outcomes: HashMap<&TransactionId, (bool, bool)> = HashMap::new();
+        for tracked in ops {
+            let Some(id) = tracked.operation.transaction_id() else {
+                continue;
+            };
+            if excluded.contains(id) {
+                continue;
+            }
+            let entry = outcomes.entry(id).or_default();
+            if rejected.contains(tracked.operation.op_id()) {
+                entry.1 = true;
+            } else {
+                entry.0 = true;
+            }
+        }
+
+        outcomes
+            .into_iter()
+            .filter(|(_, (accepted, rejected))| *accepted && *rejected)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
     fn apply_tracked_op(&mut self, tracked: TrackedOp, local_op_ids: &HashSet<OperationId>) {
         let op = &tracked.operation;
         let key = (op.collection().clone(), op.record_id().clone());
 
+        // A local operation that already lost a conflict, as a patch that
+        // no longer applied, is not replayed
+        if tracked.source == OpSource::Local && self.result.rejected_local.contains(op.op_id()) {
+            return;
+        }
+
+        // Increments and CRDT field operations commute with every other
+        // write, so they never conflict and leave the record's last operation
+        // in place
+        if op.is_commutative() {
+            self.apply_op_to_state(tracked, local_op_ids);
+            return;
+        }
+
         // Check for conflict with existing state
         if let Some(existing) = self.records.get(&key) {
             // Conflict: same record modified by different sources
-            if self.is_conflict(&existing.last_op, op, existing.last_source, tracked.source) {
+            if self.is_conflict(existing, op, tracked.source) {
                 self.handle_conflict(tracked, existing.clone(), local_op_ids);
                 return;
             }
+
+            // A pending local operation replayed over the stored state that
+            // already reflects it is not applied again, so the record's
+            // version counts each write once
+            if tracked.source == OpSource::Local
+                && existing.record.metadata.version_vector.contains(op.clock())
+            {
+                self.track_accepted(op.op_id().clone(), OpSource::Local);
+                return;
+            }
         }
 
         // No conflict - apply the operation
@@ -216,14 +553,24 @@ impl<'a> Reconciler<'a> {
 
     fn is_conflict(
         &self,
-        _existing_op: &Operation,
-        _new_op: &Operation,
-        existing_source: OpSource,
+        existing: &RecordState,
+        new_op: &Operation,
         new_source: OpSource,
     ) -> bool {
-        // Conflict if different sources are modifying the same record.
-        // The merge strategy determines which one wins.
-        existing_source != new_source
+        // Operations from the same source are already causally ordered
+        if existing.last_source == new_source {
+            return false;
+        }
+
+        // An operation whose context covers every write the record reflects
+        // was made with the existing state in view, so it simply follows it.
+        // Without causal information, different sources modifying the same
+        // record conflict and the merge strategy determines which one wins.
+        let seen = &existing.record.metadata.version_vector;
+        match new_op.context() {
+            Some(context) if !seen.is_empty() => !context.dominates(seen),
+            _ => true,
+        }
     }
 
     fn handle_conflict(
@@ -232,14 +579,100 @@ impl<'a> Reconciler<'a> {
         existing: RecordState,
         _local_op_ids: &HashSet<OperationId>,
     ) {
+        let strategy = self.strategy_for(incoming.operation.collection());
         let (local_op, remote_op, local_source) = if incoming.source == OpSource::Local {
             (incoming.operation.clone(), existing.last_op.clone(), true)
         } else {
             (existing.last_op.clone(), incoming.operation.clone(), false)
         };
 
-        // Determine winner based on strategy
-        let (winner, resolution) = self.resolve_conflict(&local_op, &remote_op);
+        // A custom resolver takes precedence over the strategy
+        let custom = self
+            .resolvers
+            .get(incoming.operation.collection())
+            .map(|resolver| resolver.resolve(&local_op, &remote_op, &existing.record));
+        let keep_both = custom.is_none() && strategy == MergeStrategy::KeepBoth;
+
+        let (winner, resolution, rule) = match custom {
+            Some(Resolution::Local) => (
+                local_op.clone(),
+                ConflictResolution::LocalWins,
+                ConflictRule::Resolver,
+            ),
+            Some(Resolution::Remote) => (
+                remote_op.clone(),
+                ConflictResolution::RemoteWins,
+                ConflictRule::Resolver,
+            ),
+            Some(Resolution::Merged(payload))
+                if self.is_valid_payload(incoming.operation.collection(), &payload) =>
+            {
+                self.apply_merged(incoming, &local_op, &remote_op, payload);
+                return;
+            }
+            _ => {
+                if strategy == MergeStrategy::FieldLevel {
+                    if let Some(merge) = self.merge_fields(&incoming.operation, &existing.record) {
+                        self.handle_field_merge(incoming, existing, merge);
+                        return;
+                    }
+                }
+
+                // The existing side counts as a delete when the record is a
+                // tombstone, whatever operation left it that way
+                let incoming_deletes = matches!(incoming.operation, Operation::Delete(_));
+                let (local_deletes, remote_deletes) = if local_source {
+                    (incoming_deletes, existing.record.deleted)
+                } else {
+                    (existing.record.deleted, incoming_deletes)
+                };
+
+                // Determine winner based on strategy
+                self.resolve_conflict(
+                    strategy,
+                    self.delete_policy_for(incoming.operation.collection()),
+                    (&local_op, local_deletes),
+                    (&remote_op, remote_deletes),
+                )
+            }
+        };
+
+        // A winning patch applies on top of the write it beat; one that no
+        // longer does, such as a failed `test`, loses to it instead
+        let (winner, resolution) = if !keep_both && self.patch_fails(&winner, &incoming, &existing)
+        {
+            match resolution {
+                ConflictResolution::LocalWins => {
+                    (remote_op.clone(), ConflictResolution::RemoteWins)
+                }
+                _ => (local_op.clone(), ConflictResolution::LocalWins),
+            }
+        } else {
+            (winner, resolution)
+        };
+
+        // A write that beat a delete by policy brings the record back
+        let revive = rule == ConflictRule::UpdateWins;
+
+        // Keeping both versions accepts both operations; the record still
+        // shows the winner until the conflict is resolved
+        let (resolution, rule) = if keep_both {
+            (ConflictResolution::KeptBoth, ConflictRule::KeepBoth)
+        } else {
+            (resolution, rule)
+        };
+        let siblings = keep_both.then(|| {
+            [
+                Sibling {
+                    op_id: existing.last_op.op_id().clone(),
+                    payload: (!existing.record.deleted).then(|| existing.record.payload.clone()),
+                },
+                Sibling {
+                    op_id: incoming.operation.op_id().clone(),
+                    payload: self.written_payload(&incoming.operation, &existing.record),
+                },
+            ]
+        });
 
         let winner_op_id = winner.op_id().clone();
         let winner_is_local = winner.op_id() == local_op.op_id();
@@ -250,105 +683,434 @@ impl<'a> Reconciler<'a> {
             remote_op: remote_op.clone(),
             resolution: resolution.clone(),
             winner_op_id: winner_op_id.clone(),
+            field_outcomes: BTreeMap::new(),
+            rule,
         });
 
-        // Update result tracking
-        match resolution {
-            ConflictResolution::LocalWins => {
-                self.result.rejected_remote.push(remote_op.op_id().clone());
-                // Local was already applied or will be kept
-                if !self.result.accepted_local.contains(local_op.op_id()) {
-                    self.result.accepted_local.push(local_op.op_id().clone());
-                }
-            }
-            ConflictResolution::RemoteWins => {
-                self.result.rejected_local.push(local_op.op_id().clone());
-                self.result
-                    .accepted_local
-                    .retain(|id| id != local_op.op_id());
-                if !self.result.applied_remote.contains(remote_op.op_id()) {
-                    self.result.applied_remote.push(remote_op.op_id().clone());
-                }
-            }
-        }
+        self.track_resolution(&resolution, &local_op, &remote_op);
 
         // Apply winner if it's the incoming operation
+        let key = (winner.collection().clone(), winner.record_id().clone());
+        let incoming_clock = incoming.operation.clock().clone();
         if (winner_is_local && local_source) || (!winner_is_local && !local_source) {
             // Incoming operation wins - apply it
+            let revive = revive && existing.record.deleted;
             let tracked = TrackedOp {
                 operation: winner,
                 source: incoming.source,
             };
             self.force_apply_op(tracked);
+
+            if revive {
+                if let Some(state) = self.records.get_mut(&key) {
+                    state.record.deleted = false;
+                }
+            }
         }
         // Otherwise, existing state remains (winner already applied)
+
+        // The record now reflects the losing version too, as a sibling
+        if let Some(siblings) = siblings {
+            if let Some(state) = self.records.get_mut(&key) {
+                state
+                    .record
+                    .metadata
+                    .version_vector
+                    .observe(&incoming_clock);
+                for sibling in siblings {
+                    state.record.add_sibling(sibling);
+                }
+            }
+        }
     }
 
-    fn resolve_conflict(
+    /// Whether `winner` is a patch that no longer applies once ordered after
+    /// the write it beat: the existing record for an incoming patch, the
+    /// incoming write for a loaded record last written by a local patch.
+    fn patch_fails(
         &self,
+        winner: &Operation,
+        incoming: &TrackedOp,
+        existing: &RecordState,
+    ) -> bool {
+        let incoming_wins = winner.op_id() == incoming.operation.op_id();
+        // A loaded record's last operation is rebuilt as a plain write, so
+        // look up the local operation behind it
+        let winner = match self.local_ops.get(winner.op_id()) {
+            Some(op) if !incoming_wins => op,
+            _ => winner,
+        };
+        let Operation::Patch(patch_op) = winner else {
+            return false;
+        };
+
+        let base = if incoming_wins {
+            (!existing.record.deleted).then(|| existing.record.payload.clone())
+        } else {
+            self.written_payload(&incoming.operation, &existing.record)
+        };
+        base.is_some_and(|base| self.schema.validate_patch(patch_op, &base).is_err())
+    }
+
+    /// The payload an operation writes over a record (`None` for a delete).
+    fn written_payload(&self, op: &Operation, record: &Record) -> Option<serde_json::Value> {
+        match op {
+            Operation::Create(create_op) => Some(create_op.payload.clone()),
+            Operation::Update(update_op) => Some(update_op.payload.clone()),
+            Operation::Restore(restore_op) => Some(restore_op.payload.clone()),
+            Operation::Upsert(upsert_op) => Some(upsert_op.payload.clone()),
+            // A patch that no longer applies leaves the record unchanged
+            Operation::Patch(patch_op) => Some(
+                self.schema
+                    .validate_patch(patch_op, &record.payload)
+                    .unwrap_or_else(|_| record.payload.clone()),
+            ),
+            Operation::Delete(_) => None,
+            Operation::Increment(_)
+            | Operation::List(_)
+            | Operation::Text(_)
+            | Operation::Set(_) => Some(record.payload.clone()),
+        }
+    }
+
+    /// Strategy for a collection: its schema's, else the reconciler default.
+    fn strategy_for(&self, collection: &str) -> MergeStrategy {
+        self.schema
+            .get_collection(collection)
+            .and_then(|schema| schema.merge_strategy)
+            .unwrap_or(self.strategy)
+    }
+
+    /// Apply a payload synthesized by a custom resolver.
+    fn apply_merged(
+        &mut self,
+        incoming: TrackedOp,
         local_op: &Operation,
         remote_op: &Operation,
-    ) -> (Operation, ConflictResolution) {
-        match self.strategy {
-            MergeStrategy::ClockWins => {
-                // Compare by clock, then timestamp, then op_id
-                if local_op >= remote_op {
-                    (local_op.clone(), ConflictResolution::LocalWins)
-                } else {
-                    (remote_op.clone(), ConflictResolution::RemoteWins)
+        payload: serde_json::Value,
+    ) {
+        self.result.conflicts.push(Conflict {
+            local_op: local_op.clone(),
+            remote_op: remote_op.clone(),
+            resolution: ConflictResolution::Merged,
+            winner_op_id: incoming.operation.op_id().clone(),
+            field_outcomes: BTreeMap::new(),
+            rule: ConflictRule::Resolver,
+        });
+        self.track_resolution(&ConflictResolution::Merged, local_op, remote_op);
+
+        // The merged result descends from both operations
+        let clock = std::cmp::max(local_op.clock(), remote_op.clock()).clone();
+        let timestamp = local_op.timestamp().max(remote_op.timestamp());
+        let key = (
+            incoming.operation.collection().clone(),
+            incoming.operation.record_id().clone(),
+        );
+        if let Some(state) = self.records.get_mut(&key) {
+            state
+                .record
+                .update_payload(payload, timestamp, clock, origin_of(incoming.source));
+            state.record.deleted = false;
+            state.last_op = incoming.operation;
+            state.last_source = incoming.source;
+        }
+    }
+
+    fn is_valid_payload(&self, collection: &str, payload: &serde_json::Value) -> bool {
+        self.schema
+            .get_collection(collection)
+            .is_none_or(|schema| schema.validate_payload(payload).is_ok())
+    }
+
+    /// Merge an incoming operation into the existing record field by field.
+    ///
+    /// Each field the operation writes is kept only if the operation's clock
+    /// is newer than that field's last write. Returns `None` when a field
+    /// merge does not apply, such as for deletes or non-object payloads.
+    fn merge_fields(&self, op: &Operation, existing: &Record) -> Option<FieldMerge> {
+        if existing.deleted {
+            return None;
+        }
+
+        let (target, declared) = match op {
+            Operation::Create(create_op) => (create_op.payload.clone(), None),
+            Operation::Upsert(upsert_op) => (upsert_op.payload.clone(), None),
+            Operation::Update(update_op) => {
+                (update_op.payload.clone(), update_op.changed_fields.clone())
+            }
+            Operation::Patch(patch_op) => {
+                let payload = self
+                    .schema
+                    .validate_patch(patch_op, &existing.payload)
+                    .ok()?;
+                (payload, None)
+            }
+            // Deletes and restores replace the record as a whole
+            Operation::Delete(_) | Operation::Restore(_) => return None,
+            // Increments and CRDT field operations commute and never conflict
+            Operation::Increment(_)
+            | Operation::List(_)
+            | Operation::Text(_)
+            | Operation::Set(_) => return None,
+        };
+
+        let declared = declared.unwrap_or_else(|| existing.changed_fields(&target));
+        let current = existing.payload.as_object()?;
+        let target = target.as_object()?;
+
+        let mut payload = current.clone();
+        let mut outcomes = BTreeMap::new();
+        for field in declared {
+            if current.get(&field) == target.get(&field) {
+                continue;
+            }
+
+            let incoming_wins = op.clock() > existing.metadata.field_clock(&field);
+            if incoming_wins {
+                match target.get(&field) {
+                    Some(value) => payload.insert(field.clone(), value.clone()),
+                    None => payload.remove(&field),
+                };
+            }
+            outcomes.insert(field, incoming_wins);
+        }
+
+        let payload = serde_json::Value::Object(payload);
+        if !self.is_valid_payload(op.collection(), &payload) {
+            return None;
+        }
+
+        Some(FieldMerge { payload, outcomes })
+    }
+
+    fn handle_field_merge(
+        &mut self,
+        incoming: TrackedOp,
+        existing: RecordState,
+        merge: FieldMerge,
+    ) {
+        // Nothing actually differs, so there is nothing to resolve
+        if merge.outcomes.is_empty() {
+            self.track_accepted(incoming.operation.op_id().clone(), incoming.source);
+            return;
+        }
+
+        let (incoming_wins, existing_wins) = match incoming.source {
+            OpSource::Local => (
+                ConflictResolution::LocalWins,
+                ConflictResolution::RemoteWins,
+            ),
+            OpSource::Remote => (
+                ConflictResolution::RemoteWins,
+                ConflictResolution::LocalWins,
+            ),
+        };
+        let field_outcomes: BTreeMap<_, _> = merge
+            .outcomes
+            .iter()
+            .map(|(field, won)| {
+                let outcome = if *won { &incoming_wins } else { &existing_wins };
+                (field.clone(), outcome.clone())
+            })
+            .collect();
+
+        let existing_won = merge.outcomes.values().all(|won| !*won);
+        let resolution = if merge.outcomes.values().all(|won| *won) {
+            incoming_wins
+        } else if existing_won {
+            existing_wins
+        } else {
+            ConflictResolution::Merged
+        };
+
+        let (local_op, remote_op) = match incoming.source {
+            OpSource::Local => (incoming.operation.clone(), existing.last_op.clone()),
+            OpSource::Remote => (existing.last_op.clone(), incoming.operation.clone()),
+        };
+        let winner_op_id = if existing_won {
+            existing.last_op.op_id().clone()
+        } else {
+            incoming.operation.op_id().clone()
+        };
+
+        self.result.conflicts.push(Conflict {
+            local_op: local_op.clone(),
+            remote_op: remote_op.clone(),
+            resolution: resolution.clone(),
+            winner_op_id,
+            field_outcomes,
+            rule: ConflictRule::FieldLevel,
+        });
+
+        // The existing operation's other fields still stand, so only an
+        // incoming operation that won nothing is rejected
+        if existing_won {
+            self.track_resolution(&resolution, &local_op, &remote_op);
+            return;
+        }
+        self.track_resolution(&ConflictResolution::Merged, &local_op, &remote_op);
+
+        let key = (
+            incoming.operation.collection().clone(),
+            incoming.operation.record_id().clone(),
+        );
+        if let Some(state) = self.records.get_mut(&key) {
+            state.record.merge_payload(
+                merge.payload,
+                incoming.operation.timestamp(),
+                incoming.operation.clock().clone(),
+                origin_of(incoming.source),
+            );
+            state.last_op = incoming.operation;
+            state.last_source = incoming.source;
+        }
+    }
+
+    fn track_resolution(
+        &mut self,
+        resolution: &ConflictResolution,
+        local_op: &Operation,
+        remote_op: &Operation,
+    ) {
+        match resolution {
+            ConflictResolution::LocalWins => {
+                self.result.rejected_remote.push(remote_op.op_id().clone());
+                // Local was already applied or will be kept
+                if !self.result.accepted_local.contains(local_op.op_id()) {
+                    self.result.accepted_local.push(local_op.op_id().clone());
                 }
             }
-            MergeStrategy::TimestampWins => {
-                if local_op.timestamp() >= remote_op.timestamp() {
-                    (local_op.clone(), ConflictResolution::LocalWins)
-                } else {
-                    (remote_op.clone(), ConflictResolution::RemoteWins)
+            ConflictResolution::RemoteWins => {
+                self.result.rejected_local.push(local_op.op_id().clone());
+                self.result
+                    .accepted_local
+                    .retain(|id| id != local_op.op_id());
+                if !self.result.applied_remote.contains(remote_op.op_id()) {
+                    self.result.applied_remote.push(remote_op.op_id().clone());
                 }
             }
+            ConflictResolution::Merged | ConflictResolution::KeptBoth => {
+                self.track_accepted(local_op.op_id().clone(), OpSource::Local);
+                self.track_accepted(remote_op.op_id().clone(), OpSource::Remote);
+            }
         }
     }
 
-    fn apply_op_to_state(&mut self, tracked: TrackedOp, _local_op_ids: &HashSet<OperationId>) {
-        let op_id = tracked.operation.op_id().clone();
-        let source = tracked.source;
-
-        // Track in result
+    fn track_rejected(&mut self, op_id: OperationId, source: OpSource) {
         match source {
             OpSource::Local => {
-                if !self.result.accepted_local.contains(&op_id) {
-                    self.result.accepted_local.push(op_id.clone());
+                self.result.accepted_local.retain(|id| *id != op_id);
+                if !self.result.rejected_local.contains(&op_id) {
+                    self.result.rejected_local.push(op_id);
                 }
             }
             OpSource::Remote => {
-                if !self.result.applied_remote.contains(&op_id) {
-                    self.result.applied_remote.push(op_id.clone());
+                self.result.applied_remote.retain(|id| *id != op_id);
+                if !self.result.rejected_remote.contains(&op_id) {
+                    self.result.rejected_remote.push(op_id);
                 }
             }
         }
+    }
+
+    fn track_accepted(&mut self, op_id: OperationId, source: OpSource) {
+        let accepted = match source {
+            OpSource::Local => &mut self.result.accepted_local,
+            OpSource::Remote => &mut self.result.applied_remote,
+        };
+        if !accepted.contains(&op_id) {
+            accepted.push(op_id);
+        }
+    }
+
+    /// Delete policy for a collection: its schema's, else the one its
+    /// strategy implies.
+    fn delete_policy_for(&self, collection: &str) -> Option<DeletePolicy> {
+        let declared = self
+            .schema
+            .get_collection(collection)
+            .and_then(|schema| schema.delete_policy);
+        declared.or(match self.strategy_for(collection) {
+            MergeStrategy::DeleteWins => Some(DeletePolicy::DeleteWins),
+            MergeStrategy::UpdateWins | MergeStrategy::KeepBoth => Some(DeletePolicy::UpdateWins),
+            MergeStrategy::ClockWins | MergeStrategy::TimestampWins | MergeStrategy::FieldLevel => {
+                None
+            }
+        })
+    }
+
+    /// Pick a winner between two operations. Each side carries whether it
+    /// deletes the record; when only one does, the delete policy decides.
+    fn resolve_conflict(
+        &self,
+        strategy: MergeStrategy,
+        delete_policy: Option<DeletePolicy>,
+        (local_op, local_deletes): (&Operation, bool),
+        (remote_op, remote_deletes): (&Operation, bool),
+    ) -> (Operation, ConflictResolution, ConflictRule) {
+        let policy = delete_policy.filter(|_| local_deletes != remote_deletes);
+        let (local_wins, rule) = match (policy, strategy) {
+            (Some(DeletePolicy::DeleteWins), _) => (local_deletes, ConflictRule::DeleteWins),
+            (Some(DeletePolicy::UpdateWins), _) => (remote_deletes, ConflictRule::UpdateWins),
+            (None, MergeStrategy::TimestampWins) => (
+                local_op.timestamp() >= remote_op.timestamp(),
+                ConflictRule::TimestampWins,
+            ),
+            // Compare by clock, then timestamp, then op_id
+            _ => (local_op >= remote_op, ConflictRule::ClockWins),
+        };
+
+        if local_wins {
+            (local_op.clone(), ConflictResolution::LocalWins, rule)
+        } else {
+            (remote_op.clone(), ConflictResolution::RemoteWins, rule)
+        }
+    }
+
+    fn apply_op_to_state(&mut self, tracked: TrackedOp, _local_op_ids: &HashSet<OperationId>) {
+        let (op_id, source) = (tracked.operation.op_id().clone(), tracked.source);
 
-        self.force_apply_op(tracked);
+        // Track in result
+        if self.force_apply_op(tracked) {
+            self.track_accepted(op_id, source);
+        } else {
+            self.track_rejected(op_id, source);
+        }
     }
 
-    fn force_apply_op(&mut self, tracked: TrackedOp) {
+    /// Apply an operation to the reconciled state.
+    ///
+    /// Returns `false` if it is a patch, increment or CRDT field operation
+    /// that no longer applies to the merged state, such as a list insert after an
+    /// element a concurrent update replaced.
+    fn force_apply_op(&mut self, tracked: TrackedOp) -> bool {
         let op = tracked.operation;
         let source = tracked.source;
         let key = (op.collection().clone(), op.record_id().clone());
-        let origin = match source {
-            OpSource::Local => Origin::Local,
-            OpSource::Remote => Origin::Remote,
-        };
+        let origin = origin_of(source);
+        let op_id = op.op_id().clone();
+
+        // A write made with the conflicting versions in view resolves them
+        if !op.is_commutative() {
+            if let (Some(context), Some(state)) = (op.context(), self.records.get_mut(&key)) {
+                if context.dominates(&state.record.metadata.version_vector) {
+                    state.record.siblings.clear();
+                }
+            }
+        }
 
         match &op {
             Operation::Create(create_op) => {
-                let record = Record::new(
+                let mut record = Record::new(
                     create_op.id.clone(),
                     create_op.collection.clone(),
                     create_op.payload.clone(),
                     create_op.timestamp,
                     create_op.clock.clone(),
                 );
+                record.metadata.expires_at = create_op.expires_at;
                 self.records.insert(
-                    key,
+                    key.clone(),
                     RecordState {
                         record,
                         last_op: op,
@@ -357,9 +1119,19 @@ impl<'a> Reconciler<'a> {
                 );
             }
             Operation::Update(update_op) => {
+                let field_level =
+                    self.strategy_for(&update_op.collection) == MergeStrategy::FieldLevel;
                 if let Some(state) = self.records.get_mut(&key) {
+                    // Under field-level merging an update only writes the
+                    // fields it changed, keeping fields merged in earlier
+                    let payload = match &update_op.changed_fields {
+                        Some(fields) if field_level => {
+                            overlay_fields(&state.record.payload, &update_op.payload, fields)
+                        }
+                        _ => update_op.payload.clone(),
+                    };
                     state.record.update_payload(
-                        update_op.payload.clone(),
+                        payload,
                         update_op.timestamp,
                         update_op.clock.clone(),
                         origin,
@@ -368,6 +1140,37 @@ impl<'a> Reconciler<'a> {
                     state.last_source = source;
                 }
             }
+            Operation::Patch(patch_op) => {
+                let schema = self.schema;
+                if let Some(state) = self.records.get_mut(&key) {
+                    // Patches are not idempotent (`add /tags/-` appends
+                    // again), so one the record already reflects, as when
+                    // pending local operations are replayed over the stored
+                    // state, is not applied twice
+                    let applied = state
+                        .record
+                        .metadata
+                        .version_vector
+                        .contains(&patch_op.clock);
+                    if !applied {
+                        // A patch that no longer applies cleanly to the
+                        // merged state leaves the record unchanged and is
+                        // rejected
+                        let Ok(payload) = schema.validate_patch(patch_op, &state.record.payload)
+                        else {
+                            return false;
+                        };
+                        state.record.update_payload(
+                            payload,
+                            patch_op.timestamp,
+                            patch_op.clock.clone(),
+                            origin,
+                        );
+                        state.last_op = op;
+                        state.last_source = source;
+                    }
+                }
+            }
             Operation::Delete(delete_op) => {
                 if let Some(state) = self.records.get_mut(&key) {
                     state
@@ -377,10 +1180,153 @@ impl<'a> Reconciler<'a> {
                     state.last_source = source;
                 }
             }
-        }
-    }
-
-    /// Get current records (for inspection during reconciliation).
+            Operation::Restore(restore_op) => {
+                if let Some(state) = self.records.get_mut(&key) {
+                    state.record.restore(
+                        restore_op.payload.clone(),
+                        restore_op.timestamp,
+                        restore_op.clock.clone(),
+                        origin,
+                    );
+                    state.last_op = op;
+                    state.last_source = source;
+                } else {
+                    // The tombstone was never seen here, so the restored
+                    // record starts its history like a create
+                    let record = Record::new(
+                        restore_op.id.clone(),
+                        restore_op.collection.clone(),
+                        restore_op.payload.clone(),
+                        restore_op.timestamp,
+                        restore_op.clock.clone(),
+                    );
+                    self.records.insert(
+                        key.clone(),
+                        RecordState {
+                            record,
+                            last_op: op,
+                            last_source: source,
+                        },
+                    );
+                }
+            }
+            Operation::Increment(increment_op) => {
+                let schema = self.schema;
+                if let Some(state) = self.records.get_mut(&key) {
+                    // A record whose version vector holds the increment's
+                    // clock already reflects it, as when pending local
+                    // operations are replayed over the stored state
+                    let applied = state
+                        .record
+                        .metadata
+                        .version_vector
+                        .contains(&increment_op.clock);
+                    if !applied && !state.record.deleted {
+                        // An increment that does not apply cleanly to the
+                        // merged state, such as one of a field that is not
+                        // numeric, leaves the record unchanged and is
+                        // rejected
+                        let Ok(payload) =
+                            schema.validate_increment(increment_op, &state.record.payload)
+                        else {
+                            return false;
+                        };
+                        state.record.update_payload(
+                            payload,
+                            increment_op.timestamp,
+                            increment_op.clock.clone(),
+                            origin,
+                        );
+                    }
+                }
+            }
+            Operation::List(list_op) => {
+                if let Some(state) = self.records.get_mut(&key) {
+                    // List operations are idempotent; one that no longer
+                    // applies to the merged state (such as an insert after an
+                    // element the list no longer holds) leaves the record
+                    // unchanged and is rejected
+                    if !state.record.deleted
+                        && state
+                            .record
+                            .apply_list(list_op, list_op.timestamp, origin)
+                            .is_err()
+                    {
+                        return false;
+                    }
+                }
+            }
+            Operation::Text(text_op) => {
+                if let Some(state) = self.records.get_mut(&key) {
+                    // Like list operations, text splices are idempotent and
+                    // rejected once they no longer apply
+                    if !state.record.deleted
+                        && state
+                            .record
+                            .apply_text(text_op, text_op.timestamp, origin)
+                            .is_err()
+                    {
+                        return false;
+                    }
+                }
+            }
+            Operation::Set(set_op) => {
+                if let Some(state) = self.records.get_mut(&key) {
+                    // Set operations are idempotent: removed adds stay removed
+                    if !state.record.deleted
+                        && state
+                            .record
+                            .apply_set(set_op, set_op.timestamp, origin)
+                            .is_err()
+                    {
+                        return false;
+                    }
+                }
+            }
+            Operation::Upsert(upsert_op) => {
+                if let Some(state) = self.records.get_mut(&key) {
+                    state.record.restore(
+                        upsert_op.payload.clone(),
+                        upsert_op.timestamp,
+                        upsert_op.clock.clone(),
+                        origin,
+                    );
+                    state.record.metadata.expires_at = upsert_op.expires_at;
+                    state.last_op = op;
+                    state.last_source = source;
+                } else {
+                    let mut record = Record::new(
+                        upsert_op.id.clone(),
+                        upsert_op.collection.clone(),
+                        upsert_op.payload.clone(),
+                        upsert_op.timestamp,
+                        upsert_op.clock.clone(),
+                    );
+                    record.metadata.expires_at = upsert_op.expires_at;
+                    self.records.insert(
+                        key.clone(),
+                        RecordState {
+                            record,
+                            last_op: op,
+                            last_source: source,
+                        },
+                    );
+                }
+            }
+        }
+
+        // The record now also reflects everything the operation's author saw
+        if let Some(state) = self.records.get_mut(&key) {
+            if state.last_op.op_id() == &op_id {
+                if let Some(context) = state.last_op.context() {
+                    state.record.metadata.version_vector.merge(context);
+                }
+            }
+        }
+        true
+    }
+
+    /// Get current records (for inspection during reconciliation).
     pub fn current_records(&self) -> impl Iterator<Item = &Record> {
         self.records.values().map(|s| &s.record)
     }
@@ -389,9 +1335,9 @@ impl<'a> Reconciler<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::operation::{CreateOp, UpdateOp};
+    use crate::operation::{CreateOp, DeleteOp, RestoreOp, UpdateOp, UpsertOp};
     use crate::schema::{CollectionSchema, FieldDef, FieldType};
-    use crate::LogicalClock;
+    use crate::{LogicalClock, VersionVector};
     use serde_json::json;
 
     fn test_schema() -> Schema {
@@ -401,6 +1347,17 @@ mod tests {
         ))
     }
 
+    /// A stored record last written by `op_id` from `origin`.
+    fn stored_record(mut record: Record, op_id: &str, origin: Origin) -> Record {
+        record.metadata.origin = origin;
+        record.metadata.last_op = Some(LastOp {
+            op_id: op_id.to_string(),
+            clock: record.metadata.clock.clone(),
+            timestamp: record.metadata.updated_at,
+        });
+        record
+    }
+
     #[test]
     fn reconcile_no_conflicts() {
         let schema = test_schema();
@@ -554,6 +1511,52 @@ mod tests {
         assert_eq!(record.payload, json!({"name": "Bob"}));
     }
 
+    #[test]
+    fn reconcile_conflicts_with_stored_last_operation() {
+        let schema = test_schema();
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::DeleteWins);
+
+        // A local tombstone stored before last operations were tracked
+        let mut existing = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            500,
+            LogicalClock::with_counter("local", 1),
+        );
+        existing.mark_deleted(700, LogicalClock::with_counter("local", 2), Origin::Local);
+        reconciler.load_records(std::iter::once(existing));
+
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Bob"}),
+            1,
+            800,
+            LogicalClock::with_counter("remote", 5),
+        ))];
+
+        let (result, records) = reconciler.reconcile(vec![], remote_ops);
+
+        // The stored state stands in as a delete at its last update
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert!(matches!(conflict.local_op, Operation::Delete(_)));
+        assert_eq!(conflict.local_op.timestamp(), 700);
+        assert_eq!(
+            conflict.local_op.clock(),
+            &LogicalClock::with_counter("local", 2)
+        );
+        assert_eq!(conflict.resolution, ConflictResolution::LocalWins);
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert!(record.deleted);
+        assert_eq!(record.metadata.last_op, None);
+    }
+
     #[test]
     fn reconcile_update_conflict() {
         let schema = test_schema();
@@ -567,15 +1570,11 @@ mod tests {
             500,
             LogicalClock::with_counter("server", 1),
         );
-        let create_op = Operation::Create(CreateOp::new(
+        reconciler.load_records(std::iter::once(stored_record(
+            existing,
             "op-0",
-            "user-1",
-            "users",
-            json!({"name": "Original"}),
-            500,
-            LogicalClock::with_counter("server", 1),
-        ));
-        reconciler.load_records(std::iter::once((existing, create_op, OpSource::Remote)));
+            Origin::Remote,
+        )));
 
         // Both try to update
         let local_ops = vec![Operation::Update(UpdateOp::new(
@@ -612,6 +1611,752 @@ mod tests {
         assert_eq!(record.payload, json!({"name": "Alice Update"}));
     }
 
+    fn tags_schema() -> Schema {
+        Schema::new(1).with_collection(CollectionSchema::new(
+            "users",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("tags", FieldType::Json),
+            ],
+        ))
+    }
+
+    fn op_payload(op: &Operation) -> serde_json::Value {
+        match op {
+            Operation::Create(create_op) => create_op.payload.clone(),
+            Operation::Update(update_op) => update_op.payload.clone(),
+            _ => json!({}),
+        }
+    }
+
+    /// Union of both tag lists, name from the later operation.
+    fn union_tags(local: &Operation, remote: &Operation, _current: &Record) -> Resolution {
+        let (earlier, later) = if local < remote {
+            (local, remote)
+        } else {
+            (remote, local)
+        };
+        let mut tags: Vec<String> = [earlier, later]
+            .iter()
+            .flat_map(|op| {
+                op_payload(op)["tags"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .filter_map(|tag| tag.as_str().map(String::from))
+            .collect();
+        tags.sort();
+        tags.dedup();
+
+        Resolution::Merged(json!({
+            "name": op_payload(later)["name"],
+            "tags": tags,
+        }))
+    }
+
+    fn tagged_create(op_id: &str, node: &str, counter: u64, tags: &[&str]) -> Operation {
+        Operation::Create(CreateOp::new(
+            op_id,
+            "user-1",
+            "users",
+            json!({"name": op_id, "tags": tags}),
+            1000,
+            LogicalClock::with_counter(node, counter),
+        ))
+    }
+
+    #[test]
+    fn reconcile_custom_resolver_picks_winner() {
+        let schema = test_schema();
+        // Prefer the local side regardless of clocks
+        let reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins).with_resolver(
+            "users",
+            Arc::new(|_: &Operation, _: &Operation, _: &Record| Resolution::Local),
+        );
+
+        let local_ops = vec![tagged_create("op-local", "local", 1, &[])];
+        let remote_ops = vec![tagged_create("op-remote", "remote", 9, &[])];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(
+            result.conflicts[0].resolution,
+            ConflictResolution::LocalWins
+        );
+        assert_eq!(result.rejected_remote, vec!["op-remote".to_string()]);
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert_eq!(record.payload["name"], "op-local");
+    }
+
+    #[test]
+    fn reconcile_custom_resolver_merges_payload() {
+        let schema = tags_schema();
+        let reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins)
+            .with_resolver("users", Arc::new(union_tags));
+
+        let local_ops = vec![tagged_create("op-local", "local", 1, &["a", "b"])];
+        let remote_ops = vec![tagged_create("op-remote", "remote", 2, &["c"])];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+
+        assert_eq!(result.conflicts[0].resolution, ConflictResolution::Merged);
+        assert!(result.accepted_local.contains(&"op-local".to_string()));
+        assert!(result.applied_remote.contains(&"op-remote".to_string()));
+        assert!(result.rejected_local.is_empty());
+        assert!(result.rejected_remote.is_empty());
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert_eq!(
+            record.payload,
+            json!({"name": "op-remote", "tags": ["a", "b", "c"]})
+        );
+        assert_eq!(
+            record.metadata.clock,
+            LogicalClock::with_counter("remote", 2)
+        );
+    }
+
+    #[test]
+    fn reconcile_custom_resolver_invalid_merge_falls_back() {
+        let schema = tags_schema();
+        let reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins).with_resolver(
+            "users",
+            Arc::new(|_: &Operation, _: &Operation, _: &Record| {
+                Resolution::Merged(json!({"tags": []}))
+            }),
+        );
+
+        let local_ops = vec![tagged_create("op-local", "local", 1, &[])];
+        let remote_ops = vec![tagged_create("op-remote", "remote", 2, &[])];
+
+        let (result, _) = reconciler.reconcile(local_ops, remote_ops);
+
+        // Missing required name, so clock wins decides instead
+        assert_eq!(
+            result.conflicts[0].resolution,
+            ConflictResolution::RemoteWins
+        );
+    }
+
+    #[test]
+    fn reconcile_custom_resolver_is_deterministic() {
+        let schema = tags_schema();
+        let run = |local_ops: Vec<Operation>, remote_ops: Vec<Operation>| {
+            let reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins)
+                .with_resolver("users", Arc::new(union_tags));
+            let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+            let record = records
+                .get(&("users".to_string(), "user-1".to_string()))
+                .unwrap()
+                .clone();
+            (result, record)
+        };
+
+        let a = tagged_create("op-a", "node-a", 3, &["x", "y"]);
+        let b = tagged_create("op-b", "node-b", 3, &["z"]);
+
+        // Replaying the same inputs gives the same output
+        let (first_result, first_record) = run(vec![a.clone()], vec![b.clone()]);
+        let (second_result, second_record) = run(vec![a.clone()], vec![b.clone()]);
+        assert_eq!(first_result, second_result);
+        assert_eq!(first_record, second_record);
+
+        // The other replica sees the roles swapped and converges on the
+        // same payload and clock
+        let (_, mirrored) = run(vec![b], vec![a]);
+        assert_eq!(first_record.payload, mirrored.payload);
+        assert_eq!(first_record.metadata.clock, mirrored.metadata.clock);
+        assert_eq!(first_record.version, mirrored.version);
+    }
+
+    /// A user record that one side deleted while the other updated it.
+    fn delete_vs_update(
+        strategy: MergeStrategy,
+        policy: Option<DeletePolicy>,
+    ) -> (ReconcileResult, Record) {
+        let mut collection =
+            CollectionSchema::new("users", vec![FieldDef::required("name", FieldType::String)])
+                .with_merge_strategy(strategy);
+        collection.delete_policy = policy;
+        let schema = Schema::new(1).with_collection(collection);
+        // The collection strategy overrides the default passed here
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
+
+        let existing = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            500,
+            LogicalClock::with_counter("server", 1),
+        );
+        reconciler.load_records(std::iter::once(stored_record(
+            existing,
+            "op-0",
+            Origin::Remote,
+        )));
+
+        // The delete has the lower clock, so clock-wins would keep the update
+        let local_ops = vec![Operation::Update(UpdateOp::new(
+            "op-local",
+            "user-1",
+            "users",
+            json!({"name": "Carol"}),
+            1,
+            1000,
+            LogicalClock::with_counter("local", 3),
+        ))];
+        let remote_ops = vec![Operation::Delete(DeleteOp::new(
+            "op-delete",
+            "user-1",
+            "users",
+            1,
+            1000,
+            LogicalClock::with_counter("remote", 2),
+        ))];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap()
+            .clone();
+        (result, record)
+    }
+
+    #[test]
+    fn reconcile_collection_delete_wins() {
+        let (result, record) = delete_vs_update(MergeStrategy::DeleteWins, None);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].winner_op_id, "op-delete");
+        assert_eq!(result.conflicts[0].rule, ConflictRule::DeleteWins);
+        assert!(result.rejected_local.contains(&"op-local".to_string()));
+        assert!(record.deleted);
+    }
+
+    #[test]
+    fn reconcile_collection_update_wins_revives() {
+        let (result, record) = delete_vs_update(MergeStrategy::UpdateWins, None);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].winner_op_id, "op-local");
+        assert_eq!(result.conflicts[0].rule, ConflictRule::UpdateWins);
+        assert!(result.rejected_remote.contains(&"op-delete".to_string()));
+        assert!(!record.deleted);
+        assert_eq!(record.payload, json!({"name": "Carol"}));
+    }
+
+    #[test]
+    fn reconcile_delete_policy_overrides_strategy() {
+        // Field-level merging has no rule of its own for deletes
+        let (result, record) =
+            delete_vs_update(MergeStrategy::FieldLevel, Some(DeletePolicy::DeleteWins));
+        assert_eq!(result.conflicts[0].winner_op_id, "op-delete");
+        assert_eq!(result.conflicts[0].rule, ConflictRule::DeleteWins);
+        assert!(record.deleted);
+
+        // The delete loses without a policy, since it has the lower clock
+        let (result, _) = delete_vs_update(MergeStrategy::FieldLevel, None);
+        assert_eq!(result.conflicts[0].winner_op_id, "op-local");
+        assert_eq!(result.conflicts[0].rule, ConflictRule::ClockWins);
+
+        // A clock-wins policy replaces the one the strategy implies
+        let (result, _) =
+            delete_vs_update(MergeStrategy::DeleteWins, Some(DeletePolicy::ClockWins));
+        assert_eq!(result.conflicts[0].winner_op_id, "op-local");
+        assert_eq!(result.conflicts[0].rule, ConflictRule::ClockWins);
+        assert!(result.rejected_remote.contains(&"op-delete".to_string()));
+
+        let (result, record) =
+            delete_vs_update(MergeStrategy::ClockWins, Some(DeletePolicy::UpdateWins));
+        assert_eq!(result.conflicts[0].rule, ConflictRule::UpdateWins);
+        assert!(!record.deleted);
+    }
+
+    #[test]
+    fn reconcile_collection_keep_both() {
+        let (result, record) = delete_vs_update(MergeStrategy::KeepBoth, None);
+
+        // Both operations stand, and the record shows the update
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].resolution, ConflictResolution::KeptBoth);
+        assert_eq!(result.conflicts[0].rule, ConflictRule::KeepBoth);
+        assert!(result.accepted_local.contains(&"op-local".to_string()));
+        assert!(result.applied_remote.contains(&"op-delete".to_string()));
+        assert!(!record.deleted);
+        assert_eq!(record.payload, json!({"name": "Carol"}));
+
+        assert!(record.is_conflicted());
+        assert_eq!(
+            record.siblings,
+            vec![
+                Sibling {
+                    op_id: "op-delete".to_string(),
+                    payload: None,
+                },
+                Sibling {
+                    op_id: "op-local".to_string(),
+                    payload: Some(json!({"name": "Carol"})),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reconcile_concurrent_upserts() {
+        let schema = test_schema();
+        let reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
+
+        // Both nodes upsert a record neither has seen yet
+        let local_ops = vec![Operation::Upsert(UpsertOp::new(
+            "op-local",
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            1000,
+            LogicalClock::with_counter("local", 2),
+        ))];
+        let remote_ops = vec![Operation::Upsert(UpsertOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Bob"}),
+            1000,
+            LogicalClock::with_counter("remote", 3),
+        ))];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+
+        // The first creates the record and the second conflicts with it
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].winner_op_id, "op-remote");
+        assert!(result.rejected_local.contains(&"op-local".to_string()));
+
+        let record = &records[&("users".to_string(), "user-1".to_string())];
+        assert_eq!(record.payload["name"], "Bob");
+        assert_eq!(record.version, 2);
+    }
+
+    /// A local delete-then-restore racing a remote delete.
+    fn restore_vs_delete(strategy: MergeStrategy) -> (ReconcileResult, Record) {
+        let schema = test_schema();
+        let mut reconciler = Reconciler::new(&schema, strategy);
+
+        let existing = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            500,
+            LogicalClock::with_counter("server", 1),
+        );
+        reconciler.load_records(std::iter::once(stored_record(
+            existing,
+            "op-0",
+            Origin::Remote,
+        )));
+
+        let local_ops = vec![
+            Operation::Delete(DeleteOp::new(
+                "op-delete",
+                "user-1",
+                "users",
+                1,
+                1000,
+                LogicalClock::with_counter("local", 2),
+            )),
+            Operation::Restore(RestoreOp::new(
+                "op-restore",
+                "user-1",
+                "users",
+                json!({"name": "Alicia"}),
+                2,
+                3000,
+                LogicalClock::with_counter("local", 4),
+            )),
+        ];
+        let remote_ops = vec![Operation::Delete(DeleteOp::new(
+            "op-remote-delete",
+            "user-1",
+            "users",
+            1,
+            2000,
+            LogicalClock::with_counter("remote", 3),
+        ))];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap()
+            .clone();
+        (result, record)
+    }
+
+    #[test]
+    fn reconcile_restore_after_concurrent_delete() {
+        // The restore is the latest write, so it revives the record
+        let (result, record) = restore_vs_delete(MergeStrategy::ClockWins);
+        assert!(result.accepted_local.contains(&"op-restore".to_string()));
+        assert!(!record.deleted);
+        assert_eq!(record.payload["name"], "Alicia");
+
+        // Deletes beat the restore whatever the clocks say
+        let (result, record) = restore_vs_delete(MergeStrategy::DeleteWins);
+        assert!(result.rejected_local.contains(&"op-restore".to_string()));
+        assert!(record.deleted);
+    }
+
+    #[test]
+    fn reconcile_transaction_loses_together() {
+        let schema = test_schema();
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
+
+        let existing = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            500,
+            LogicalClock::with_counter("server", 1),
+        );
+        reconciler.load_records(std::iter::once(stored_record(
+            existing,
+            "op-0",
+            Origin::Remote,
+        )));
+
+        // The create would succeed on its own, but its transaction also
+        // holds an update that loses to the remote edit
+        let local_ops = vec![
+            Operation::Create(CreateOp::new(
+                "op-create",
+                "user-2",
+                "users",
+                json!({"name": "Bob"}),
+                1000,
+                LogicalClock::with_counter("local", 2),
+            ))
+            .with_transaction_id("tx-1"),
+            Operation::Update(UpdateOp::new(
+                "op-update",
+                "user-1",
+                "users",
+                json!({"name": "Carol"}),
+                1,
+                1000,
+                LogicalClock::with_counter("local", 3),
+            ))
+            .with_transaction_id("tx-1"),
+        ];
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Dave"}),
+            1,
+            1000,
+            LogicalClock::with_counter("remote", 5),
+        ))];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+
+        assert!(result.accepted_local.is_empty());
+        assert!(result.rejected_local.contains(&"op-create".to_string()));
+        assert!(result.rejected_local.contains(&"op-update".to_string()));
+        assert!(!records.contains_key(&("users".to_string(), "user-2".to_string())));
+        assert_eq!(
+            records[&("users".to_string(), "user-1".to_string())].payload["name"],
+            "Dave"
+        );
+    }
+
+    /// Reconcile a remote update against a record the local node created.
+    fn causal_fixture(
+        local_update: bool,
+        remote_context: Option<VersionVector>,
+    ) -> ReconcileResult {
+        let schema = test_schema();
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
+
+        let existing = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            500,
+            LogicalClock::with_counter("local", 1),
+        );
+        let context = existing.metadata.version_vector.clone();
+        reconciler.load_records(std::iter::once(stored_record(
+            existing,
+            "op-0",
+            Origin::Local,
+        )));
+
+        let local_ops = if local_update {
+            vec![Operation::Update(UpdateOp::new(
+                "op-local",
+                "user-1",
+                "users",
+                json!({"name": "Carol"}),
+                1,
+                1000,
+                LogicalClock::with_counter("local", 2),
+            ))
+            .with_context(context)]
+        } else {
+            vec![]
+        };
+        let mut remote_op = Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Bob"}),
+            1,
+            1000,
+            LogicalClock::with_counter("remote", 3),
+        ));
+        if let Some(context) = remote_context {
+            remote_op = remote_op.with_context(context);
+        }
+        let remote_ops = vec![remote_op];
+
+        reconciler.reconcile(local_ops, remote_ops).0
+    }
+
+    #[test]
+    fn reconcile_causal_update_is_not_conflict() {
+        // The remote update was made after seeing the local create
+        let context = VersionVector::from_clock(&LogicalClock::with_counter("local", 1));
+        let result = causal_fixture(false, Some(context));
+
+        assert!(result.conflicts.is_empty());
+        assert!(result.applied_remote.contains(&"op-remote".to_string()));
+    }
+
+    #[test]
+    fn reconcile_concurrent_update_with_context_conflicts() {
+        // Both updates were made against the create, neither saw the other
+        let context = VersionVector::from_clock(&LogicalClock::with_counter("local", 1));
+        let result = causal_fixture(true, Some(context));
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].winner_op_id, "op-remote");
+    }
+
+    #[test]
+    fn reconcile_without_context_keeps_conflicting() {
+        // Peers that do not track causality still conflict on every
+        // cross-source write
+        let result = causal_fixture(false, None);
+
+        assert_eq!(result.conflicts.len(), 1);
+    }
+
+    fn field_level_fixture() -> (Schema, Record) {
+        let schema = Schema::new(1).with_collection(CollectionSchema::new(
+            "users",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("age", FieldType::Int),
+            ],
+        ));
+
+        // Local record where age was edited after name
+        let mut record = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice", "age": 30}),
+            500,
+            LogicalClock::with_counter("local", 1),
+        );
+        record.update_payload(
+            json!({"name": "Alice", "age": 31}),
+            900,
+            LogicalClock::with_counter("local", 10),
+            Origin::Local,
+        );
+
+        (schema, stored_record(record, "op-local", Origin::Local))
+    }
+
+    #[test]
+    fn reconcile_field_level_merges_fields() {
+        let (schema, record) = field_level_fixture();
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::FieldLevel);
+        reconciler.load_records(std::iter::once(record));
+
+        // Remote renamed concurrently, carrying a stale age
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Alicia", "age": 30}),
+            1,
+            800,
+            LogicalClock::with_counter("remote", 5),
+        ))];
+
+        let (result, records) = reconciler.reconcile(vec![], remote_ops);
+
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.resolution, ConflictResolution::Merged);
+        assert_eq!(
+            conflict.field_outcomes.get("name"),
+            Some(&ConflictResolution::RemoteWins)
+        );
+        assert_eq!(
+            conflict.field_outcomes.get("age"),
+            Some(&ConflictResolution::LocalWins)
+        );
+        assert!(result.applied_remote.contains(&"op-remote".to_string()));
+        assert!(result.rejected_remote.is_empty());
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert_eq!(record.payload, json!({"name": "Alicia", "age": 31}));
+        assert_eq!(
+            record.metadata.field_clock("name"),
+            &LogicalClock::with_counter("remote", 5)
+        );
+        assert_eq!(
+            record.metadata.field_clock("age"),
+            &LogicalClock::with_counter("local", 10)
+        );
+    }
+
+    #[test]
+    fn reconcile_field_level_respects_changed_fields() {
+        let (schema, record) = field_level_fixture();
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::FieldLevel);
+        reconciler.load_records(std::iter::once(record));
+
+        // Declared as a rename only, so the stale age is not considered
+        let remote_ops = vec![Operation::Update(
+            UpdateOp::new(
+                "op-remote",
+                "user-1",
+                "users",
+                json!({"name": "Alicia", "age": 30}),
+                1,
+                800,
+                LogicalClock::with_counter("remote", 5),
+            )
+            .with_changed_fields(["name".to_string()]),
+        )];
+
+        let (result, records) = reconciler.reconcile(vec![], remote_ops);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(
+            result.conflicts[0].resolution,
+            ConflictResolution::RemoteWins
+        );
+        assert_eq!(result.conflicts[0].field_outcomes.len(), 1);
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert_eq!(record.payload, json!({"name": "Alicia", "age": 31}));
+    }
+
+    #[test]
+    fn reconcile_field_level_same_field_latest_wins() {
+        let (schema, record) = field_level_fixture();
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::FieldLevel);
+        reconciler.load_records(std::iter::once(record));
+
+        // Remote edit to age is older than the local one
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Alice", "age": 40}),
+            1,
+            800,
+            LogicalClock::with_counter("remote", 5),
+        ))];
+
+        let (result, records) = reconciler.reconcile(vec![], remote_ops);
+
+        assert_eq!(
+            result.conflicts[0].resolution,
+            ConflictResolution::LocalWins
+        );
+        assert_eq!(result.conflicts[0].winner_op_id, "op-local");
+        assert!(result.rejected_remote.contains(&"op-remote".to_string()));
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert_eq!(record.payload, json!({"name": "Alice", "age": 31}));
+    }
+
+    #[test]
+    fn reconcile_patch_composes_with_remote_update() {
+        use crate::{Patch, PatchOp};
+
+        let schema = Schema::new(1).with_collection(CollectionSchema::new(
+            "users",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("age", FieldType::Int),
+            ],
+        ));
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
+
+        let existing = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Original", "age": 30}),
+            500,
+            LogicalClock::with_counter("server", 1),
+        );
+        reconciler.load_records(std::iter::once(stored_record(
+            existing,
+            "op-0",
+            Origin::Remote,
+        )));
+
+        // Remote renames, then a later local patch only touches age
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Renamed", "age": 30}),
+            1,
+            1000,
+            LogicalClock::with_counter("remote", 2),
+        ))];
+        let local_ops = vec![Operation::Patch(PatchOp::new(
+            "op-local",
+            "user-1",
+            "users",
+            Patch::MergePatch(json!({"age": 31})),
+            1,
+            1000,
+            LogicalClock::with_counter("local", 3),
+        ))];
+
+        let (result, records) = reconciler.reconcile(local_ops, remote_ops);
+
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(
+            result.conflicts[0].resolution,
+            ConflictResolution::LocalWins
+        );
+
+        let record = records
+            .get(&("users".to_string(), "user-1".to_string()))
+            .unwrap();
+        assert_eq!(record.payload, json!({"name": "Renamed", "age": 31}));
+    }
+
     #[test]
     fn reconcile_timestamp_strategy() {
         let schema = test_schema();
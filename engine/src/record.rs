@@ -1,7 +1,13 @@
 //! Record types for storing data.
 
-use crate::{CollectionName, LogicalClock, RecordId, Timestamp, Version};
+use crate::{
+    crdt::{FieldCrdt, ListCrdt, SetCrdt, TextCrdt},
+    error::Result,
+    CollectionName, ListAction, ListOp, LogicalClock, Operation, OperationId, RecordId, SetAction,
+    SetOp, TextOp, Timestamp, Version, VersionVector,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Origin of a record or operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,6 +19,32 @@ pub enum Origin {
     Remote,
 }
 
+/// The last operation that wrote a record's state.
+///
+/// Increments and CRDT field operations commute with every other write, so
+/// they are not tracked here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastOp {
+    /// ID of the operation
+    pub op_id: OperationId,
+    /// Logical clock of the operation
+    pub clock: LogicalClock,
+    /// Timestamp of the operation
+    pub timestamp: Timestamp,
+}
+
+impl LastOp {
+    /// Describe an operation as a record's last write.
+    pub fn of(op: &Operation) -> Self {
+        Self {
+            op_id: op.op_id().clone(),
+            clock: op.clock().clone(),
+            timestamp: op.timestamp(),
+        }
+    }
+}
+
 /// Metadata associated with a record.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -25,6 +57,21 @@ pub struct Metadata {
     pub origin: Origin,
     /// Logical clock at the time of last update
     pub clock: LogicalClock,
+    /// Clock of the last write to each top-level payload field.
+    /// Fields without an entry were last written at `clock`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_clocks: BTreeMap<String, LogicalClock>,
+    /// Clocks of every write this record state reflects
+    #[serde(default, skip_serializing_if = "VersionVector::is_empty")]
+    pub version_vector: VersionVector,
+    /// The last operation that wrote this record (`None` for records stored
+    /// before it was tracked)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_op: Option<LastOp>,
+    /// When the record expires (milliseconds since epoch), overriding the
+    /// collection's TTL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
 }
 
 impl Metadata {
@@ -34,7 +81,11 @@ impl Metadata {
             created_at: timestamp,
             updated_at: timestamp,
             origin: Origin::Local,
+            version_vector: VersionVector::from_clock(&clock),
             clock,
+            field_clocks: BTreeMap::new(),
+            last_op: None,
+            expires_at: None,
         }
     }
 
@@ -44,16 +95,97 @@ impl Metadata {
             created_at: timestamp,
             updated_at: timestamp,
             origin: Origin::Remote,
+            version_vector: VersionVector::from_clock(&clock),
             clock,
+            field_clocks: BTreeMap::new(),
+            last_op: None,
+            expires_at: None,
         }
     }
 
     /// Update metadata for a modification.
     pub fn update(&mut self, timestamp: Timestamp, clock: LogicalClock, origin: Origin) {
         self.updated_at = timestamp;
+        self.version_vector.observe(&clock);
         self.clock = clock;
         self.origin = origin;
     }
+
+    /// Get the clock of the last write to a top-level payload field.
+    pub fn field_clock(&self, field: &str) -> &LogicalClock {
+        self.field_clocks.get(field).unwrap_or(&self.clock)
+    }
+}
+
+/// A version of a record kept for manual resolution by
+/// [`MergeStrategy::KeepBoth`](crate::MergeStrategy::KeepBoth).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sibling {
+    /// Operation that wrote this version
+    pub op_id: OperationId,
+    /// Payload of this version (`None` if the operation deleted the record)
+    pub payload: Option<serde_json::Value>,
+}
+
+/// A version of a record kept in its collection's history log.
+///
+/// Collections keep a log when their schema sets
+/// [`CollectionSchema::history_limit`](crate::CollectionSchema::history_limit).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordVersion {
+    /// Version number of the record
+    pub version: Version,
+    /// Operation that wrote this version (`None` if unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op_id: Option<OperationId>,
+    /// Logical clock of the record at this version
+    pub clock: LogicalClock,
+    /// When this version was written (milliseconds since epoch)
+    pub timestamp: Timestamp,
+    /// Whether this version was written locally or came from remote
+    pub origin: Origin,
+    /// Payload at this version
+    pub payload: serde_json::Value,
+    /// Whether the record was deleted at this version
+    pub deleted: bool,
+}
+
+impl RecordVersion {
+    /// Capture a record's current version, written by `op_id`.
+    pub fn of(record: &Record, op_id: Option<OperationId>) -> Self {
+        Self {
+            version: record.version,
+            op_id,
+            clock: record.metadata.clock.clone(),
+            timestamp: record.metadata.updated_at,
+            origin: record.metadata.origin,
+            payload: record.payload.clone(),
+            deleted: record.deleted,
+        }
+    }
+}
+
+/// A point in a record's history: a version number or a logical clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryPoint {
+    /// The version with this number
+    Version(Version),
+    /// The latest version written at or before this clock
+    Clock(LogicalClock),
+}
+
+impl From<Version> for HistoryPoint {
+    fn from(version: Version) -> Self {
+        Self::Version(version)
+    }
+}
+
+impl From<LogicalClock> for HistoryPoint {
+    fn from(clock: LogicalClock) -> Self {
+        Self::Clock(clock)
+    }
 }
 
 /// A data record in the store.
@@ -72,6 +204,13 @@ pub struct Record {
     pub metadata: Metadata,
     /// Soft delete flag (tombstone)
     pub deleted: bool,
+    /// Replicated state of CRDT fields, keyed by field name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub crdt: BTreeMap<String, FieldCrdt>,
+    /// Conflicting versions awaiting manual resolution, ordered by
+    /// operation ID
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub siblings: Vec<Sibling>,
 }
 
 impl Record {
@@ -90,6 +229,8 @@ impl Record {
             payload,
             metadata: Metadata::new_local(timestamp, clock),
             deleted: false,
+            crdt: BTreeMap::new(),
+            siblings: Vec::new(),
         }
     }
 
@@ -98,6 +239,43 @@ impl Record {
         !self.deleted
     }
 
+    /// When the record expires: its own expiry, else `ttl` milliseconds
+    /// after the operation that last wrote it. `None` if it never expires.
+    ///
+    /// The TTL counts from the operation's timestamp rather than
+    /// `updated_at`, so every node computes the same expiry.
+    pub fn expires_at(&self, ttl: Option<u64>) -> Option<Timestamp> {
+        let written_at = self
+            .metadata
+            .last_op
+            .as_ref()
+            .map_or(self.metadata.updated_at, |op| op.timestamp);
+        self.metadata
+            .expires_at
+            .or_else(|| ttl.map(|ttl| written_at.saturating_add(ttl)))
+    }
+
+    /// Check if the record is active but expired at `now`.
+    pub fn is_expired(&self, now: Timestamp, ttl: Option<u64>) -> bool {
+        self.is_active() && self.expires_at(ttl).is_some_and(|at| at <= now)
+    }
+
+    /// Check if record holds conflicting versions awaiting resolution.
+    pub fn is_conflicted(&self) -> bool {
+        !self.siblings.is_empty()
+    }
+
+    /// Keep a conflicting version, replacing any from the same operation.
+    pub fn add_sibling(&mut self, sibling: Sibling) {
+        match self
+            .siblings
+            .binary_search_by(|s| s.op_id.cmp(&sibling.op_id))
+        {
+            Ok(index) => self.siblings[index] = sibling,
+            Err(index) => self.siblings.insert(index, sibling),
+        }
+    }
+
     /// Mark record as deleted (tombstone).
     pub fn mark_deleted(&mut self, timestamp: Timestamp, clock: LogicalClock, origin: Origin) {
         self.deleted = true;
@@ -105,7 +283,22 @@ impl Record {
         self.metadata.update(timestamp, clock, origin);
     }
 
+    /// Revive a deleted record with a new payload.
+    pub fn restore(
+        &mut self,
+        payload: serde_json::Value,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+        origin: Origin,
+    ) {
+        self.deleted = false;
+        self.update_payload(payload, timestamp, clock, origin);
+    }
+
     /// Update record payload.
+    ///
+    /// Only the top-level fields whose value changes are stamped with the
+    /// new clock; the others keep the clock they were last written at.
     pub fn update_payload(
         &mut self,
         payload: serde_json::Value,
@@ -113,12 +306,241 @@ impl Record {
         clock: LogicalClock,
         origin: Origin,
     ) {
+        self.stamp_changed_fields(&payload, &clock);
         self.payload = payload;
         self.version += 1;
         self.metadata.update(timestamp, clock, origin);
     }
 }
 
+impl Record {
+    /// Merge fields written at `clock` into the payload.
+    ///
+    /// Unlike [`Record::update_payload`], the record clock never moves
+    /// backwards, so a field-level merge of an older concurrent write keeps
+    /// the clocks of the fields it lost.
+    pub fn merge_payload(
+        &mut self,
+        payload: serde_json::Value,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+        origin: Origin,
+    ) {
+        let previous = self.metadata.clock.clone();
+        self.update_payload(payload, timestamp, clock.clone(), origin);
+
+        if previous > clock {
+            if let Some(obj) = self.payload.as_object() {
+                for key in obj.keys() {
+                    self.metadata
+                        .field_clocks
+                        .entry(key.clone())
+                        .or_insert_with(|| clock.clone());
+                }
+            }
+            self.metadata.clock = previous;
+        }
+    }
+
+    /// Get the list state of a `List` field.
+    ///
+    /// The state is rebuilt from the payload when a full-payload write has
+    /// replaced the value since the last list operation.
+    pub fn list(&self, field: &str) -> ListCrdt {
+        let current = match self.payload.get(field) {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            _ => Vec::new(),
+        };
+
+        match self.crdt.get(field) {
+            Some(FieldCrdt::List(list)) if list.values() == current => list.clone(),
+            _ => ListCrdt::from_values(&current, self.metadata.field_clock(field)),
+        }
+    }
+
+    /// Get the text state of a `Text` field.
+    ///
+    /// The state is rebuilt from the payload when a full-payload write has
+    /// replaced the value since the last text operation.
+    pub fn text(&self, field: &str) -> TextCrdt {
+        let current = self
+            .payload
+            .get(field)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        match self.crdt.get(field) {
+            Some(FieldCrdt::Text(text)) if text.text() == current => text.clone(),
+            _ => TextCrdt::from_text(current, self.metadata.field_clock(field)),
+        }
+    }
+
+    /// Get the set state of a `Set` field.
+    ///
+    /// The state is rebuilt from the payload when a full-payload write has
+    /// replaced the value since the last set operation.
+    pub fn set(&self, field: &str) -> SetCrdt {
+        let mut current = match self.payload.get(field) {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            _ => Vec::new(),
+        };
+        // Full-payload writes need not keep the elements in order
+        current.sort_by_cached_key(|value| value.to_string());
+
+        match self.crdt.get(field) {
+            Some(FieldCrdt::Set(set)) if set.values() == current => set.clone(),
+            _ => SetCrdt::from_values(&current, self.metadata.field_clock(field)),
+        }
+    }
+
+    /// Apply a list operation to a `List` field.
+    ///
+    /// Returns `false` if the operation was already applied.
+    pub fn apply_list(
+        &mut self,
+        op: &ListOp,
+        timestamp: Timestamp,
+        origin: Origin,
+    ) -> Result<bool> {
+        let mut list = self.list(&op.field);
+        let applied = match &op.action {
+            ListAction::Insert { after, value } => {
+                list.insert(&op.op_id, &op.clock, after.as_ref(), value.clone())?
+            }
+            ListAction::Remove { element } => list.remove(element)?,
+            ListAction::Move { element, after } => {
+                list.move_element(&op.op_id, &op.clock, element, after.as_ref())?
+            }
+        };
+        if !applied {
+            return Ok(false);
+        }
+
+        let value = serde_json::Value::Array(list.values());
+        self.write_crdt(
+            &op.field,
+            value,
+            FieldCrdt::List(list),
+            timestamp,
+            op.clock.clone(),
+            origin,
+        );
+        Ok(true)
+    }
+
+    /// Apply a text operation to a `Text` field.
+    ///
+    /// Returns `false` if the operation was already applied.
+    pub fn apply_text(
+        &mut self,
+        op: &TextOp,
+        timestamp: Timestamp,
+        origin: Origin,
+    ) -> Result<bool> {
+        let mut text = self.text(&op.field);
+        if !text.apply(&op.op_id, &op.clock, &op.splice)? {
+            return Ok(false);
+        }
+
+        let value = serde_json::Value::String(text.text());
+        self.write_crdt(
+            &op.field,
+            value,
+            FieldCrdt::Text(text),
+            timestamp,
+            op.clock.clone(),
+            origin,
+        );
+        Ok(true)
+    }
+
+    /// Apply a set operation to a `Set` field.
+    ///
+    /// Returns `false` if the operation changes nothing.
+    pub fn apply_set(&mut self, op: &SetOp, timestamp: Timestamp, origin: Origin) -> Result<bool> {
+        let mut set = self.set(&op.field);
+        let applied = match &op.action {
+            SetAction::Add { value } => set.add(&op.op_id, value.clone()),
+            SetAction::Remove { value, observed } => set.remove(value, observed),
+        };
+        if !applied {
+            return Ok(false);
+        }
+
+        let value = serde_json::Value::Array(set.values());
+        self.write_crdt(
+            &op.field,
+            value,
+            FieldCrdt::Set(set),
+            timestamp,
+            op.clock.clone(),
+            origin,
+        );
+        Ok(true)
+    }
+
+    /// Store a CRDT field's new state and its materialized value.
+    fn write_crdt(
+        &mut self,
+        field: &str,
+        value: serde_json::Value,
+        state: FieldCrdt,
+        timestamp: Timestamp,
+        clock: LogicalClock,
+        origin: Origin,
+    ) {
+        let mut payload = self.payload.clone();
+        if let Some(obj) = payload.as_object_mut() {
+            obj.insert(field.to_string(), value);
+        }
+        self.crdt.insert(field.to_string(), state);
+        self.update_payload(payload, timestamp, clock, origin);
+    }
+
+    /// Top-level payload fields whose value differs in `payload`.
+    ///
+    /// Fields present on only one side count as changed.
+    pub fn changed_fields(&self, payload: &serde_json::Value) -> BTreeSet<String> {
+        match (self.payload.as_object(), payload.as_object()) {
+            (Some(old), Some(new)) => old
+                .keys()
+                .chain(new.keys())
+                .filter(|key| old.get(*key) != new.get(*key))
+                .cloned()
+                .collect(),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    fn stamp_changed_fields(&mut self, payload: &serde_json::Value, clock: &LogicalClock) {
+        let (Some(old), Some(new)) = (self.payload.as_object(), payload.as_object()) else {
+            self.metadata.field_clocks.clear();
+            return;
+        };
+
+        // Pin unchanged fields to the clock they were written at before the
+        // record clock moves on
+        let previous = self.metadata.clock.clone();
+        for key in old.keys().chain(new.keys()) {
+            if old.get(key) != new.get(key) {
+                self.metadata
+                    .field_clocks
+                    .insert(key.clone(), clock.clone());
+            } else {
+                self.metadata
+                    .field_clocks
+                    .entry(key.clone())
+                    .or_insert_with(|| previous.clone());
+            }
+        }
+
+        // Nothing to track when every field shares the record clock
+        if self.metadata.field_clocks.values().all(|c| c == clock) {
+            self.metadata.field_clocks.clear();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +577,59 @@ mod tests {
         assert_eq!(record.metadata.updated_at, 2000);
     }
 
+    #[test]
+    fn update_tracks_field_clocks() {
+        let clock1 = LogicalClock::with_counter("node-1", 1);
+        let mut record = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice", "age": 30}),
+            1000,
+            clock1.clone(),
+        );
+        assert!(record.metadata.field_clocks.is_empty());
+
+        let clock2 = LogicalClock::with_counter("node-1", 2);
+        record.update_payload(
+            json!({"name": "Alice", "age": 31}),
+            2000,
+            clock2.clone(),
+            Origin::Local,
+        );
+
+        assert_eq!(record.metadata.field_clock("name"), &clock1);
+        assert_eq!(record.metadata.field_clock("age"), &clock2);
+
+        // Rewriting every field collapses back to the record clock
+        let clock3 = LogicalClock::with_counter("node-1", 3);
+        record.update_payload(
+            json!({"name": "Bob", "age": 32}),
+            3000,
+            clock3.clone(),
+            Origin::Local,
+        );
+        assert!(record.metadata.field_clocks.is_empty());
+        assert_eq!(record.metadata.field_clock("name"), &clock3);
+    }
+
+    #[test]
+    fn changed_fields() {
+        let clock = LogicalClock::with_counter("node-1", 1);
+        let record = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "Alice", "age": 30}),
+            1000,
+            clock,
+        );
+
+        let changed = record.changed_fields(&json!({"name": "Alice", "email": "a@b.c"}));
+        assert_eq!(
+            changed.into_iter().collect::<Vec<_>>(),
+            vec!["age".to_string(), "email".to_string()]
+        );
+    }
+
     #[test]
     fn delete_record() {
         let clock = LogicalClock::with_counter("node-1", 1);
@@ -179,6 +654,28 @@ mod tests {
         assert_eq!(remote.origin, Origin::Remote);
     }
 
+    #[test]
+    fn expires_after_last_operation() {
+        let clock = LogicalClock::with_counter("node-1", 1);
+        let mut record = Record::new("s1", "sessions", json!({}), 5000, clock.clone());
+        assert_eq!(record.expires_at(None), None);
+        assert_eq!(record.expires_at(Some(1000)), Some(6000));
+
+        // Applied later than the operation was made
+        record.metadata.last_op = Some(LastOp {
+            op_id: "op-1".to_string(),
+            clock,
+            timestamp: 2000,
+        });
+        assert_eq!(record.expires_at(Some(1000)), Some(3000));
+        assert!(record.is_expired(3000, Some(1000)));
+
+        // Its own expiry overrides the TTL
+        record.metadata.expires_at = Some(9000);
+        assert_eq!(record.expires_at(Some(1000)), Some(9000));
+        assert!(!record.is_expired(3000, Some(1000)));
+    }
+
     #[test]
     fn serialization_roundtrip() {
         let clock = LogicalClock::with_counter("node-1", 1);
@@ -0,0 +1,165 @@
+//! Pluggable conflict resolution.
+//!
+//! A [`ConflictResolver`] replaces the built-in [`MergeStrategy`] for the
+//! collections it is registered on. It is consulted whenever the
+//! [`Reconciler`] detects a conflict on a record of that collection.
+//!
+//! # Determinism
+//!
+//! Every replica must reach the same state from the same operations, so a
+//! resolver must be a pure function of its inputs:
+//!
+//! - No wall-clock time, randomness, IO or mutable state
+//! - Symmetric in its operations: the replica that authored one operation
+//!   sees it as `local`, while the other replica sees it as `remote`, so the
+//!   decision must follow from the operations themselves (their clocks,
+//!   payloads, IDs) rather than from which side they arrived on
+//!
+//! [`MergeStrategy`]: crate::MergeStrategy
+//! [`Reconciler`]: crate::Reconciler
+
+use crate::{CollectionName, Operation, Record};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Decision returned by a [`ConflictResolver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    /// Keep the local operation
+    Local,
+    /// Keep the remote operation
+    Remote,
+    /// Replace the record payload with a merged one.
+    ///
+    /// The payload is validated against the collection schema; an invalid
+    /// payload falls back to the reconciler's merge strategy.
+    Merged(serde_json::Value),
+}
+
+/// Custom conflict resolution for a collection.
+///
+/// See the [module documentation](self) for the determinism contract.
+pub trait ConflictResolver: Send + Sync {
+    /// Resolve a conflict between a local and a remote operation.
+    ///
+    /// `current` is the record as it stands before the later of the two
+    /// operations is applied.
+    fn resolve(&self, local: &Operation, remote: &Operation, current: &Record) -> Resolution;
+}
+
+impl<F> ConflictResolver for F
+where
+    F: Fn(&Operation, &Operation, &Record) -> Resolution + Send + Sync,
+{
+    fn resolve(&self, local: &Operation, remote: &Operation, current: &Record) -> Resolution {
+        self(local, remote, current)
+    }
+}
+
+/// Conflict resolvers registered by collection.
+#[derive(Clone, Default)]
+pub struct ResolverRegistry {
+    resolvers: HashMap<CollectionName, Arc<dyn ConflictResolver>>,
+}
+
+impl ResolverRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a resolver for a collection, replacing any previous one.
+    pub fn register(
+        &mut self,
+        collection: impl Into<CollectionName>,
+        resolver: Arc<dyn ConflictResolver>,
+    ) {
+        self.resolvers.insert(collection.into(), resolver);
+    }
+
+    /// Remove the resolver for a collection.
+    pub fn unregister(&mut self, collection: &str) {
+        self.resolvers.remove(collection);
+    }
+
+    /// Get the resolver for a collection.
+    pub fn get(&self, collection: &str) -> Option<&Arc<dyn ConflictResolver>> {
+        self.resolvers.get(collection)
+    }
+
+    /// Check if no resolvers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+}
+
+impl fmt::Debug for ResolverRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut collections: Vec<_> = self.resolvers.keys().collect();
+        collections.sort();
+        f.debug_struct("ResolverRegistry")
+            .field("collections", &collections)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CreateOp, LogicalClock};
+    use serde_json::json;
+
+    fn create(op_id: &str, node: &str, counter: u64) -> Operation {
+        Operation::Create(CreateOp::new(
+            op_id,
+            "user-1",
+            "users",
+            json!({"name": op_id}),
+            1000,
+            LogicalClock::with_counter(node, counter),
+        ))
+    }
+
+    #[test]
+    fn closure_resolver() {
+        let mut registry = ResolverRegistry::new();
+        registry.register(
+            "users",
+            Arc::new(|_: &Operation, _: &Operation, _: &Record| Resolution::Remote),
+        );
+
+        let local = create("op-local", "local", 1);
+        let remote = create("op-remote", "remote", 1);
+        let record = Record::new(
+            "user-1",
+            "users",
+            json!({"name": "op-local"}),
+            1000,
+            LogicalClock::with_counter("local", 1),
+        );
+
+        let resolver = registry.get("users").unwrap();
+        assert_eq!(
+            resolver.resolve(&local, &remote, &record),
+            Resolution::Remote
+        );
+        assert!(registry.get("posts").is_none());
+    }
+
+    #[test]
+    fn registry_debug_lists_collections() {
+        let mut registry = ResolverRegistry::new();
+        registry.register(
+            "users",
+            Arc::new(|_: &Operation, _: &Operation, _: &Record| Resolution::Local),
+        );
+        assert_eq!(
+            format!("{:?}", registry),
+            r#"ResolverRegistry { collections: ["users"] }"#
+        );
+
+        registry.unregister("users");
+        assert!(registry.is_empty());
+    }
+}
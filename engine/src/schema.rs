@@ -3,9 +3,12 @@
 //! Schemas define the structure of collections and enable validation
 //! of operations before they are applied.
 
-use crate::{error::Result, CollectionName, Error, Operation, SchemaVersion};
+use crate::{
+    error::Result, CollectionName, DeletePolicy, Error, IncrementOp, MergeStrategy, Operation,
+    PatchOp, SchemaVersion,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Field types supported in schemas.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,6 +21,13 @@ pub enum FieldType {
     Timestamp,
     /// Arbitrary nested JSON
     Json,
+    /// Ordered list edited with list operations, see [`ListCrdt`](crate::ListCrdt)
+    List,
+    /// String edited with text splices, see [`TextCrdt`](crate::TextCrdt)
+    Text,
+    /// Array of distinct values edited with set operations, see
+    /// [`SetCrdt`](crate::SetCrdt)
+    Set,
 }
 
 impl std::fmt::Display for FieldType {
@@ -29,6 +39,9 @@ impl std::fmt::Display for FieldType {
             FieldType::Bool => write!(f, "Bool"),
             FieldType::Timestamp => write!(f, "Timestamp"),
             FieldType::Json => write!(f, "Json"),
+            FieldType::List => write!(f, "List"),
+            FieldType::Text => write!(f, "Text"),
+            FieldType::Set => write!(f, "Set"),
         }
     }
 }
@@ -85,17 +98,30 @@ impl FieldDef {
             FieldType::Bool => value.is_boolean(),
             FieldType::Timestamp => value.is_u64() || value.is_i64(),
             FieldType::Json => true, // Any JSON is valid
+            FieldType::List => value.is_array(),
+            FieldType::Text => value.is_string(),
+            FieldType::Set => value.is_array(),
         };
 
-        if valid {
-            Ok(())
-        } else {
-            Err(Error::TypeMismatch {
+        if !valid {
+            return Err(Error::TypeMismatch {
                 field: self.name.clone(),
                 expected: self.field_type.to_string(),
                 got: json_type_name(value).to_string(),
-            })
+            });
+        }
+
+        if let (FieldType::Set, Some(items)) = (self.field_type, value.as_array()) {
+            let distinct: HashSet<String> = items.iter().map(|item| item.to_string()).collect();
+            if distinct.len() != items.len() {
+                return Err(Error::InvalidPayload(format!(
+                    "set field '{}' contains duplicate values",
+                    self.name
+                )));
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -119,6 +145,22 @@ pub struct CollectionSchema {
     pub name: CollectionName,
     /// Field definitions
     pub fields: Vec<FieldDef>,
+    /// Merge strategy for conflicts in this collection, overriding the
+    /// strategy passed to the reconciler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_strategy: Option<MergeStrategy>,
+    /// How a delete racing a write to the same record is decided, overriding
+    /// the merge strategy's rule for such conflicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_policy: Option<DeletePolicy>,
+    /// Number of versions of each record the store keeps in its history
+    /// log; no history is kept when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_limit: Option<usize>,
+    /// Milliseconds after the operation that last wrote them at which
+    /// records expire; records never expire when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
 }
 
 impl CollectionSchema {
@@ -127,6 +169,79 @@ impl CollectionSchema {
         Self {
             name: name.into(),
             fields,
+            merge_strategy: None,
+            delete_policy: None,
+            history_limit: None,
+            ttl: None,
+        }
+    }
+
+    /// Builder-style method to set the collection's merge strategy.
+    pub fn with_merge_strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.merge_strategy = Some(strategy);
+        self
+    }
+
+    /// Builder-style method to set the collection's delete policy.
+    pub fn with_delete_policy(mut self, policy: DeletePolicy) -> Self {
+        self.delete_policy = Some(policy);
+        self
+    }
+
+    /// Builder-style method to keep the last `limit` versions of each
+    /// record in the store's history log.
+    pub fn with_history(mut self, limit: usize) -> Self {
+        self.history_limit = Some(limit);
+        self
+    }
+
+    /// Builder-style method to expire records `ttl` milliseconds after
+    /// the operation that last wrote them.
+    pub fn with_ttl(mut self, ttl: u64) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Get a field definition by name.
+    pub fn field(&self, name: &str) -> Option<&FieldDef> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Check that `delta` can be added to a field, returning its type.
+    fn validate_increment(&self, field: &str, delta: &serde_json::Number) -> Result<FieldType> {
+        let def = self.field(field).ok_or_else(|| {
+            Error::InvalidPayload(format!("cannot increment unknown field '{field}'"))
+        })?;
+
+        match def.field_type {
+            FieldType::Int if !delta.is_i64() => Err(Error::TypeMismatch {
+                field: field.to_string(),
+                expected: FieldType::Int.to_string(),
+                got: FieldType::Float.to_string(),
+            }),
+            FieldType::Int | FieldType::Float => Ok(def.field_type),
+            other => Err(Error::TypeMismatch {
+                field: field.to_string(),
+                expected: "Int or Float".to_string(),
+                got: other.to_string(),
+            }),
+        }
+    }
+
+    /// Check that a CRDT operation targets a field of the matching type.
+    fn validate_crdt(&self, field: &str, expected: FieldType) -> Result<()> {
+        let def = self
+            .field(field)
+            .ok_or_else(|| Error::InvalidCrdt(format!("unknown {expected} field '{field}'")))?;
+
+        if def.field_type == expected {
+            Ok(())
+        } else {
+            Err(Error::TypeMismatch {
+                field: field.to_string(),
+                expected: expected.to_string(),
+                got: def.field_type.to_string(),
+            })
         }
     }
 
@@ -189,7 +304,7 @@ impl Schema {
             .get(collection_name)
             .ok_or_else(|| Error::CollectionNotFound(collection_name.clone()))?;
 
-        // Validate payload for create and update operations
+        // Validate payload for operations that carry a full payload
         match op {
             Operation::Create(create_op) => {
                 collection_schema.validate_payload(&create_op.payload)?;
@@ -197,19 +312,116 @@ impl Schema {
             Operation::Update(update_op) => {
                 collection_schema.validate_payload(&update_op.payload)?;
             }
+            Operation::Patch(patch_op) => {
+                // The resulting payload is checked by `validate_patch` once
+                // the current record is known
+                patch_op.patch.validate()?;
+            }
             Operation::Delete(_) => {
                 // Delete operations don't need payload validation
             }
+            Operation::Restore(restore_op) => {
+                collection_schema.validate_payload(&restore_op.payload)?;
+            }
+            Operation::Upsert(upsert_op) => {
+                collection_schema.validate_payload(&upsert_op.payload)?;
+            }
+            Operation::Increment(increment_op) => {
+                // The resulting payload is checked by `validate_increment`
+                // once the current record is known
+                collection_schema.validate_increment(&increment_op.field, &increment_op.delta)?;
+            }
+            Operation::List(list_op) => {
+                collection_schema.validate_crdt(&list_op.field, FieldType::List)?;
+            }
+            Operation::Text(text_op) => {
+                collection_schema.validate_crdt(&text_op.field, FieldType::Text)?;
+            }
+            Operation::Set(set_op) => {
+                collection_schema.validate_crdt(&set_op.field, FieldType::Set)?;
+            }
         }
 
         Ok(())
     }
+
+    /// Apply a patch operation to the current payload and validate the result.
+    ///
+    /// Returns the patched payload on success.
+    pub fn validate_patch(
+        &self,
+        op: &PatchOp,
+        current: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let collection_schema = self
+            .collections
+            .get(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
+
+        let payload = op.patch.apply(current)?;
+        collection_schema.validate_payload(&payload)?;
+        Ok(payload)
+    }
+
+    /// Apply an increment operation to the current payload and validate the
+    /// result.
+    ///
+    /// Returns the incremented payload on success.
+    pub fn validate_increment(
+        &self,
+        op: &IncrementOp,
+        current: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let collection_schema = self
+            .collections
+            .get(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
+
+        let field_type = collection_schema.validate_increment(&op.field, &op.delta)?;
+
+        let mut payload = current.clone();
+        let obj = payload
+            .as_object_mut()
+            .ok_or_else(|| Error::InvalidPayload("payload must be an object".into()))?;
+
+        let zero = serde_json::Value::from(0);
+        let value = match obj.get(&op.field) {
+            None | Some(serde_json::Value::Null) => &zero,
+            Some(value) => value,
+        };
+        let mismatch = || Error::TypeMismatch {
+            field: op.field.clone(),
+            expected: field_type.to_string(),
+            got: json_type_name(value).to_string(),
+        };
+
+        let sum = if field_type == FieldType::Int {
+            let current = value.as_i64().ok_or_else(mismatch)?;
+            let delta = op.delta.as_i64().ok_or_else(mismatch)?;
+            let sum = current.checked_add(delta).ok_or_else(|| {
+                Error::InvalidPayload(format!("increment of '{}' overflows", op.field))
+            })?;
+            serde_json::Value::from(sum)
+        } else {
+            let current = value.as_f64().ok_or_else(mismatch)?;
+            let delta = op.delta.as_f64().ok_or_else(mismatch)?;
+            serde_json::Number::from_f64(current + delta)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| {
+                    Error::InvalidPayload(format!("increment of '{}' is not finite", op.field))
+                })?
+        };
+
+        obj.insert(op.field.clone(), sum);
+        collection_schema.validate_payload(&payload)?;
+        Ok(payload)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::operation::CreateOp;
+    use crate::operation::{CreateOp, IncrementOp};
     use crate::LogicalClock;
     use serde_json::json;
 
@@ -258,6 +470,54 @@ mod tests {
         assert!(matches!(result, Err(Error::TypeMismatch { field, .. }) if field == "age"));
     }
 
+    #[test]
+    fn validate_increment() {
+        let schema = Schema::new(1).with_collection(CollectionSchema::new(
+            "items",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("stock", FieldType::Int),
+                FieldDef::optional("rating", FieldType::Float),
+            ],
+        ));
+        let clock = LogicalClock::with_counter("node-1", 1);
+        let increment = |field: &str, delta: serde_json::Number| {
+            IncrementOp::new("op-1", "item-1", "items", field, delta, 1000, clock.clone())
+        };
+        let current = json!({"name": "Widget", "stock": 5});
+
+        // Missing fields count as zero
+        let payload = schema
+            .validate_increment(&increment("stock", (-2).into()), &current)
+            .unwrap();
+        assert_eq!(payload["stock"], 3);
+        let rating = serde_json::Number::from_f64(0.5).unwrap();
+        let payload = schema
+            .validate_increment(&increment("rating", rating.clone()), &current)
+            .unwrap();
+        assert_eq!(payload["rating"], 0.5);
+
+        // Int fields only take whole deltas, and only numbers increment
+        let op = Operation::Increment(increment("stock", rating));
+        assert!(matches!(
+            schema.validate_operation(&op),
+            Err(Error::TypeMismatch { field, .. }) if field == "stock"
+        ));
+        let op = Operation::Increment(increment("name", 1.into()));
+        assert!(schema.validate_operation(&op).is_err());
+        let op = Operation::Increment(increment("missing", 1.into()));
+        assert!(matches!(
+            schema.validate_operation(&op),
+            Err(Error::InvalidPayload(_))
+        ));
+
+        // Overflow is rejected rather than wrapping
+        let full = json!({"name": "Widget", "stock": i64::MAX});
+        assert!(schema
+            .validate_increment(&increment("stock", 1.into()), &full)
+            .is_err());
+    }
+
     #[test]
     fn validate_null_required_field() {
         let schema = test_schema();
@@ -312,6 +572,49 @@ mod tests {
         assert!(schema.validate_operation(&invalid_op).is_err());
     }
 
+    #[test]
+    fn validate_patch_operation() {
+        use crate::{JsonPatchOperation, Patch};
+
+        let schema = test_schema();
+        let clock = LogicalClock::with_counter("node-1", 2);
+        let current = json!({"name": "Alice", "age": 30});
+
+        let valid = PatchOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            Patch::MergePatch(json!({"age": 31})),
+            1,
+            2000,
+            clock.clone(),
+        );
+        assert!(schema
+            .validate_operation(&Operation::Patch(valid.clone()))
+            .is_ok());
+        assert_eq!(
+            schema.validate_patch(&valid, &current).unwrap(),
+            json!({"name": "Alice", "age": 31})
+        );
+
+        // Removing a required field fails against the resulting payload
+        let invalid = PatchOp::new(
+            "op-3",
+            "user-1",
+            "users",
+            Patch::JsonPatch(vec![JsonPatchOperation::Remove {
+                path: "/age".into(),
+            }]),
+            1,
+            2000,
+            clock,
+        );
+        assert!(matches!(
+            schema.validate_patch(&invalid, &current),
+            Err(Error::MissingRequiredField(f)) if f == "age"
+        ));
+    }
+
     #[test]
     fn field_type_display() {
         assert_eq!(FieldType::String.to_string(), "String");
@@ -327,6 +630,57 @@ mod tests {
         assert_eq!(schema, parsed);
     }
 
+    #[test]
+    fn collection_merge_strategy_from_json() {
+        let json = json!({
+            "version": 1,
+            "collections": {
+                "todos": {
+                    "name": "todos",
+                    "fields": [{"name": "title", "fieldType": "string", "required": true}],
+                    "mergeStrategy": "deleteWins",
+                    "deletePolicy": "updateWins"
+                },
+                "notes": {
+                    "name": "notes",
+                    "fields": []
+                }
+            }
+        });
+
+        let schema: Schema = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            schema.get_collection("todos").unwrap().merge_strategy,
+            Some(MergeStrategy::DeleteWins)
+        );
+        assert_eq!(schema.get_collection("notes").unwrap().merge_strategy, None);
+        assert_eq!(
+            schema.get_collection("todos").unwrap().delete_policy,
+            Some(DeletePolicy::UpdateWins)
+        );
+        assert_eq!(schema.get_collection("notes").unwrap().delete_policy, None);
+
+        // Unset strategies are left out of the serialized schema
+        let notes = serde_json::to_value(schema.get_collection("notes").unwrap()).unwrap();
+        assert!(notes.get("mergeStrategy").is_none());
+        assert!(notes.get("deletePolicy").is_none());
+    }
+
+    #[test]
+    fn collection_ttl() {
+        let sessions = CollectionSchema::new("sessions", vec![]).with_ttl(60_000);
+        assert_eq!(sessions.ttl, Some(60_000));
+
+        let value = serde_json::to_value(&sessions).unwrap();
+        assert_eq!(value["ttl"], json!(60_000));
+        let parsed: CollectionSchema = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.ttl, Some(60_000));
+
+        // Collections without a TTL leave it out
+        let notes = serde_json::to_value(CollectionSchema::new("notes", vec![])).unwrap();
+        assert!(notes.get("ttl").is_none());
+    }
+
     #[test]
     fn json_field_accepts_any() {
         let collection =
@@ -345,4 +699,19 @@ mod tests {
             .validate_payload(&json!({"data": {"nested": "object"}}))
             .is_ok());
     }
+
+    #[test]
+    fn set_field_rejects_duplicates() {
+        let field = FieldDef::optional("tags", FieldType::Set);
+
+        assert!(field.validate(Some(&json!(["a", "b", 1]))).is_ok());
+        assert!(matches!(
+            field.validate(Some(&json!(["a", "b", "a"]))),
+            Err(Error::InvalidPayload(_))
+        ));
+        assert!(matches!(
+            field.validate(Some(&json!("a"))),
+            Err(Error::TypeMismatch { .. })
+        ));
+    }
 }
@@ -5,7 +5,7 @@
 
 use crate::{
     error::Result, CollectionName, Error, LogicalClock, NodeId, PendingOp, Record, RecordId,
-    Schema, SchemaVersion,
+    RecordVersion, Schema, SchemaVersion,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -33,6 +33,10 @@ pub struct StoreSnapshot {
     pub collections: BTreeMap<CollectionName, BTreeMap<RecordId, Record>>,
     /// Pending operations not yet synced
     pub pending_ops: Vec<PendingOp>,
+    /// Logged versions of records in collections that keep history,
+    /// organized by collection, then by record ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub history: BTreeMap<CollectionName, BTreeMap<RecordId, Vec<RecordVersion>>>,
 }
 
 impl StoreSnapshot {
@@ -46,6 +50,7 @@ impl StoreSnapshot {
             clock: LogicalClock::new(node_id),
             collections: BTreeMap::new(),
             pending_ops: Vec::new(),
+            history: BTreeMap::new(),
         }
     }
 
@@ -235,6 +240,7 @@ mod tests {
                 clock,
             )),
             applied_at: 1000,
+            folded: Vec::new(),
         };
         snapshot.add_pending(pending);
 
@@ -360,6 +366,7 @@ mod tests {
                 clock,
             )),
             applied_at: 1000,
+            folded: Vec::new(),
         });
 
         let metadata: SnapshotMetadata = (&snapshot).into();
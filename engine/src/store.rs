@@ -4,17 +4,23 @@
 //! locally and tracks what needs to be synced.
 
 use crate::{
-    error::Result, CollectionName, Error, LogicalClock, NodeId, Operation, OperationId, Record,
-    RecordId, Schema, Timestamp, Version,
+    error::Result, record::LastOp, CollectionName, ConflictResolver, Error, HistoryPoint,
+    LogicalClock, NodeId, Operation, OperationId, Record, RecordId, RecordVersion,
+    ResolverRegistry, Schema, Timestamp, TransactionId, UndoChange, UndoManager, UndoStep, Version,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// A collection of records.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Collection {
     records: HashMap<RecordId, Record>,
+    /// Versions of each record, oldest first, for collections that keep
+    /// history
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    history: HashMap<RecordId, Vec<RecordVersion>>,
 }
 
 impl Collection {
@@ -22,6 +28,7 @@ impl Collection {
     pub fn new() -> Self {
         Self {
             records: HashMap::new(),
+            history: HashMap::new(),
         }
     }
 
@@ -40,6 +47,11 @@ impl Collection {
         self.records.insert(record.id.clone(), record);
     }
 
+    /// Remove a record, returning it if present.
+    pub fn remove(&mut self, id: &str) -> Option<Record> {
+        self.records.remove(id)
+    }
+
     /// Check if a record exists (including deleted).
     pub fn contains(&self, id: &str) -> bool {
         self.records.contains_key(id)
@@ -64,6 +76,25 @@ impl Collection {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Get the logged versions of a record, oldest first.
+    pub fn history(&self, id: &str) -> &[RecordVersion] {
+        self.history.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Log a record's current version, keeping at most `limit` versions.
+    fn log_version(&mut self, id: &str, op_id: Option<OperationId>, limit: usize) {
+        let Some(record) = self.records.get(id) else {
+            return;
+        };
+        let versions = self.history.entry(id.to_string()).or_default();
+        versions.push(RecordVersion::of(record, op_id));
+        let excess = versions.len().saturating_sub(limit);
+        versions.drain(..excess);
+        if versions.is_empty() {
+            self.history.remove(id);
+        }
+    }
 }
 
 /// Result of applying an operation.
@@ -78,6 +109,32 @@ pub struct ApplyResult {
     pub version: Version,
 }
 
+/// A record as it is before and after a reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordDiff {
+    /// Collection of the record
+    pub collection: CollectionName,
+    /// Record ID
+    pub id: RecordId,
+    /// Record before reconciliation (`None` if it did not exist)
+    pub before: Option<Record>,
+    /// Record after reconciliation
+    pub after: Record,
+}
+
+/// Outcome of a reconciliation that has not been applied.
+///
+/// Returned by [`Store::preview_reconcile`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcilePreview {
+    /// Result the reconciliation would produce
+    pub result: crate::reconcile::ReconcileResult,
+    /// Records the reconciliation would change
+    pub records: Vec<RecordDiff>,
+}
+
 /// A pending operation waiting to be synced.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -86,8 +143,93 @@ pub struct PendingOp {
     pub operation: Operation,
     /// When it was applied locally
     pub applied_at: Timestamp,
+    /// IDs of earlier operations folded into this one by
+    /// [`Store::compact_pending`]. Acknowledging this operation
+    /// acknowledges them too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folded: Vec<OperationId>,
 }
 
+impl PendingOp {
+    /// IDs of the operations this entry stands for: the folded ones, then
+    /// its own.
+    pub fn op_ids(&self) -> impl Iterator<Item = &OperationId> {
+        self.folded
+            .iter()
+            .chain(std::iter::once(self.operation.op_id()))
+    }
+}
+
+/// Add the change from `before` to `after` to a collection's change set.
+fn record_change(
+    changes: &mut crate::reconcile::CollectionChanges,
+    before: Option<&Record>,
+    after: &Record,
+) {
+    let was_visible = before.is_some_and(|r| r.is_active());
+    let change = crate::reconcile::RecordChange {
+        id: after.id.clone(),
+        old_version: before.map(|r| r.version),
+        new_version: after.version,
+    };
+
+    match (was_visible, after.is_active()) {
+        (false, true) => changes.inserted.push(change),
+        (true, false) => changes.deleted.push(change),
+        (true, true) if before.is_some_and(|r| r.payload != after.payload) => {
+            changes.updated.push(change)
+        }
+        _ => {}
+    }
+}
+
+/// Fold a pending operation into the previous one on the same record.
+///
+/// Returns `None` if they cannot be folded and `Some(None)` if they cancel
+/// each other out.
+fn fold_pending(previous: &PendingOp, next: &PendingOp) -> Option<Option<PendingOp>> {
+    // Transactions are pushed as a unit
+    if previous.operation.transaction_id().is_some() || next.operation.transaction_id().is_some() {
+        return None;
+    }
+
+    let operation = match (&previous.operation, &next.operation) {
+        (Operation::Create(create), Operation::Update(update)) => {
+            let mut create = create.clone();
+            create.op_id = update.op_id.clone();
+            create.payload = update.payload.clone();
+            create.timestamp = update.timestamp;
+            create.clock = update.clock.clone();
+            Operation::Create(create)
+        }
+        (Operation::Update(first), Operation::Update(second)) => {
+            let mut update = second.clone();
+            update.base_version = first.base_version;
+            update.context = first.context.clone();
+            update.changed_fields = match (&first.changed_fields, &second.changed_fields) {
+                (Some(a), Some(b)) => Some(a.union(b).cloned().collect()),
+                _ => None,
+            };
+            Operation::Update(update)
+        }
+        (Operation::Create(_), Operation::Delete(_)) => return Some(None),
+        _ => return None,
+    };
+
+    Some(Some(PendingOp {
+        operation,
+        applied_at: next.applied_at,
+        folded: previous
+            .op_ids()
+            .chain(next.folded.iter())
+            .cloned()
+            .collect(),
+    }))
+}
+
+/// Node ID of the clocks on deletes written by [`Store::expire`].
+const EXPIRE_NODE_ID: &str = "expire";
+
 /// The main store holding all state.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -102,6 +244,15 @@ pub struct Store {
     collections: HashMap<CollectionName, Collection>,
     /// Operations pending sync
     pending_ops: Vec<PendingOp>,
+    /// Custom conflict resolvers (not persisted, register after loading)
+    #[serde(skip)]
+    resolvers: ResolverRegistry,
+    /// Undo and redo steps of local changes (not persisted)
+    #[serde(skip)]
+    undo: UndoManager,
+    /// Maximum milliseconds an incoming clock may run ahead of wall time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_drift: Option<u64>,
 }
 
 impl Store {
@@ -122,6 +273,9 @@ impl Store {
             clock,
             collections,
             pending_ops: Vec::new(),
+            resolvers: ResolverRegistry::new(),
+            undo: UndoManager::default(),
+            max_drift: None,
         }
     }
 
@@ -140,38 +294,290 @@ impl Store {
         &self.schema
     }
 
+    /// Register a conflict resolver for a collection.
+    ///
+    /// It takes precedence over the merge strategy passed to
+    /// [`Store::reconcile`] for conflicts on that collection.
+    pub fn register_resolver(
+        &mut self,
+        collection: impl Into<CollectionName>,
+        resolver: Arc<dyn ConflictResolver>,
+    ) {
+        self.resolvers.register(collection, resolver);
+    }
+
+    /// Remove the conflict resolver for a collection.
+    pub fn unregister_resolver(&mut self, collection: &str) {
+        self.resolvers.unregister(collection);
+    }
+
     /// Tick the clock and return a clone of the new value.
     pub fn tick(&mut self) -> LogicalClock {
         self.clock.tick();
         self.clock.clone()
     }
 
+    /// Advance the clock as a hybrid logical clock at `wall_time` and return
+    /// the new value.
+    ///
+    /// Unlike [`Store::tick`], the counter tracks physical time, so clock-wins
+    /// resolution follows real time across devices. See [`HybridClock`].
+    ///
+    /// [`HybridClock`]: crate::HybridClock
+    pub fn tick_at(&mut self, wall_time: Timestamp) -> LogicalClock {
+        let mut hybrid = crate::HybridClock::from_clock(&self.clock);
+        self.clock = hybrid.now(wall_time);
+        self.clock.clone()
+    }
+
+    /// Get the maximum drift (milliseconds) accepted from incoming clocks.
+    pub fn max_drift(&self) -> Option<u64> {
+        self.max_drift
+    }
+
+    /// Set the maximum drift (milliseconds) accepted from incoming clocks,
+    /// or `None` to accept any clock.
+    ///
+    /// With a maximum set, incoming clocks advance the store clock through
+    /// [`HybridClock::receive`], so it becomes a hybrid clock. [`Store::apply`]
+    /// fails with [`Error::ClockDrift`] on operations whose clock runs too
+    /// far ahead of the apply timestamp, and [`Store::reconcile_at`] rejects
+    /// such remote operations.
+    ///
+    /// [`HybridClock::receive`]: crate::HybridClock::receive
+    pub fn set_max_drift(&mut self, max_drift: Option<u64>) {
+        self.max_drift = max_drift;
+    }
+
+    /// Advance the store clock past an incoming clock received at
+    /// `wall_time`, checking its drift if a maximum is set.
+    fn receive_clock(&mut self, remote: &LogicalClock, wall_time: Timestamp) -> Result<()> {
+        match self.max_drift {
+            Some(max_drift) => {
+                let mut hybrid =
+                    crate::HybridClock::from_clock(&self.clock).with_max_drift(max_drift);
+                self.clock = hybrid.receive(remote, wall_time)?;
+            }
+            None => self.clock.merge(remote),
+        }
+        Ok(())
+    }
+
+    /// Check an incoming clock against the maximum drift without advancing
+    /// the store clock.
+    fn check_drift(&self, remote: &LogicalClock, wall_time: Timestamp) -> Result<()> {
+        if let Some(max_drift) = self.max_drift {
+            crate::HybridClock::from_clock(&self.clock)
+                .with_max_drift(max_drift)
+                .receive(remote, wall_time)?;
+        }
+        Ok(())
+    }
+
     /// Apply an operation to the store.
     ///
     /// This validates the operation, applies it, and adds it to pending ops.
+    /// The change can be reverted with [`Store::undo`].
     pub fn apply(&mut self, op: Operation, timestamp: Timestamp) -> Result<ApplyResult> {
+        let (collection, id) = (op.collection().clone(), op.record_id().clone());
+        let before =
+            crate::undo::visible_payload(self.get_including_deleted(&collection, &id)).cloned();
+
+        let result = self.apply_op(op, timestamp)?;
+
+        let after = self.get(&collection, &id).map(|r| r.payload.clone());
+        if before != after {
+            self.undo.record(UndoStep {
+                changes: vec![UndoChange {
+                    collection,
+                    id,
+                    before,
+                    after,
+                }],
+            });
+        }
+        Ok(result)
+    }
+
+    /// Apply an operation without recording an undo step.
+    fn apply_op(&mut self, op: Operation, timestamp: Timestamp) -> Result<ApplyResult> {
         // Validate against schema
         self.schema.validate_operation(&op)?;
 
-        // Update clock from operation
-        self.clock.merge(op.clock());
+        // Update clock from operation, rejecting far-future clocks
+        self.receive_clock(op.clock(), timestamp)?;
+
+        // Capture what the operation was made against
+        let mut op = match self.get_including_deleted(op.collection(), op.record_id()) {
+            Some(record) if op.context().is_none() => {
+                let context = record.metadata.version_vector.clone();
+                op.with_context(context)
+            }
+            _ => op,
+        };
+
+        // A write made with every version of a conflicted record in view
+        // resolves the conflict
+        let resolves = self
+            .get_including_deleted(op.collection(), op.record_id())
+            .zip(op.context())
+            .is_some_and(|(record, context)| context.dominates(&record.metadata.version_vector));
 
         // Apply the operation
-        let result = match &op {
+        let result = match &mut op {
             Operation::Create(create_op) => self.apply_create(create_op, timestamp)?,
             Operation::Update(update_op) => self.apply_update(update_op, timestamp)?,
+            Operation::Patch(patch_op) => self.apply_patch(patch_op, timestamp)?,
             Operation::Delete(delete_op) => self.apply_delete(delete_op, timestamp)?,
+            Operation::Restore(restore_op) => self.apply_restore(restore_op, timestamp)?,
+            Operation::Upsert(upsert_op) => self.apply_upsert(upsert_op, timestamp)?,
+            Operation::Increment(increment_op) => self.apply_increment(increment_op, timestamp)?,
+            Operation::List(list_op) => self.apply_list(list_op, timestamp)?,
+            Operation::Text(text_op) => self.apply_text(text_op, timestamp)?,
+            Operation::Set(set_op) => self.apply_set(set_op, timestamp)?,
         };
 
+        // Remember the write for reconciliation; commutative operations
+        // never conflict, so they leave the last operation in place
+        if !op.is_commutative() {
+            if let Some(record) = self
+                .collections
+                .get_mut(op.collection())
+                .and_then(|c| c.get_mut(op.record_id()))
+            {
+                record.metadata.last_op = Some(LastOp::of(&op));
+                if resolves {
+                    record.siblings.clear();
+                }
+            }
+        }
+
+        // Log the version the operation wrote
+        if let Some(limit) = self.history_limit(op.collection()) {
+            if let Some(collection) = self.collections.get_mut(op.collection()) {
+                collection.log_version(op.record_id(), Some(op.op_id().clone()), limit);
+            }
+        }
+
         // Track as pending
         self.pending_ops.push(PendingOp {
             operation: op,
             applied_at: timestamp,
+            folded: Vec::new(),
         });
 
         Ok(result)
     }
 
+    /// Apply a group of operations atomically.
+    ///
+    /// Operations are applied in order. If any of them fails, every change
+    /// made by the batch is rolled back and the error is returned. Applied
+    /// operations share a transaction ID (the first operation's ID), so
+    /// reconciliation and the server accept or reject them together.
+    ///
+    /// The batch is a single step for [`Store::undo`].
+    pub fn apply_batch(
+        &mut self,
+        ops: Vec<Operation>,
+        timestamp: Timestamp,
+    ) -> Result<Vec<ApplyResult>> {
+        let mut records: Vec<(CollectionName, RecordId)> = Vec::new();
+        for op in &ops {
+            let key = (op.collection().clone(), op.record_id().clone());
+            if !records.contains(&key) {
+                records.push(key);
+            }
+        }
+        let before: Vec<_> = records
+            .iter()
+            .map(|(collection, id)| {
+                crate::undo::visible_payload(self.get_including_deleted(collection, id)).cloned()
+            })
+            .collect();
+
+        let results = self.apply_batch_ops(ops, timestamp)?;
+
+        let changes: Vec<UndoChange> = records
+            .into_iter()
+            .zip(before)
+            .filter_map(|((collection, id), before)| {
+                let after = self.get(&collection, &id).map(|r| r.payload.clone());
+                (before != after).then_some(UndoChange {
+                    collection,
+                    id,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.undo.record(UndoStep { changes });
+        }
+        Ok(results)
+    }
+
+    /// Apply a group of operations atomically without recording an undo
+    /// step.
+    fn apply_batch_ops(
+        &mut self,
+        ops: Vec<Operation>,
+        timestamp: Timestamp,
+    ) -> Result<Vec<ApplyResult>> {
+        let Some(transaction_id) = ops.first().map(|op| op.op_id().clone()) else {
+            return Ok(Vec::new());
+        };
+
+        let clock = self.clock.clone();
+        let pending_len = self.pending_ops.len();
+        let mut touched: Vec<(CollectionName, RecordId, Option<Record>, Vec<RecordVersion>)> =
+            Vec::new();
+        let mut results = Vec::with_capacity(ops.len());
+
+        for op in ops {
+            let collection = op.collection().clone();
+            let id = op.record_id().clone();
+            if !touched
+                .iter()
+                .any(|(c, i, _, _)| *c == collection && *i == id)
+            {
+                let existing = self.collections.get(&collection);
+                let previous = existing.and_then(|c| c.get(&id)).cloned();
+                let history = existing
+                    .map(|c| c.history(&id).to_vec())
+                    .unwrap_or_default();
+                touched.push((collection, id, previous, history));
+            }
+
+            match self.apply_op(op.with_transaction_id(transaction_id.clone()), timestamp) {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    // Roll back to the state before the batch
+                    for (collection, id, previous, history) in touched {
+                        if let Some(collection) = self.collections.get_mut(&collection) {
+                            match previous {
+                                Some(record) => collection.insert(record),
+                                None => {
+                                    collection.remove(&id);
+                                }
+                            }
+                            if history.is_empty() {
+                                collection.history.remove(&id);
+                            } else {
+                                collection.history.insert(id, history);
+                            }
+                        }
+                    }
+                    self.pending_ops.truncate(pending_len);
+                    self.clock = clock;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
     fn apply_create(&mut self, op: &crate::CreateOp, timestamp: Timestamp) -> Result<ApplyResult> {
         let collection = self
             .collections
@@ -183,18 +589,19 @@ impl Store {
             if existing.is_active() {
                 return Err(Error::RecordAlreadyExists(op.id.clone()));
             }
-            // If deleted, we could potentially resurrect, but for v1 we reject
+            // A tombstone is revived with a restore operation instead
             return Err(Error::RecordAlreadyExists(op.id.clone()));
         }
 
         // Create the record
-        let record = Record::new(
+        let mut record = Record::new(
             op.id.clone(),
             op.collection.clone(),
             op.payload.clone(),
             timestamp,
             op.clock.clone(),
         );
+        record.metadata.expires_at = op.expires_at;
 
         let version = record.version;
         collection.insert(record);
@@ -206,7 +613,11 @@ impl Store {
         })
     }
 
-    fn apply_update(&mut self, op: &crate::UpdateOp, timestamp: Timestamp) -> Result<ApplyResult> {
+    fn apply_update(
+        &mut self,
+        op: &mut crate::UpdateOp,
+        timestamp: Timestamp,
+    ) -> Result<ApplyResult> {
         let collection = self
             .collections
             .get_mut(&op.collection)
@@ -229,6 +640,11 @@ impl Store {
             });
         }
 
+        // Record which fields this update changed, for field-level merges
+        if op.changed_fields.is_none() {
+            op.changed_fields = Some(record.changed_fields(&op.payload));
+        }
+
         // Apply update
         record.update_payload(
             op.payload.clone(),
@@ -244,6 +660,46 @@ impl Store {
         })
     }
 
+    fn apply_patch(&mut self, op: &crate::PatchOp, timestamp: Timestamp) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
+
+        let record = collection
+            .get_mut(&op.id)
+            .ok_or_else(|| Error::RecordNotFound(op.id.clone()))?;
+
+        // Check if deleted
+        if record.deleted {
+            return Err(Error::OperationOnDeleted(op.id.clone()));
+        }
+
+        // Check version
+        if record.version != op.base_version {
+            return Err(Error::VersionMismatch {
+                expected: op.base_version,
+                actual: record.version,
+            });
+        }
+
+        // Validate the patched payload before touching the record
+        let payload = self.schema.validate_patch(op, &record.payload)?;
+
+        record.update_payload(
+            payload,
+            timestamp,
+            op.clock.clone(),
+            crate::record::Origin::Local,
+        );
+
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version: record.version,
+        })
+    }
+
     fn apply_delete(&mut self, op: &crate::DeleteOp, timestamp: Timestamp) -> Result<ApplyResult> {
         let collection = self
             .collections
@@ -277,624 +733,3459 @@ impl Store {
         })
     }
 
-    /// Get a record by collection and ID.
-    pub fn get(&self, collection: &str, id: &str) -> Option<&Record> {
-        self.collections
-            .get(collection)
-            .and_then(|c| c.get(id))
-            .filter(|r| r.is_active())
-    }
+    fn apply_restore(
+        &mut self,
+        op: &crate::RestoreOp,
+        timestamp: Timestamp,
+    ) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
 
-    /// Get a record including deleted ones.
-    pub fn get_including_deleted(&self, collection: &str, id: &str) -> Option<&Record> {
-        self.collections.get(collection).and_then(|c| c.get(id))
-    }
+        let record = collection
+            .get_mut(&op.id)
+            .ok_or_else(|| Error::RecordNotFound(op.id.clone()))?;
 
-    /// Query records in a collection.
-    pub fn query(&self, collection: &str) -> Option<QueryBuilder<'_>> {
-        self.collections.get(collection).map(QueryBuilder::new)
-    }
+        // Only tombstones can be restored
+        if !record.deleted {
+            return Err(Error::RecordNotDeleted(op.id.clone()));
+        }
 
-    /// Get all pending operations.
-    pub fn pending_ops(&self) -> &[PendingOp] {
-        &self.pending_ops
-    }
+        // Check version
+        if record.version != op.base_version {
+            return Err(Error::VersionMismatch {
+                expected: op.base_version,
+                actual: record.version,
+            });
+        }
 
-    /// Get count of pending operations.
-    pub fn pending_count(&self) -> usize {
-        self.pending_ops.len()
-    }
+        // Apply restore
+        record.restore(
+            op.payload.clone(),
+            timestamp,
+            op.clock.clone(),
+            crate::record::Origin::Local,
+        );
 
-    /// Acknowledge operations as synced (remove from pending).
-    pub fn acknowledge(&mut self, op_ids: &[OperationId]) {
-        self.pending_ops
-            .retain(|p| !op_ids.contains(p.operation.op_id()));
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version: record.version,
+        })
     }
 
-    /// Clear all pending operations.
-    pub fn clear_pending(&mut self) {
-        self.pending_ops.clear();
-    }
+    fn apply_upsert(&mut self, op: &crate::UpsertOp, timestamp: Timestamp) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
 
-    /// Get a collection by name.
-    pub fn collection(&self, name: &str) -> Option<&Collection> {
-        self.collections.get(name)
+        let version = match collection.get_mut(&op.id) {
+            // Replace the payload, reviving a tombstone
+            Some(record) => {
+                record.restore(
+                    op.payload.clone(),
+                    timestamp,
+                    op.clock.clone(),
+                    crate::record::Origin::Local,
+                );
+                record.metadata.expires_at = op.expires_at;
+                record.version
+            }
+            None => {
+                let mut record = Record::new(
+                    op.id.clone(),
+                    op.collection.clone(),
+                    op.payload.clone(),
+                    timestamp,
+                    op.clock.clone(),
+                );
+                record.metadata.expires_at = op.expires_at;
+                collection.insert(record);
+                1
+            }
+        };
+
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version,
+        })
     }
 
-    /// Reconcile local pending operations with remote operations.
-    ///
-    /// This is the core sync operation:
-    /// 1. Takes pending local ops and incoming remote ops
-    /// 2. Orders all ops deterministically
-    /// 3. Resolves conflicts using the specified strategy
-    /// 4. Updates store state to match reconciled result
-    /// 5. Returns details about what was accepted/rejected
-    pub fn reconcile(
+    fn apply_increment(
         &mut self,
-        remote_ops: Vec<Operation>,
-        strategy: crate::reconcile::MergeStrategy,
-    ) -> crate::reconcile::ReconcileResult {
-        use crate::reconcile::{OpSource, Reconciler};
+        op: &crate::IncrementOp,
+        timestamp: Timestamp,
+    ) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
 
-        // Create reconciler with current schema
-        let mut reconciler = Reconciler::new(&self.schema, strategy);
+        let record = collection
+            .get_mut(&op.id)
+            .ok_or_else(|| Error::RecordNotFound(op.id.clone()))?;
 
-        // Load existing records with their last operations
-        // For existing records, we create synthetic "create" ops to track state
-        for (collection_name, collection) in &self.collections {
-            for record in collection.all_records() {
-                // Create a synthetic operation representing current state
-                let synthetic_op = Operation::Create(crate::CreateOp::new(
-                    format!("__existing__{}", record.id),
-                    record.id.clone(),
-                    collection_name.clone(),
-                    record.payload.clone(),
-                    record.metadata.created_at,
-                    record.metadata.clock.clone(),
-                ));
-                reconciler.load_records(std::iter::once((
-                    record.clone(),
-                    synthetic_op,
-                    match record.metadata.origin {
-                        crate::record::Origin::Local => OpSource::Local,
-                        crate::record::Origin::Remote => OpSource::Remote,
-                    },
-                )));
-            }
+        // Check if deleted
+        if record.deleted {
+            return Err(Error::OperationOnDeleted(op.id.clone()));
         }
 
-        // Extract pending operations
-        let local_ops: Vec<_> = self
-            .pending_ops
-            .iter()
-            .map(|p| p.operation.clone())
-            .collect();
+        // No version check: increments commute
+        let payload = self.schema.validate_increment(op, &record.payload)?;
+        record.update_payload(
+            payload,
+            timestamp,
+            op.clock.clone(),
+            crate::record::Origin::Local,
+        );
 
-        // Run reconciliation
-        let (result, final_records) = reconciler.reconcile(local_ops, remote_ops);
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version: record.version,
+        })
+    }
 
-        // Update store state from reconciled records
-        for ((collection_name, _record_id), record) in final_records {
-            if let Some(collection) = self.collections.get_mut(&collection_name) {
-                collection.insert(record);
-            }
-        }
+    fn apply_list(&mut self, op: &crate::ListOp, timestamp: Timestamp) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
 
-        // Remove rejected local ops from pending (they lost conflict resolution)
-        let before_retain = self.pending_ops.len();
-        self.pending_ops
-            .retain(|p| !result.rejected_local.contains(p.operation.op_id()));
-        let after_retain = self.pending_ops.len();
+        let record = collection
+            .get_mut(&op.id)
+            .ok_or_else(|| Error::RecordNotFound(op.id.clone()))?;
 
-        // NOTE: Accepted local ops are NOT removed here - they remain pending
-        // until the server acknowledges them via the acknowledge() method.
-        // This ensures ops are pushed to the server before being cleared.
+        // Check if deleted
+        if record.deleted {
+            return Err(Error::OperationOnDeleted(op.id.clone()));
+        }
 
-        // Debug: Add pending count to result for verification (v2 fix)
-        let mut result = result;
-        result.debug_pending_before = Some(before_retain);
-        result.debug_pending_after = Some(after_retain);
+        // No version check: list operations commute
+        record.apply_list(op, timestamp, crate::record::Origin::Local)?;
 
-        result
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version: record.version,
+        })
     }
 
-    /// Export the current store state as a snapshot.
-    ///
-    /// The snapshot can be serialized and persisted by the Flutter layer.
-    pub fn export_state(&self) -> crate::snapshot::StoreSnapshot {
-        let mut snapshot =
-            crate::snapshot::StoreSnapshot::new(self.schema.version, self.node_id.clone());
-        snapshot.clock = self.clock.clone();
+    fn apply_text(&mut self, op: &crate::TextOp, timestamp: Timestamp) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
 
-        // Export all records from all collections
-        for collection in self.collections.values() {
-            for record in collection.all_records() {
-                snapshot.add_record(record.clone());
-            }
-        }
+        let record = collection
+            .get_mut(&op.id)
+            .ok_or_else(|| Error::RecordNotFound(op.id.clone()))?;
 
-        // Export pending operations
-        for pending in &self.pending_ops {
-            snapshot.add_pending(pending.clone());
+        // Check if deleted
+        if record.deleted {
+            return Err(Error::OperationOnDeleted(op.id.clone()));
         }
 
-        snapshot
-    }
+        // No version check: text operations commute
+        record.apply_text(op, timestamp, crate::record::Origin::Local)?;
 
-    /// Import state from a snapshot.
-    ///
-    /// This replaces the current state with the snapshot's state.
-    /// The schema must match.
-    pub fn import_state(&mut self, snapshot: crate::snapshot::StoreSnapshot) -> Result<()> {
-        // Validate against current schema
-        snapshot.validate(&self.schema)?;
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version: record.version,
+        })
+    }
 
-        // Validate node ID matches
-        if snapshot.node_id != self.node_id {
-            return Err(Error::InvalidSnapshot(format!(
-                "node ID mismatch: expected '{}', got '{}'",
-                self.node_id, snapshot.node_id
-            )));
-        }
+    fn apply_set(&mut self, op: &mut crate::SetOp, timestamp: Timestamp) -> Result<ApplyResult> {
+        let collection = self
+            .collections
+            .get_mut(&op.collection)
+            .ok_or_else(|| Error::CollectionNotFound(op.collection.clone()))?;
 
-        // Import clock
-        self.clock = snapshot.clock;
+        let record = collection
+            .get_mut(&op.id)
+            .ok_or_else(|| Error::RecordNotFound(op.id.clone()))?;
 
-        // Clear and import collections
-        for collection in self.collections.values_mut() {
-            collection.records.clear();
+        // Check if deleted
+        if record.deleted {
+            return Err(Error::OperationOnDeleted(op.id.clone()));
         }
 
-        for (collection_name, records) in snapshot.collections {
-            if let Some(collection) = self.collections.get_mut(&collection_name) {
-                for (_, record) in records {
-                    collection.insert(record);
-                }
+        // A remove takes out the adds this node has seen
+        if let crate::SetAction::Remove { value, observed } = &mut op.action {
+            if observed.is_empty() {
+                *observed = record.set(&op.field).observed(value);
             }
         }
 
-        // Import pending operations
-        self.pending_ops = snapshot.pending_ops;
+        // No version check: set operations commute
+        record.apply_set(op, timestamp, crate::record::Origin::Local)?;
 
-        Ok(())
+        Ok(ApplyResult {
+            op_id: op.op_id.clone(),
+            record_id: op.id.clone(),
+            version: record.version,
+        })
     }
 
-    /// Get snapshot metadata without full export.
-    pub fn snapshot_metadata(&self) -> crate::snapshot::SnapshotMetadata {
-        crate::snapshot::SnapshotMetadata {
-            format_version: crate::snapshot::SNAPSHOT_FORMAT_VERSION,
-            schema_version: self.schema.version,
-            node_id: self.node_id.clone(),
-            clock_counter: self.clock.counter,
-            record_count: self.collections.values().map(|c| c.records.len()).sum(),
-            pending_count: self.pending_ops.len(),
-        }
+    /// Get a record by collection and ID.
+    pub fn get(&self, collection: &str, id: &str) -> Option<&Record> {
+        self.collections
+            .get(collection)
+            .and_then(|c| c.get(id))
+            .filter(|r| r.is_active())
     }
-}
 
-/// Builder for querying records in a collection.
-#[derive(Debug)]
-pub struct QueryBuilder<'a> {
-    collection: &'a Collection,
-    include_deleted: bool,
-}
+    /// Get a record unless it is deleted or expired at `now`.
+    pub fn get_at(&self, collection: &str, id: &str, now: Timestamp) -> Option<&Record> {
+        let ttl = self.ttl(collection);
+        self.get(collection, id).filter(|r| !r.is_expired(now, ttl))
+    }
 
-impl<'a> QueryBuilder<'a> {
-    fn new(collection: &'a Collection) -> Self {
-        Self {
-            collection,
-            include_deleted: false,
-        }
+    /// Get a record including deleted ones.
+    pub fn get_including_deleted(&self, collection: &str, id: &str) -> Option<&Record> {
+        self.collections.get(collection).and_then(|c| c.get(id))
     }
 
-    /// Include deleted records in results.
-    pub fn include_deleted(mut self) -> Self {
-        self.include_deleted = true;
-        self
+    /// Query records in a collection.
+    pub fn query(&self, collection: &str) -> Option<QueryBuilder<'_>> {
+        let ttl = self.ttl(collection);
+        self.collections
+            .get(collection)
+            .map(|c| QueryBuilder::new(c, ttl))
     }
 
-    /// Get all matching records.
-    pub fn all(self) -> Vec<&'a Record> {
-        if self.include_deleted {
-            self.collection.all_records().collect()
-        } else {
-            self.collection.active_records().collect()
-        }
+    /// Get records holding conflicting versions kept by
+    /// [`MergeStrategy::KeepBoth`](crate::MergeStrategy::KeepBoth), ordered
+    /// by collection and ID.
+    pub fn conflicted_records(&self) -> Vec<&Record> {
+        let mut records: Vec<&Record> = self
+            .collections
+            .values()
+            .flat_map(|c| c.all_records())
+            .filter(|r| r.is_conflicted())
+            .collect();
+        records.sort_by(|a, b| (&a.collection, &a.id).cmp(&(&b.collection, &b.id)));
+        records
     }
 
-    /// Get the first matching record.
-    pub fn first(self) -> Option<&'a Record> {
-        if self.include_deleted {
-            self.collection.all_records().next()
-        } else {
-            self.collection.active_records().next()
+    /// Resolve a conflicted record with the chosen payload.
+    ///
+    /// Applies an update (a restore, if the record is deleted) made against
+    /// every kept version, so the siblings are cleared here and wherever the
+    /// operation syncs to.
+    pub fn resolve_conflict(
+        &mut self,
+        op_id: impl Into<OperationId>,
+        collection: &str,
+        id: &str,
+        payload: serde_json::Value,
+        timestamp: Timestamp,
+    ) -> Result<ApplyResult> {
+        let record = self
+            .get_including_deleted(collection, id)
+            .ok_or_else(|| Error::RecordNotFound(id.to_string()))?;
+        if !record.is_conflicted() {
+            return Err(Error::RecordNotConflicted(id.to_string()));
         }
-    }
 
-    /// Count matching records.
-    pub fn count(self) -> usize {
-        if self.include_deleted {
-            self.collection.records.len()
+        let (deleted, version) = (record.deleted, record.version);
+        let clock = self.tick();
+        let op = if deleted {
+            Operation::Restore(crate::RestoreOp::new(
+                op_id, id, collection, payload, version, timestamp, clock,
+            ))
         } else {
-            self.collection.len()
+            Operation::Update(crate::UpdateOp::new(
+                op_id, id, collection, payload, version, timestamp, clock,
+            ))
+        };
+        self.apply(op, timestamp)
+    }
+
+    /// Number of versions kept in a collection's history log, if it keeps
+    /// one.
+    fn history_limit(&self, collection: &str) -> Option<usize> {
+        self.schema
+            .get_collection(collection)
+            .and_then(|schema| schema.history_limit)
+    }
+
+    fn ttl(&self, collection: &str) -> Option<u64> {
+        self.schema
+            .get_collection(collection)
+            .and_then(|schema| schema.ttl)
+    }
+
+    /// Get the logged versions of a record, oldest first.
+    ///
+    /// Empty unless the collection's schema sets
+    /// [`CollectionSchema::history_limit`](crate::CollectionSchema::history_limit).
+    pub fn history(&self, collection: &str, id: &str) -> &[RecordVersion] {
+        self.collections
+            .get(collection)
+            .map(|c| c.history(id))
+            .unwrap_or_default()
+    }
+
+    /// Get a record as it was at a version or clock, from its history.
+    pub fn record_at(
+        &self,
+        collection: &str,
+        id: &str,
+        at: impl Into<HistoryPoint>,
+    ) -> Option<&RecordVersion> {
+        let history = self.history(collection, id);
+        match at.into() {
+            HistoryPoint::Version(version) => history.iter().find(|v| v.version == version),
+            HistoryPoint::Clock(clock) => history.iter().rev().find(|v| v.clock <= clock),
         }
     }
 
-    /// Filter records by a predicate on payload.
-    pub fn filter<F>(self, predicate: F) -> Vec<&'a Record>
-    where
-        F: Fn(&serde_json::Value) -> bool,
-    {
-        let iter: Box<dyn Iterator<Item = &Record>> = if self.include_deleted {
-            Box::new(self.collection.all_records())
-        } else {
-            Box::new(self.collection.active_records())
+    /// Revert a record to a version from its history.
+    ///
+    /// Applies an update with that version's payload (a restore, if the
+    /// record is deleted, or a delete, if the version is), so the revert
+    /// syncs like any other edit.
+    pub fn revert_to(
+        &mut self,
+        op_id: impl Into<OperationId>,
+        collection: &str,
+        id: &str,
+        at: impl Into<HistoryPoint>,
+        timestamp: Timestamp,
+    ) -> Result<ApplyResult> {
+        let target = self
+            .record_at(collection, id, at)
+            .ok_or_else(|| Error::VersionNotInHistory(id.to_string()))?;
+        let payload = (!target.deleted).then(|| target.payload.clone());
+
+        let op = self.write_op(op_id, collection, id, payload, timestamp)?;
+        self.apply(op, timestamp)
+    }
+
+    /// Build an operation that gives a record `payload`, or deletes it if
+    /// `None`: a create, update, restore or delete, depending on the
+    /// record's state.
+    fn write_op(
+        &mut self,
+        op_id: impl Into<OperationId>,
+        collection: &str,
+        id: &str,
+        payload: Option<serde_json::Value>,
+        timestamp: Timestamp,
+    ) -> Result<Operation> {
+        let state = self
+            .get_including_deleted(collection, id)
+            .map(|r| (r.deleted, r.version));
+        let clock = match (state, &payload) {
+            (Some((true, _)), None) => return Err(Error::OperationOnDeleted(id.to_string())),
+            (None, None) => return Err(Error::RecordNotFound(id.to_string())),
+            _ => self.tick(),
         };
 
-        iter.filter(|r| predicate(&r.payload)).collect()
+        let op = match (state, payload) {
+            (None, Some(payload)) => Operation::Create(crate::CreateOp::new(
+                op_id, id, collection, payload, timestamp, clock,
+            )),
+            (Some((false, version)), Some(payload)) => Operation::Update(crate::UpdateOp::new(
+                op_id, id, collection, payload, version, timestamp, clock,
+            )),
+            (Some((true, version)), Some(payload)) => Operation::Restore(crate::RestoreOp::new(
+                op_id, id, collection, payload, version, timestamp, clock,
+            )),
+            (Some((_, version)), None) => Operation::Delete(crate::DeleteOp::new(
+                op_id, id, collection, version, timestamp, clock,
+            )),
+            (None, None) => unreachable!("rejected above"),
+        };
+        Ok(op)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::operation::{CreateOp, DeleteOp, UpdateOp};
-    use crate::schema::{CollectionSchema, FieldDef, FieldType};
-    use serde_json::json;
+    /// Remove tombstones every known node has seen.
+    ///
+    /// A deleted record is purged once it was deleted before `older_than`
+    /// and `stable_clock` (the writes every known node has acknowledged,
+    /// see [`VersionVector::meet`]) covers its version vector, so no node
+    /// can still send a write the tombstone would have to win against.
+    /// Records with pending operations are kept. Returns the number of
+    /// tombstones removed.
+    ///
+    /// [`VersionVector::meet`]: crate::VersionVector::meet
+    pub fn purge_tombstones(
+        &mut self,
+        older_than: Timestamp,
+        stable_clock: &crate::VersionVector,
+    ) -> usize {
+        let pending: HashSet<(&CollectionName, &RecordId)> = self
+            .pending_ops
+            .iter()
+            .map(|p| (p.operation.collection(), p.operation.record_id()))
+            .collect();
 
-    fn test_schema() -> Schema {
-        Schema::new(1).with_collection(CollectionSchema::new(
-            "users",
-            vec![
-                FieldDef::required("name", FieldType::String),
-                FieldDef::optional("age", FieldType::Int),
-            ],
-        ))
+        let mut purged = 0;
+        for (name, collection) in &mut self.collections {
+            let before = collection.records.len();
+            collection.records.retain(|id, record| {
+                !(record.deleted
+                    && record.metadata.updated_at < older_than
+                    && stable_clock.dominates(&record.metadata.version_vector)
+                    && !pending.contains(&(name, id)))
+            });
+            if collection.records.len() < before {
+                let records = &collection.records;
+                collection.history.retain(|id, _| records.contains_key(id));
+                purged += before - records.len();
+            }
+        }
+        purged
     }
 
-    fn test_store() -> Store {
-        Store::new(test_schema(), "test-node")
+    /// Delete every record expired at `now`.
+    ///
+    /// A record expires at its own [`Metadata::expires_at`], or its
+    /// collection's [`CollectionSchema::ttl`] after its last write. Each
+    /// expired record gets a pending delete operation derived from the
+    /// record alone: its ID from the record and the clock of its last write,
+    /// its timestamp the expiry time, and its clock that clock plus one
+    /// under the `expire` node ID. Every node sweeping the same state writes the same
+    /// delete. Records are swept in collection and ID order. No undo steps
+    /// are recorded.
+    ///
+    /// [`Metadata::expires_at`]: crate::Metadata::expires_at
+    /// [`CollectionSchema::ttl`]: crate::CollectionSchema::ttl
+    pub fn expire(&mut self, now: Timestamp) -> Result<Vec<ApplyResult>> {
+        let mut expired: Vec<(CollectionName, RecordId, Version, Timestamp, LogicalClock)> =
+            Vec::new();
+        for (name, collection) in &self.collections {
+            let ttl = self.ttl(name);
+            for record in collection.active_records() {
+                if let Some(at) = record.expires_at(ttl).filter(|&at| at <= now) {
+                    let clock = record.metadata.clock.clone();
+                    expired.push((name.clone(), record.id.clone(), record.version, at, clock));
+                }
+            }
+        }
+        expired.sort();
+
+        let mut results = Vec::with_capacity(expired.len());
+        for (collection, id, version, at, written) in expired {
+            let op_id = format!(
+                "expire:{collection}:{id}:{}:{}",
+                written.node_id, written.counter
+            );
+            let clock = LogicalClock::with_counter(EXPIRE_NODE_ID, written.counter + 1);
+            let op = Operation::Delete(crate::DeleteOp::new(
+                op_id,
+                id.as_str(),
+                collection.as_str(),
+                version,
+                at,
+                clock,
+            ));
+            results.push(self.apply_op(op, at)?);
+        }
+        Ok(results)
     }
 
-    #[test]
-    fn create_store() {
-        let store = test_store();
-        assert_eq!(store.node_id(), "test-node");
-        assert_eq!(store.clock().counter, 0);
-        assert!(store.collection("users").is_some());
+    /// Get the undo manager holding the local changes that can be undone
+    /// and redone.
+    pub fn undo_manager(&self) -> &UndoManager {
+        &self.undo
     }
 
-    #[test]
-    fn apply_create() {
-        let mut store = test_store();
-        let clock = store.tick();
+    /// Change the number of undo and redo steps kept.
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.undo.set_limit(limit);
+    }
 
-        let op = Operation::Create(CreateOp::new(
-            "op-1",
-            "user-1",
-            "users",
-            json!({"name": "Alice"}),
-            1000,
-            clock,
-        ));
+    /// Undo the latest local change still standing.
+    ///
+    /// Applies a new operation per changed record that writes its earlier
+    /// state back (an update, restore, delete or create), so undo works
+    /// whether or not the original operations have been pushed. A step
+    /// recorded by [`Store::apply_batch`] is undone with one batch; its
+    /// operations are `op_id`, then `op_id:1`, `op_id:2` and so on. Returns
+    /// `None` if there is nothing to undo. A step that no longer applies is
+    /// dropped and its error returned.
+    pub fn undo(
+        &mut self,
+        op_id: impl Into<OperationId>,
+        timestamp: Timestamp,
+    ) -> Result<Option<Vec<ApplyResult>>> {
+        let Some(step) = self.undo.pop_undo() else {
+            return Ok(None);
+        };
+        let results = self.write_step(op_id.into(), &step, |change| &change.before, timestamp)?;
+        self.undo.push_redo(step);
+        Ok(Some(results))
+    }
 
-        let result = store.apply(op, 1000).unwrap();
-        assert_eq!(result.record_id, "user-1");
-        assert_eq!(result.version, 1);
+    /// Redo the latest undone change.
+    ///
+    /// Like [`Store::undo`], this applies new operations. Returns `None` if
+    /// there is nothing to redo.
+    pub fn redo(
+        &mut self,
+        op_id: impl Into<OperationId>,
+        timestamp: Timestamp,
+    ) -> Result<Option<Vec<ApplyResult>>> {
+        let Some(step) = self.undo.pop_redo() else {
+            return Ok(None);
+        };
+        let results = self.write_step(op_id.into(), &step, |change| &change.after, timestamp)?;
+        self.undo.push_undo(step);
+        Ok(Some(results))
+    }
+
+    /// Give each record of an undo step the payload `target` picks, in one
+    /// batch if the step changed several records.
+    fn write_step(
+        &mut self,
+        op_id: OperationId,
+        step: &UndoStep,
+        target: impl Fn(&UndoChange) -> &Option<serde_json::Value>,
+        timestamp: Timestamp,
+    ) -> Result<Vec<ApplyResult>> {
+        let mut ops = Vec::with_capacity(step.changes.len());
+        for (index, change) in step.changes.iter().enumerate() {
+            let op_id = match index {
+                0 => op_id.clone(),
+                _ => format!("{op_id}:{index}"),
+            };
+            ops.push(self.write_op(
+                op_id,
+                &change.collection,
+                &change.id,
+                target(change).clone(),
+                timestamp,
+            )?);
+        }
+
+        if ops.len() == 1 {
+            let op = ops.remove(0);
+            return Ok(vec![self.apply_op(op, timestamp)?]);
+        }
+        self.apply_batch_ops(ops, timestamp)
+    }
+
+    /// Get all pending operations.
+    pub fn pending_ops(&self) -> &[PendingOp] {
+        &self.pending_ops
+    }
+
+    /// Get count of pending operations.
+    pub fn pending_count(&self) -> usize {
+        self.pending_ops.len()
+    }
+
+    /// Acknowledge operations as synced (remove from pending).
+    ///
+    /// A pending operation is removed when its own ID or any ID folded into
+    /// it by [`Store::compact_pending`] is acknowledged.
+    pub fn acknowledge(&mut self, op_ids: &[OperationId]) {
+        self.pending_ops
+            .retain(|p| !p.op_ids().any(|id| op_ids.contains(id)));
+    }
+
+    /// Rewrite pending operations onto the current record state.
+    ///
+    /// Reconciliation can move records past the versions their pending
+    /// operations were made against. Each pending operation gets the base
+    /// version it has when the pending operations on its record are replayed,
+    /// in order, to reach the current state. Reconciliation does not apply a
+    /// pending operation the record already reflects again, so the record's
+    /// version counts each of them once and only remote writes move the
+    /// base. Operations that no longer apply,
+    /// because the schema rejects them or they edit a record that is now
+    /// deleted, are dropped; their IDs are returned.
+    ///
+    /// [`Store::reconcile`] calls this after merging.
+    pub fn rebase_pending(&mut self) -> Vec<OperationId> {
+        // Walk back from the current state to learn whether each operation's
+        // record was deleted when the operation applies
+        let mut deleted: HashMap<(CollectionName, RecordId), bool> = HashMap::new();
+        let mut deleted_before = vec![false; self.pending_ops.len()];
+        for (index, pending) in self.pending_ops.iter().enumerate().rev() {
+            let op = &pending.operation;
+            let after = *deleted
+                .entry((op.collection().clone(), op.record_id().clone()))
+                .or_insert_with(|| {
+                    self.get_including_deleted(op.collection(), op.record_id())
+                        .is_none_or(|record| record.deleted)
+                });
+            let before = match op {
+                Operation::Delete(_) => false,
+                Operation::Restore(_) => true,
+                _ => after,
+            };
+            deleted_before[index] = before;
+            deleted.insert((op.collection().clone(), op.record_id().clone()), before);
+        }
+
+        let mut invalidated = Vec::new();
+        let mut remaining: HashMap<(CollectionName, RecordId), u64> = HashMap::new();
+        let pending_ops: Vec<PendingOp> = std::mem::take(&mut self.pending_ops)
+            .into_iter()
+            .zip(deleted_before)
+            .filter_map(|(pending, deleted)| {
+                let op = &pending.operation;
+                let needs_record = !matches!(
+                    op,
+                    Operation::Create(_) | Operation::Restore(_) | Operation::Upsert(_)
+                );
+                if (needs_record && deleted) || self.schema.validate_operation(op).is_err() {
+                    invalidated.push(op.op_id().clone());
+                    return None;
+                }
+                *remaining
+                    .entry((op.collection().clone(), op.record_id().clone()))
+                    .or_insert(0) += 1;
+                Some(pending)
+            })
+            .collect();
+
+        self.pending_ops = pending_ops
+            .into_iter()
+            .map(|mut pending| {
+                let op = &pending.operation;
+                let key = (op.collection().clone(), op.record_id().clone());
+                let count = remaining.get_mut(&key).expect("counted above");
+                if let Some(record) = self.get_including_deleted(&key.0, &key.1) {
+                    let base_version = record.version.saturating_sub(*count);
+                    pending.operation = pending.operation.with_base_version(base_version);
+                }
+                *count -= 1;
+                pending
+            })
+            .collect();
+
+        invalidated
+    }
+
+    /// Fold consecutive pending operations on the same record into one.
+    ///
+    /// A create followed by updates becomes a single create, successive
+    /// updates become one update, and a create followed by a delete is
+    /// dropped. Operations in a transaction are left alone. Each remaining
+    /// operation lists the IDs it absorbed in [`PendingOp::folded`].
+    ///
+    /// Call this before pushing, never while a push of the pending
+    /// operations is in flight. Returns the IDs of operations that were
+    /// dropped, which will never be acknowledged.
+    pub fn compact_pending(&mut self) -> Vec<OperationId> {
+        let mut compacted: Vec<Option<PendingOp>> = Vec::with_capacity(self.pending_ops.len());
+        // Position of the latest pending operation on each record
+        let mut latest: HashMap<(CollectionName, RecordId), usize> = HashMap::new();
+        let mut dropped = Vec::new();
+
+        for pending in std::mem::take(&mut self.pending_ops) {
+            let key = (
+                pending.operation.collection().clone(),
+                pending.operation.record_id().clone(),
+            );
+
+            if let Some(&index) = latest.get(&key) {
+                if let Some(previous) = &compacted[index] {
+                    match fold_pending(previous, &pending) {
+                        Some(Some(merged)) => {
+                            compacted[index] = None;
+                            latest.insert(key, compacted.len());
+                            compacted.push(Some(merged));
+                            continue;
+                        }
+                        Some(None) => {
+                            dropped.extend(previous.op_ids().chain(pending.op_ids()).cloned());
+                            compacted[index] = None;
+                            latest.remove(&key);
+                            continue;
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            latest.insert(key, compacted.len());
+            compacted.push(Some(pending));
+        }
+
+        self.pending_ops = compacted.into_iter().flatten().collect();
+        dropped
+    }
+
+    /// Clear all pending operations.
+    pub fn clear_pending(&mut self) {
+        self.pending_ops.clear();
+    }
+
+    /// Get a collection by name.
+    pub fn collection(&self, name: &str) -> Option<&Collection> {
+        self.collections.get(name)
+    }
+
+    /// Reconcile local pending operations with remote operations.
+    ///
+    /// This is the core sync operation:
+    /// 1. Takes pending local ops and incoming remote ops
+    /// 2. Orders all ops deterministically
+    /// 3. Resolves conflicts using the specified strategy
+    /// 4. Updates store state to match reconciled result
+    /// 5. Returns details about what was accepted/rejected
+    ///
+    /// Only records the operations reference are loaded into the
+    /// reconciler, so its cost follows the size of the batch, not the store.
+    pub fn reconcile(
+        &mut self,
+        remote_ops: Vec<Operation>,
+        strategy: crate::reconcile::MergeStrategy,
+    ) -> crate::reconcile::ReconcileResult {
+        use crate::reconcile::Reconciler;
+
+        // Create reconciler with current schema
+        let mut reconciler =
+            Reconciler::new(&self.schema, strategy).with_resolvers(self.resolvers.clone());
+
+        // Load only the records the operations touch, with their last
+        // operations, so the cost follows the batch rather than the store
+        let touched: HashSet<(&CollectionName, &RecordId)> = self
+            .pending_ops
+            .iter()
+            .map(|p| &p.operation)
+            .chain(&remote_ops)
+            .map(|op| (op.collection(), op.record_id()))
+            .collect();
+        reconciler.load_records(
+            touched
+                .into_iter()
+                .filter_map(|(collection, id)| self.collections.get(collection)?.get(id))
+                .cloned(),
+        );
+
+        // Extract pending operations
+        let local_ops: Vec<_> = self
+            .pending_ops
+            .iter()
+            .map(|p| p.operation.clone())
+            .collect();
+
+        // Run reconciliation
+        let (result, final_records) = reconciler.reconcile(local_ops, remote_ops);
+
+        // Update store state from reconciled records, noting what changed
+        let mut result = result;
+        let mut rewritten = HashSet::new();
+        for ((collection_name, record_id), record) in final_records {
+            let limit = self.history_limit(&collection_name);
+            if let Some(collection) = self.collections.get_mut(&collection_name) {
+                let before = collection.get(&record.id);
+                let changed = before != Some(&record);
+                if crate::undo::visible_payload(before)
+                    != crate::undo::visible_payload(Some(&record))
+                {
+                    rewritten.insert((collection_name.clone(), record_id.clone()));
+                }
+                let changes = result.changes.entry(collection_name).or_default();
+                record_change(changes, before, &record);
+                let op_id = record.metadata.last_op.as_ref().map(|op| op.op_id.clone());
+                collection.insert(record);
+                if let Some(limit) = limit.filter(|_| changed) {
+                    collection.log_version(&record_id, op_id, limit);
+                }
+            }
+        }
+        result.changes.retain(|_, changes| !changes.is_empty());
+
+        // Undoing a change to a record reconciliation rewrote would
+        // overwrite the remote edits
+        self.undo.invalidate(&rewritten);
+        for changes in result.changes.values_mut() {
+            for list in [
+                &mut changes.inserted,
+                &mut changes.updated,
+                &mut changes.deleted,
+            ] {
+                list.sort_by(|a, b| a.id.cmp(&b.id));
+            }
+        }
+
+        // Remove rejected local ops from pending (they lost conflict resolution)
+        let before_retain = self.pending_ops.len();
+        self.pending_ops
+            .retain(|p| !result.rejected_local.contains(p.operation.op_id()));
+        let after_retain = self.pending_ops.len();
+
+        // NOTE: Accepted local ops are NOT removed here - they remain pending
+        // until the server acknowledges them via the acknowledge() method.
+        // This ensures ops are pushed to the server before being cleared.
+
+        // Move the remaining pending ops onto the reconciled records
+        result.invalidated_local = self.rebase_pending();
+
+        // Debug: Add pending count to result for verification (v2 fix)
+        result.debug_pending_before = Some(before_retain);
+        result.debug_pending_after = Some(after_retain);
+
+        result
+    }
+
+    /// Reconcile with remote operations received at `now`.
+    ///
+    /// Like [`Store::reconcile`], but remote operations whose clock runs
+    /// further ahead of `now` than [`Store::max_drift`] are reported in
+    /// [`ReconcileResult::rejected_remote`] instead of applied, along with
+    /// the rest of their transaction. The clocks of applied remote
+    /// operations advance the store clock.
+    ///
+    /// [`ReconcileResult::rejected_remote`]: crate::ReconcileResult::rejected_remote
+    pub fn reconcile_at(
+        &mut self,
+        remote_ops: Vec<Operation>,
+        strategy: crate::reconcile::MergeStrategy,
+        now: Timestamp,
+    ) -> crate::reconcile::ReconcileResult {
+        let drifted: Vec<&Operation> = remote_ops
+            .iter()
+            .filter(|op| self.check_drift(op.clock(), now).is_err())
+            .collect();
+        let drifted_ops: HashSet<OperationId> =
+            drifted.iter().map(|op| op.op_id().clone()).collect();
+        let drifted_transactions: HashSet<TransactionId> = drifted
+            .iter()
+            .filter_map(|op| op.transaction_id().cloned())
+            .collect();
+        let (drifted, remote_ops): (Vec<_>, Vec<_>) = remote_ops.into_iter().partition(|op| {
+            drifted_ops.contains(op.op_id())
+                || op
+                    .transaction_id()
+                    .is_some_and(|id| drifted_transactions.contains(id))
+        });
+
+        let clocks: HashMap<OperationId, LogicalClock> = remote_ops
+            .iter()
+            .map(|op| (op.op_id().clone(), op.clock().clone()))
+            .collect();
+        let mut result = self.reconcile(remote_ops, strategy);
+        for op_id in &result.applied_remote {
+            if let Some(clock) = clocks.get(op_id) {
+                self.receive_clock(clock, now)
+                    .expect("drift is checked before reconciling");
+            }
+        }
+        result
+            .rejected_remote
+            .extend(drifted.iter().map(|op| op.op_id().clone()));
+        result
+    }
+
+    /// Preview a reconciliation without changing the store.
+    ///
+    /// Runs [`Store::reconcile`] against a copy of the store and reports its
+    /// result along with every record it would change, so callers can warn
+    /// before local edits are overwritten.
+    pub fn preview_reconcile(
+        &self,
+        remote_ops: Vec<Operation>,
+        strategy: crate::reconcile::MergeStrategy,
+    ) -> ReconcilePreview {
+        let mut preview = self.clone();
+        let result = preview.reconcile(remote_ops, strategy);
+
+        let mut records = Vec::new();
+        for (collection_name, collection) in &preview.collections {
+            for after in collection.all_records() {
+                let before = self.get_including_deleted(collection_name, &after.id);
+                if before != Some(after) {
+                    records.push(RecordDiff {
+                        collection: collection_name.clone(),
+                        id: after.id.clone(),
+                        before: before.cloned(),
+                        after: after.clone(),
+                    });
+                }
+            }
+        }
+        // Collections are hash maps; give callers a stable order
+        records.sort_by(|a, b| (&a.collection, &a.id).cmp(&(&b.collection, &b.id)));
+
+        ReconcilePreview { result, records }
+    }
+
+    /// Export the current store state as a snapshot.
+    ///
+    /// The snapshot can be serialized and persisted by the Flutter layer.
+    pub fn export_state(&self) -> crate::snapshot::StoreSnapshot {
+        let mut snapshot =
+            crate::snapshot::StoreSnapshot::new(self.schema.version, self.node_id.clone());
+        snapshot.clock = self.clock.clone();
+
+        // Export all records from all collections
+        for (name, collection) in &self.collections {
+            for record in collection.all_records() {
+                snapshot.add_record(record.clone());
+            }
+            for (id, versions) in &collection.history {
+                snapshot
+                    .history
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(id.clone(), versions.clone());
+            }
+        }
+
+        // Export pending operations
+        for pending in &self.pending_ops {
+            snapshot.add_pending(pending.clone());
+        }
+
+        snapshot
+    }
+
+    /// Import state from a snapshot.
+    ///
+    /// This replaces the current state with the snapshot's state.
+    /// The schema must match.
+    pub fn import_state(&mut self, snapshot: crate::snapshot::StoreSnapshot) -> Result<()> {
+        // Validate against current schema
+        snapshot.validate(&self.schema)?;
+
+        // Validate node ID matches
+        if snapshot.node_id != self.node_id {
+            return Err(Error::InvalidSnapshot(format!(
+                "node ID mismatch: expected '{}', got '{}'",
+                self.node_id, snapshot.node_id
+            )));
+        }
+
+        // Import clock
+        self.clock = snapshot.clock;
+        self.undo.clear();
+
+        // Clear and import collections
+        for collection in self.collections.values_mut() {
+            collection.records.clear();
+            collection.history.clear();
+        }
+
+        for (collection_name, records) in snapshot.collections {
+            if let Some(collection) = self.collections.get_mut(&collection_name) {
+                for (_, record) in records {
+                    collection.insert(record);
+                }
+            }
+        }
+        for (collection_name, history) in snapshot.history {
+            if let Some(collection) = self.collections.get_mut(&collection_name) {
+                collection.history.extend(history);
+            }
+        }
+
+        // Import pending operations
+        self.pending_ops = snapshot.pending_ops;
+
+        Ok(())
+    }
+
+    /// Get snapshot metadata without full export.
+    pub fn snapshot_metadata(&self) -> crate::snapshot::SnapshotMetadata {
+        crate::snapshot::SnapshotMetadata {
+            format_version: crate::snapshot::SNAPSHOT_FORMAT_VERSION,
+            schema_version: self.schema.version,
+            node_id: self.node_id.clone(),
+            clock_counter: self.clock.counter,
+            record_count: self.collections.values().map(|c| c.records.len()).sum(),
+            pending_count: self.pending_ops.len(),
+        }
+    }
+}
+
+/// Builder for querying records in a collection.
+#[derive(Debug)]
+pub struct QueryBuilder<'a> {
+    collection: &'a Collection,
+    include_deleted: bool,
+    ttl: Option<u64>,
+    now: Option<Timestamp>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    fn new(collection: &'a Collection, ttl: Option<u64>) -> Self {
+        Self {
+            collection,
+            include_deleted: false,
+            ttl,
+            now: None,
+        }
+    }
+
+    /// Include deleted records in results.
+    pub fn include_deleted(mut self) -> Self {
+        self.include_deleted = true;
+        self
+    }
+
+    /// Exclude records expired at `now`.
+    pub fn at(mut self, now: Timestamp) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Get all matching records.
+    pub fn all(self) -> Vec<&'a Record> {
+        self.records().collect()
+    }
+
+    /// Get the first matching record.
+    pub fn first(self) -> Option<&'a Record> {
+        self.records().next()
+    }
+
+    /// Count matching records.
+    pub fn count(self) -> usize {
+        match self.now {
+            None if self.include_deleted => self.collection.records.len(),
+            None => self.collection.len(),
+            Some(_) => self.records().count(),
+        }
+    }
+
+    /// Filter records by a predicate on payload.
+    pub fn filter<F>(self, predicate: F) -> Vec<&'a Record>
+    where
+        F: Fn(&serde_json::Value) -> bool,
+    {
+        self.records().filter(|r| predicate(&r.payload)).collect()
+    }
+
+    fn records(&self) -> impl Iterator<Item = &'a Record> {
+        let iter: Box<dyn Iterator<Item = &'a Record>> = if self.include_deleted {
+            Box::new(self.collection.all_records())
+        } else {
+            Box::new(self.collection.active_records())
+        };
+
+        let (now, ttl) = (self.now, self.ttl);
+        iter.filter(move |r| now.is_none_or(|now| !r.is_expired(now, ttl)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::{
+        CreateOp, DeleteOp, ListAction, ListOp, SetAction, SetOp, TextOp, UpdateOp,
+    };
+    use crate::schema::{CollectionSchema, FieldDef, FieldType};
+    use serde_json::json;
+
+    fn test_schema() -> Schema {
+        Schema::new(1).with_collection(CollectionSchema::new(
+            "users",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("age", FieldType::Int),
+            ],
+        ))
+    }
+
+    fn test_store() -> Store {
+        Store::new(test_schema(), "test-node")
+    }
+
+    #[test]
+    fn create_store() {
+        let store = test_store();
+        assert_eq!(store.node_id(), "test-node");
+        assert_eq!(store.clock().counter, 0);
+        assert!(store.collection("users").is_some());
+    }
+
+    #[test]
+    fn tick_at_tracks_wall_time() {
+        let mut store = test_store();
+        store.tick();
+
+        let first = store.tick_at(5000);
+        assert_eq!(first.physical_time(), 5000);
+        assert_eq!(first.logical_count(), 0);
+
+        // A stalled wall clock still moves forward
+        let second = store.tick_at(4000);
+        assert!(second > first);
+        assert_eq!(second.physical_time(), 5000);
+
+        // Remote hybrid clocks merge into the store clock on apply
+        let remote = crate::LogicalClock::hybrid("remote", 9000, 2);
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    9000,
+                    remote,
+                )),
+                9000,
+            )
+            .unwrap();
+        assert_eq!(store.tick_at(6000).physical_time(), 9000);
+    }
+
+    #[test]
+    fn max_drift_rejects_far_future_clocks() {
+        let mut store = test_store();
+        store.set_max_drift(Some(1000));
+        let create = |op_id: &str, id: &str, physical| {
+            Operation::Create(CreateOp::new(
+                op_id,
+                id,
+                "users",
+                json!({"name": "Alice"}),
+                physical,
+                crate::LogicalClock::hybrid("remote", physical, 0),
+            ))
+        };
+
+        assert_eq!(
+            store.apply(create("op-1", "user-1", 9000), 5000),
+            Err(Error::ClockDrift {
+                drift: 4000,
+                max_drift: 1000
+            })
+        );
+        assert!(store.get("users", "user-1").is_none());
+        assert_eq!(store.clock().counter, 0);
+
+        store.apply(create("op-2", "user-2", 5500), 5000).unwrap();
+        assert_eq!(store.clock().physical_time(), 5500);
+
+        // Remote operations too far ahead are rejected with their transaction
+        let result = store.reconcile_at(
+            vec![
+                create("op-3", "user-3", 6000),
+                create("op-4", "user-4", 99_000).with_transaction_id("tx-1"),
+                create("op-5", "user-5", 6000).with_transaction_id("tx-1"),
+            ],
+            crate::MergeStrategy::ClockWins,
+            5000,
+        );
+        assert_eq!(result.applied_remote, vec!["op-3".to_string()]);
+        assert_eq!(
+            result.rejected_remote,
+            vec!["op-4".to_string(), "op-5".into()]
+        );
+        assert!(store.get("users", "user-3").is_some());
+        assert!(store.get("users", "user-5").is_none());
+        assert_eq!(store.clock().physical_time(), 6000);
+    }
+
+    #[test]
+    fn apply_create() {
+        let mut store = test_store();
+        let clock = store.tick();
+
+        let op = Operation::Create(CreateOp::new(
+            "op-1",
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            1000,
+            clock,
+        ));
+
+        let result = store.apply(op, 1000).unwrap();
+        assert_eq!(result.record_id, "user-1");
+        assert_eq!(result.version, 1);
+
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload, json!({"name": "Alice"}));
+    }
+
+    #[test]
+    fn apply_create_duplicate() {
+        let mut store = test_store();
+        let clock = store.tick();
+
+        let op = Operation::Create(CreateOp::new(
+            "op-1",
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            1000,
+            clock,
+        ));
+        store.apply(op, 1000).unwrap();
+
+        // Try to create same ID again
+        let clock2 = store.tick();
+        let op2 = Operation::Create(CreateOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            json!({"name": "Bob"}),
+            2000,
+            clock2,
+        ));
+
+        let result = store.apply(op2, 2000);
+        assert!(matches!(result, Err(Error::RecordAlreadyExists(_))));
+    }
+
+    fn create_op(op_id: &str, id: &str, payload: serde_json::Value) -> Operation {
+        Operation::Create(CreateOp::new(
+            op_id,
+            id,
+            "users",
+            payload,
+            1000,
+            crate::LogicalClock::with_counter("test-node", 1),
+        ))
+    }
+
+    #[test]
+    fn apply_batch() {
+        let mut store = test_store();
+
+        let results = store
+            .apply_batch(
+                vec![
+                    create_op("op-1", "user-1", json!({"name": "Alice"})),
+                    create_op("op-2", "user-2", json!({"name": "Bob"})),
+                ],
+                1000,
+            )
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(store.collection("users").unwrap().len(), 2);
+        assert!(store
+            .pending_ops()
+            .iter()
+            .all(|p| p.operation.transaction_id() == Some(&"op-1".to_string())));
+    }
+
+    #[test]
+    fn apply_batch_rolls_back_on_failure() {
+        let mut store = test_store();
+        store
+            .apply(create_op("op-0", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        let clock = store.clock().clone();
+
+        let result = store.apply_batch(
+            vec![
+                Operation::Update(UpdateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alicia"}),
+                    1,
+                    2000,
+                    crate::LogicalClock::with_counter("test-node", 5),
+                )),
+                create_op("op-2", "user-2", json!({"name": "Bob"})),
+                // Missing required name
+                create_op("op-3", "user-3", json!({"age": 3})),
+            ],
+            2000,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alice"
+        );
+        assert!(store.get_including_deleted("users", "user-2").is_none());
+        assert_eq!(store.pending_ops().len(), 1);
+        assert_eq!(store.clock(), &clock);
+    }
+
+    #[test]
+    fn apply_update() {
+        let mut store = test_store();
+
+        // Create first
+        let clock1 = store.tick();
+        let create = Operation::Create(CreateOp::new(
+            "op-1",
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            1000,
+            clock1,
+        ));
+        store.apply(create, 1000).unwrap();
+
+        // Update
+        let clock2 = store.tick();
+        let update = Operation::Update(UpdateOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            json!({"name": "Alice Smith", "age": 30}),
+            1, // base_version
+            2000,
+            clock2,
+        ));
+        let result = store.apply(update, 2000).unwrap();
+        assert_eq!(result.version, 2);
+
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload, json!({"name": "Alice Smith", "age": 30}));
+
+        // Pending update is stamped with the fields it changed
+        match &store.pending_ops()[1].operation {
+            Operation::Update(update_op) => assert_eq!(
+                update_op.changed_fields,
+                Some(["age".to_string(), "name".to_string()].into())
+            ),
+            other => panic!("expected update, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn apply_update_version_mismatch() {
+        let mut store = test_store();
+
+        // Create
+        let clock1 = store.tick();
+        let create = Operation::Create(CreateOp::new(
+            "op-1",
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            1000,
+            clock1,
+        ));
+        store.apply(create, 1000).unwrap();
+
+        // Update with wrong base version
+        let clock2 = store.tick();
+        let update = Operation::Update(UpdateOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            json!({"name": "Alice Smith"}),
+            5, // wrong base_version
+            2000,
+            clock2,
+        ));
+
+        let result = store.apply(update, 2000);
+        assert!(matches!(
+            result,
+            Err(Error::VersionMismatch {
+                expected: 5,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn apply_patch() {
+        use crate::{Patch, PatchOp};
+
+        let mut store = test_store();
+
+        let clock1 = store.tick();
+        let create = Operation::Create(CreateOp::new(
+            "op-1",
+            "user-1",
+            "users",
+            json!({"name": "Alice", "age": 30}),
+            1000,
+            clock1,
+        ));
+        store.apply(create, 1000).unwrap();
+
+        let clock2 = store.tick();
+        let patch = Operation::Patch(PatchOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            Patch::MergePatch(json!({"age": 31})),
+            1,
+            2000,
+            clock2,
+        ));
+        let result = store.apply(patch, 2000).unwrap();
+        assert_eq!(result.version, 2);
+
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload, json!({"name": "Alice", "age": 31}));
+        assert_eq!(store.pending_count(), 2);
+    }
+
+    #[test]
+    fn apply_patch_invalid_result() {
+        use crate::{Patch, PatchOp};
+
+        let mut store = test_store();
+
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        // Nulling out a required field must be rejected
+        let clock2 = store.tick();
+        let patch = Operation::Patch(PatchOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            Patch::MergePatch(json!({"name": null})),
+            1,
+            2000,
+            clock2,
+        ));
+        let result = store.apply(patch, 2000);
+        assert!(matches!(result, Err(Error::MissingRequiredField(_))));
+
+        // Record and pending ops are unchanged
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.version, 1);
+        assert_eq!(store.pending_count(), 1);
+    }
+
+    #[test]
+    fn apply_delete() {
+        let mut store = test_store();
+
+        // Create
+        let clock1 = store.tick();
+        let create = Operation::Create(CreateOp::new(
+            "op-1",
+            "user-1",
+            "users",
+            json!({"name": "Alice"}),
+            1000,
+            clock1,
+        ));
+        store.apply(create, 1000).unwrap();
+
+        // Delete
+        let clock2 = store.tick();
+        let delete = Operation::Delete(DeleteOp::new("op-2", "user-1", "users", 1, 2000, clock2));
+        let result = store.apply(delete, 2000).unwrap();
+        assert_eq!(result.version, 2);
+
+        // Should not be found via normal get
+        assert!(store.get("users", "user-1").is_none());
+
+        // But should be found including deleted
+        let record = store.get_including_deleted("users", "user-1").unwrap();
+        assert!(record.deleted);
+    }
+
+    #[test]
+    fn apply_update_on_deleted() {
+        let mut store = test_store();
+
+        // Create and delete
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        let clock2 = store.tick();
+        store
+            .apply(
+                Operation::Delete(DeleteOp::new("op-2", "user-1", "users", 1, 2000, clock2)),
+                2000,
+            )
+            .unwrap();
+
+        // Try to update deleted record
+        let clock3 = store.tick();
+        let update = Operation::Update(UpdateOp::new(
+            "op-3",
+            "user-1",
+            "users",
+            json!({"name": "Alice Smith"}),
+            2,
+            3000,
+            clock3,
+        ));
+
+        let result = store.apply(update, 3000);
+        assert!(matches!(result, Err(Error::OperationOnDeleted(_))));
+    }
+
+    #[test]
+    fn apply_increment() {
+        use crate::IncrementOp;
+
+        let mut store = test_store();
+        store.tick();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+
+        for (op_id, delta) in [("op-2", 5), ("op-3", -2)] {
+            let clock = store.tick();
+            let op = Operation::Increment(IncrementOp::new(
+                op_id, "user-1", "users", "age", delta, 2000, clock,
+            ));
+            store.apply(op, 2000).unwrap();
+        }
+
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload["age"], 3);
+        assert_eq!(record.version, 3);
+    }
+
+    fn crdt_store(node_id: &str) -> Store {
+        let schema = Schema::new(1).with_collection(CollectionSchema::new(
+            "tasks",
+            vec![
+                FieldDef::required("title", FieldType::String),
+                FieldDef::optional("tags", FieldType::List),
+                FieldDef::optional("notes", FieldType::Text),
+                FieldDef::optional("labels", FieldType::Set),
+            ],
+        ));
+        let mut store = Store::new(schema, node_id);
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-create",
+                    "task-1",
+                    "tasks",
+                    json!({"title": "Ship", "tags": ["a"], "notes": "ab", "labels": ["x"]}),
+                    1000,
+                    LogicalClock::with_counter("origin", 1),
+                )),
+                1000,
+            )
+            .unwrap();
+        store.clear_pending();
+        store
+    }
+
+    fn list_op(store: &mut Store, op_id: &str, field: &str, action: ListAction) -> Operation {
+        let clock = store.tick();
+        Operation::List(ListOp::new(
+            op_id, "task-1", "tasks", field, action, 2000, clock,
+        ))
+    }
+
+    fn set_op(store: &mut Store, op_id: &str, action: SetAction) -> Operation {
+        let clock = store.tick();
+        Operation::Set(SetOp::new(
+            op_id, "task-1", "tasks", "labels", action, 2000, clock,
+        ))
+    }
+
+    fn text_op(
+        store: &mut Store,
+        op_id: &str,
+        index: usize,
+        delete: usize,
+        insert: &str,
+    ) -> Operation {
+        let splice = store
+            .get("tasks", "task-1")
+            .unwrap()
+            .text("notes")
+            .splice_at(index, delete, insert);
+        let clock = store.tick();
+        Operation::Text(TextOp::new(
+            op_id, "task-1", "tasks", "notes", splice, 2000, clock,
+        ))
+    }
+
+    #[test]
+    fn apply_list() {
+        let mut store = crdt_store("test-node");
+        let first = "origin:1:0".to_string();
+
+        let insert = list_op(
+            &mut store,
+            "op-1",
+            "tags",
+            ListAction::Insert {
+                after: Some(first.clone()),
+                value: json!("b"),
+            },
+        );
+        store.apply(insert, 2000).unwrap();
+        let head = list_op(
+            &mut store,
+            "op-2",
+            "tags",
+            ListAction::Move {
+                element: "op-1".to_string(),
+                after: None,
+            },
+        );
+        store.apply(head, 2000).unwrap();
+        let remove = list_op(
+            &mut store,
+            "op-3",
+            "tags",
+            ListAction::Remove { element: first },
+        );
+        let result = store.apply(remove, 2000).unwrap();
+
+        let record = store.get("tasks", "task-1").unwrap();
+        assert_eq!(record.payload["tags"], json!(["b"]));
+        assert_eq!(result.version, 4);
+
+        // Only List fields take list operations
+        let wrong_field = list_op(
+            &mut store,
+            "op-4",
+            "title",
+            ListAction::Remove {
+                element: "op-1".to_string(),
+            },
+        );
+        assert!(matches!(
+            store.apply(wrong_field, 2000),
+            Err(Error::TypeMismatch { .. })
+        ));
+
+        // Unknown elements are rejected
+        let unknown = list_op(
+            &mut store,
+            "op-5",
+            "tags",
+            ListAction::Remove {
+                element: "missing".to_string(),
+            },
+        );
+        assert!(matches!(
+            store.apply(unknown, 2000),
+            Err(Error::InvalidCrdt(_))
+        ));
+    }
+
+    #[test]
+    fn apply_text() {
+        let mut store = crdt_store("test-node");
+
+        let op = text_op(&mut store, "op-1", 2, 0, "cd");
+        store.apply(op, 2000).unwrap();
+        let op = text_op(&mut store, "op-2", 0, 1, "A");
+        store.apply(op, 2000).unwrap();
+
+        // The payload holds the plain string, so queries see it
+        let matches = store
+            .query("tasks")
+            .unwrap()
+            .filter(|p| p["notes"] == "Abcd");
+        assert_eq!(matches.len(), 1);
+
+        // A full-payload write replaces the text; later splices build on it
+        let record = store.get("tasks", "task-1").unwrap();
+        let (version, mut payload) = (record.version, record.payload.clone());
+        payload["notes"] = json!("new");
+        let clock = store.tick();
+        store
+            .apply(
+                Operation::Update(UpdateOp::new(
+                    "op-3", "task-1", "tasks", payload, version, 3000, clock,
+                )),
+                3000,
+            )
+            .unwrap();
+        let op = text_op(&mut store, "op-4", 3, 0, "er");
+        store.apply(op, 3000).unwrap();
+        assert_eq!(
+            store.get("tasks", "task-1").unwrap().payload["notes"],
+            "newer"
+        );
+    }
+
+    #[test]
+    fn apply_set() {
+        let mut store = crdt_store("test-node");
+
+        for (op_id, value) in [("op-1", "c"), ("op-2", "a"), ("op-3", "c")] {
+            let op = set_op(
+                &mut store,
+                op_id,
+                SetAction::Add {
+                    value: json!(value),
+                },
+            );
+            store.apply(op, 2000).unwrap();
+        }
+        let labels =
+            |store: &Store| store.get("tasks", "task-1").unwrap().payload["labels"].clone();
+        assert_eq!(labels(&store), json!(["a", "c", "x"]));
+
+        // The store fills in the adds a remove has observed
+        let remove = set_op(
+            &mut store,
+            "op-4",
+            SetAction::Remove {
+                value: json!("c"),
+                observed: Vec::new(),
+            },
+        );
+        store.apply(remove, 2000).unwrap();
+        assert_eq!(labels(&store), json!(["a", "x"]));
+
+        let pending = store.pending_ops().last().unwrap();
+        match &pending.operation {
+            Operation::Set(SetOp {
+                action: SetAction::Remove { observed, .. },
+                ..
+            }) => assert_eq!(observed, &vec!["op-1".to_string(), "op-3".to_string()]),
+            other => panic!("unexpected operation: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compact_pending_folds_record_edits() {
+        let mut store = test_store();
+        let update = |store: &mut Store, op_id: &str, id: &str, name: &str| {
+            let version = store.get("users", id).unwrap().version;
+            let clock = store.tick();
+            let op = Operation::Update(UpdateOp::new(
+                op_id,
+                id,
+                "users",
+                json!({"name": name}),
+                version,
+                2000,
+                clock,
+            ));
+            store.apply(op, 2000).unwrap();
+        };
+
+        store.tick();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "A"})), 1000)
+            .unwrap();
+        store
+            .apply(create_op("op-2", "user-2", json!({"name": "B"})), 1000)
+            .unwrap();
+        store.acknowledge(&["op-2".to_string()]);
+
+        // Edits to two records interleave
+        update(&mut store, "op-3", "user-1", "Al");
+        update(&mut store, "op-4", "user-2", "Bo");
+        update(&mut store, "op-5", "user-1", "Alice");
+        update(&mut store, "op-6", "user-2", "Bob");
+
+        // A transaction is never folded
+        let clock = store.tick();
+        store
+            .apply_batch(
+                vec![Operation::Update(UpdateOp::new(
+                    "op-7",
+                    "user-2",
+                    "users",
+                    json!({"name": "Bobby"}),
+                    3,
+                    3000,
+                    clock,
+                ))],
+                3000,
+            )
+            .unwrap();
+
+        assert!(store.compact_pending().is_empty());
+        let pending = store.pending_ops();
+        assert_eq!(pending.len(), 3);
+
+        // user-1: create + updates became one create
+        match &pending[0].operation {
+            Operation::Create(create) => {
+                assert_eq!(create.op_id, "op-5");
+                assert_eq!(create.payload, json!({"name": "Alice"}));
+            }
+            other => panic!("unexpected operation: {:?}", other),
+        }
+        assert_eq!(pending[0].folded, vec!["op-1", "op-3"]);
+
+        // user-2: updates became one update against the first base version
+        match &pending[1].operation {
+            Operation::Update(update) => {
+                assert_eq!(update.op_id, "op-6");
+                assert_eq!(update.base_version, 1);
+                assert_eq!(update.payload, json!({"name": "Bob"}));
+            }
+            other => panic!("unexpected operation: {:?}", other),
+        }
+        assert_eq!(pending[2].operation.op_id(), "op-7");
+
+        // Compacting again changes nothing
+        let before = store.pending_ops().to_vec();
+        store.compact_pending();
+        assert_eq!(store.pending_ops(), before.as_slice());
+
+        // Acknowledging a folded operation acknowledges what it became
+        store.acknowledge(&["op-3".to_string()]);
+        let remaining: Vec<_> = store
+            .pending_ops()
+            .iter()
+            .map(|p| p.operation.op_id().as_str())
+            .collect();
+        assert_eq!(remaining, ["op-6", "op-7"]);
+    }
+
+    #[test]
+    fn apply_upsert() {
+        use crate::UpsertOp;
+
+        let mut store = test_store();
+        let upsert = |store: &mut Store, op_id: &str, name: &str| {
+            let clock = store.tick();
+            let op = Operation::Upsert(UpsertOp::new(
+                op_id,
+                "user-1",
+                "users",
+                json!({"name": name}),
+                1000,
+                clock,
+            ));
+            store.apply(op, 1000).unwrap().version
+        };
+
+        // Missing: created
+        assert_eq!(upsert(&mut store, "op-1", "Alice"), 1);
+
+        // Present: replaced without a version check
+        assert_eq!(upsert(&mut store, "op-2", "Alicia"), 2);
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alicia"
+        );
+
+        // Deleted: revived
+        let clock = store.tick();
+        store
+            .apply(
+                Operation::Delete(DeleteOp::new("op-3", "user-1", "users", 2, 2000, clock)),
+                2000,
+            )
+            .unwrap();
+        assert_eq!(upsert(&mut store, "op-4", "Bob"), 4);
+        assert_eq!(store.get("users", "user-1").unwrap().payload["name"], "Bob");
+    }
+
+    #[test]
+    fn apply_restore() {
+        use crate::RestoreOp;
+
+        let mut store = test_store();
+
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        // Restoring an active record is rejected
+        let clock2 = store.tick();
+        let early = Operation::Restore(RestoreOp::new(
+            "op-2",
+            "user-1",
+            "users",
+            json!({"name": "Alicia"}),
+            1,
+            2000,
+            clock2,
+        ));
+        assert_eq!(
+            store.apply(early, 2000),
+            Err(Error::RecordNotDeleted("user-1".into()))
+        );
+
+        let clock3 = store.tick();
+        store
+            .apply(
+                Operation::Delete(DeleteOp::new("op-3", "user-1", "users", 1, 3000, clock3)),
+                3000,
+            )
+            .unwrap();
+
+        // A create cannot reuse the tombstone's ID
+        let clock4 = store.tick();
+        let create = Operation::Create(CreateOp::new(
+            "op-4",
+            "user-1",
+            "users",
+            json!({"name": "Alicia"}),
+            4000,
+            clock4,
+        ));
+        assert!(store.apply(create, 4000).is_err());
+
+        let clock5 = store.tick();
+        let restore = Operation::Restore(RestoreOp::new(
+            "op-5",
+            "user-1",
+            "users",
+            json!({"name": "Alicia"}),
+            2,
+            5000,
+            clock5,
+        ));
+        let result = store.apply(restore, 5000).unwrap();
+        assert_eq!(result.version, 3);
+
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload["name"], "Alicia");
+    }
+
+    fn history_store(limit: usize) -> Store {
+        let schema = Schema::new(1).with_collection(
+            CollectionSchema::new("users", vec![FieldDef::required("name", FieldType::String)])
+                .with_history(limit),
+        );
+        Store::new(schema, "test-node")
+    }
+
+    fn rename(store: &mut Store, op_id: &str, name: &str, base_version: Version) {
+        let clock = store.tick();
+        let timestamp = clock.counter * 1000;
+        store
+            .apply(
+                Operation::Update(UpdateOp::new(
+                    op_id,
+                    "user-1",
+                    "users",
+                    json!({"name": name}),
+                    base_version,
+                    timestamp,
+                    clock,
+                )),
+                timestamp,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn record_history() {
+        let mut store = history_store(3);
+        let clock = store.tick();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        rename(&mut store, "op-2", "Alicia", 1);
+        rename(&mut store, "op-3", "Ali", 2);
+        rename(&mut store, "op-4", "Al", 3);
+
+        // Only the last three versions are kept
+        let history = store.history("users", "user-1");
+        assert_eq!(
+            history.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(history[0].op_id.as_deref(), Some("op-2"));
+        assert!(store.record_at("users", "user-1", 1).is_none());
+
+        let at_clock = store.history("users", "user-1")[1].clock.clone();
+        let version = store.record_at("users", "user-1", at_clock).unwrap();
+        assert_eq!(version.payload, json!({"name": "Ali"}));
+        assert_eq!(version.op_id.as_deref(), Some("op-3"));
+
+        // Clocks before the oldest kept version find nothing
+        assert!(store.record_at("users", "user-1", clock).is_none());
+
+        // History survives a snapshot
+        let snapshot = store.export_state();
+        let mut restored = history_store(3);
+        restored.import_state(snapshot).unwrap();
+        assert_eq!(restored.history("users", "user-1"), history);
+
+        // Reconciled writes are logged with the operation that wrote them
+        store.clear_pending();
+        let remote = Operation::Update(UpdateOp::new(
+            "op-r",
+            "user-1",
+            "users",
+            json!({"name": "Remote"}),
+            4,
+            9000,
+            crate::LogicalClock::with_counter("remote", 50),
+        ));
+        store.reconcile(vec![remote], crate::MergeStrategy::ClockWins);
+        let latest = store.history("users", "user-1").last().unwrap();
+        assert_eq!(latest.version, 5);
+        assert_eq!(latest.op_id.as_deref(), Some("op-r"));
+        assert_eq!(latest.origin, crate::Origin::Remote);
+
+        // Collections without a limit keep no history
+        let mut plain = test_store();
+        plain
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        assert!(plain.history("users", "user-1").is_empty());
+    }
+
+    #[test]
+    fn revert_to_history() {
+        let mut store = history_store(10);
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        rename(&mut store, "op-2", "Bob", 1);
+        store.clear_pending();
+
+        let result = store.revert_to("op-3", "users", "user-1", 1, 3000).unwrap();
+        assert_eq!(result.version, 3);
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alice"
+        );
+
+        // The revert is a normal update waiting to sync
+        assert_eq!(store.pending_count(), 1);
+        let Operation::Update(update) = &store.pending_ops()[0].operation else {
+            panic!("expected an update");
+        };
+        assert_eq!(update.base_version, 2);
+
+        // Reverting to a tombstone deletes the record
+        let clock = store.tick();
+        store
+            .apply(
+                Operation::Delete(DeleteOp::new("op-4", "user-1", "users", 3, 4000, clock)),
+                4000,
+            )
+            .unwrap();
+        store.revert_to("op-5", "users", "user-1", 2, 5000).unwrap();
+        assert_eq!(store.get("users", "user-1").unwrap().payload["name"], "Bob");
+        store.revert_to("op-6", "users", "user-1", 4, 6000).unwrap();
+        assert!(store.get("users", "user-1").is_none());
+
+        assert_eq!(
+            store.revert_to("op-7", "users", "user-1", 42, 7000),
+            Err(Error::VersionNotInHistory("user-1".into()))
+        );
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut store = test_store();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        rename(&mut store, "op-2", "Bob", 1);
+
+        // The pushed operations are acknowledged before undoing
+        store.acknowledge(&["op-1".to_string(), "op-2".to_string()]);
+
+        let results = store.undo("op-3", 3000).unwrap().unwrap();
+        assert_eq!(results[0].version, 3);
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alice"
+        );
+        let ops: Vec<_> = store.pending_ops().iter().map(|p| &p.operation).collect();
+        assert!(matches!(ops[..], [Operation::Update(_)]));
+
+        // Undoing the create deletes the record; redo restores it
+        store.undo("op-4", 4000).unwrap().unwrap();
+        assert!(store.get("users", "user-1").is_none());
+        assert_eq!(store.undo("op-5", 5000), Ok(None));
+
+        store.redo("op-6", 6000).unwrap().unwrap();
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alice"
+        );
+        store.redo("op-7", 7000).unwrap().unwrap();
+        assert_eq!(store.get("users", "user-1").unwrap().payload["name"], "Bob");
+        assert_eq!(store.redo("op-8", 8000), Ok(None));
+
+        // A new edit after an undo drops the redo steps
+        store.undo("op-9", 9000).unwrap();
+        assert!(store.undo_manager().can_redo());
+        rename(&mut store, "op-10", "Carol", 7);
+        assert!(!store.undo_manager().can_redo());
+        assert_eq!(store.undo_manager().undo_steps().len(), 2);
+    }
+
+    #[test]
+    fn undo_redo_batch() {
+        let mut store = test_store();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        let clock = store.tick();
+        store
+            .apply_batch(
+                vec![
+                    create_op("op-2", "user-2", json!({"name": "Bob"})),
+                    Operation::Update(UpdateOp::new(
+                        "op-3",
+                        "user-1",
+                        "users",
+                        json!({"name": "Alicia"}),
+                        1,
+                        2000,
+                        clock,
+                    )),
+                ],
+                2000,
+            )
+            .unwrap();
+        assert_eq!(store.undo_manager().undo_steps().len(), 2);
+        store.acknowledge(&["op-1".into(), "op-2".into(), "op-3".into()]);
+
+        // The batch is undone in one step, with one transaction
+        let results = store.undo("op-4", 3000).unwrap().unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(store.get("users", "user-2").is_none());
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alice"
+        );
+        let ops: Vec<_> = store.pending_ops().iter().map(|p| &p.operation).collect();
+        assert_eq!(ops[0].op_id(), "op-4");
+        assert_eq!(ops[1].op_id(), "op-4:1");
+        assert!(ops
+            .iter()
+            .all(|op| op.transaction_id() == Some(&"op-4".to_string())));
+
+        store.redo("op-5", 4000).unwrap().unwrap();
+        assert_eq!(store.get("users", "user-2").unwrap().payload["name"], "Bob");
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alicia"
+        );
+        assert_eq!(store.undo_manager().undo_steps().len(), 2);
+    }
+
+    #[test]
+    fn undo_skips_steps_rewritten_by_reconcile() {
+        let mut store = test_store();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        store
+            .apply(create_op("op-2", "user-2", json!({"name": "Bob"})), 1000)
+            .unwrap();
+        rename(&mut store, "op-3", "Alicia", 1);
+        store.clear_pending();
+
+        // A remote edit lands on user-1 after the local rename
+        let remote = Operation::Update(UpdateOp::new(
+            "op-r",
+            "user-1",
+            "users",
+            json!({"name": "Remote"}),
+            2,
+            9000,
+            crate::LogicalClock::with_counter("remote", 50),
+        ));
+        store.reconcile(vec![remote], crate::MergeStrategy::ClockWins);
+
+        // Only the step on user-2 is left to undo
+        store.undo("op-4", 4000).unwrap().unwrap();
+        assert!(store.get("users", "user-2").is_none());
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Remote"
+        );
+        assert!(!store.undo_manager().can_undo());
+    }
+
+    #[test]
+    fn purge_tombstones() {
+        let mut store = test_store();
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        store
+            .apply(create_op("op-2", "user-2", json!({"name": "Bob"})), 1000)
+            .unwrap();
+        for (op_id, id) in [("op-3", "user-1"), ("op-4", "user-2")] {
+            let clock = store.tick();
+            store
+                .apply(
+                    Operation::Delete(DeleteOp::new(op_id, id, "users", 1, 2000, clock)),
+                    2000,
+                )
+                .unwrap();
+        }
+        store.acknowledge(&["op-1".into(), "op-2".into(), "op-3".into()]);
+
+        let stable = store
+            .get_including_deleted("users", "user-2")
+            .unwrap()
+            .metadata
+            .version_vector
+            .clone();
+
+        // Too recent
+        assert_eq!(store.purge_tombstones(2000, &stable), 0);
+
+        // Not yet seen by every node
+        let mut unseen = stable.clone();
+        unseen.meet(&crate::VersionVector::new());
+        assert_eq!(store.purge_tombstones(3000, &unseen), 0);
+
+        // user-2 still has its delete pending
+        assert_eq!(store.purge_tombstones(3000, &stable), 1);
+        assert!(store.get_including_deleted("users", "user-1").is_none());
+        assert!(store.get_including_deleted("users", "user-2").is_some());
+
+        store.acknowledge(&["op-4".into()]);
+        assert_eq!(store.purge_tombstones(3000, &stable), 1);
+        assert_eq!(store.export_state().record_count(), 0);
+    }
+
+    fn session_store(node_id: &str) -> Store {
+        let schema = Schema::new(1).with_collection(
+            CollectionSchema::new(
+                "sessions",
+                vec![FieldDef::required("user", FieldType::String)],
+            )
+            .with_ttl(10_000),
+        );
+        Store::new(schema, node_id)
+    }
+
+    fn open_session(store: &mut Store, id: &str, timestamp: Timestamp, expires_at: Option<u64>) {
+        let clock = store.tick();
+        let mut op = CreateOp::new(
+            format!("op-{id}"),
+            id,
+            "sessions",
+            json!({"user": "alice"}),
+            timestamp,
+            clock,
+        );
+        if let Some(expires_at) = expires_at {
+            op = op.with_expires_at(expires_at);
+        }
+        store.apply(Operation::Create(op), timestamp).unwrap();
+    }
+
+    #[test]
+    fn ttl_hides_expired_records() {
+        let mut store = session_store("test-node");
+        open_session(&mut store, "s1", 1000, None);
+        // Its own expiry overrides the collection's TTL
+        open_session(&mut store, "s2", 1000, Some(50_000));
+
+        assert!(store.get_at("sessions", "s1", 10_999).is_some());
+        assert!(store.get_at("sessions", "s1", 11_000).is_none());
+        assert!(store.get_at("sessions", "s2", 11_000).is_some());
+
+        let query = || store.query("sessions").unwrap();
+        assert_eq!(query().at(5000).count(), 2);
+        assert_eq!(query().at(11_000).all().len(), 1);
+        assert_eq!(query().at(50_000).count(), 0);
+
+        // Without a time, expired records still show until swept
+        assert!(store.get("sessions", "s1").is_some());
+        assert_eq!(query().count(), 2);
+    }
+
+    #[test]
+    fn expire_deletes_deterministically() {
+        let mut store = session_store("test-node");
+        open_session(&mut store, "s2", 1000, None);
+        open_session(&mut store, "s1", 1000, None);
+        open_session(&mut store, "s3", 1000, Some(100_000));
+        let creates: Vec<_> = store
+            .pending_ops()
+            .iter()
+            .map(|p| p.operation.clone())
+            .collect();
+        store.acknowledge(&["op-s1".into(), "op-s2".into(), "op-s3".into()]);
+
+        // Another node touches s1, and reconciles with the update still
+        // pending before the two sync
+        let mut replica = session_store("other-node");
+        let result = replica.reconcile(creates, crate::MergeStrategy::ClockWins);
+        assert_eq!(result.applied_remote.len(), 3);
+        let clock = replica.tick();
+        let touch = Operation::Update(UpdateOp::new(
+            "op-touch",
+            "s1",
+            "sessions",
+            json!({"user": "bob"}),
+            1,
+            2000,
+            clock,
+        ));
+        replica.apply(touch, 2000).unwrap();
+        replica.reconcile(Vec::new(), crate::MergeStrategy::ClockWins);
+        let touch = replica.pending_ops()[0].operation.clone();
+        store.reconcile(vec![touch], crate::MergeStrategy::ClockWins);
+
+        assert_eq!(store.expire(11_000).unwrap().len(), 1);
+        let results = store.expire(20_000).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].op_id, "expire:sessions:s1:other-node:1");
+
+        let ops: Vec<_> = store.pending_ops().iter().map(|p| &p.operation).collect();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].op_id(), "expire:sessions:s2:test-node:1");
+        // The delete is timestamped when the record expired, not when swept
+        assert_eq!(ops[0].timestamp(), 11_000);
+        assert_eq!(ops[1].timestamp(), 12_000);
+
+        assert!(store.get("sessions", "s1").is_none());
+        assert!(store.get("sessions", "s3").is_some());
+        // Only the creates of s2 and s3 can be undone; s1 was rewritten
+        assert_eq!(store.undo_manager().undo_steps().len(), 2);
+
+        // The other node sweeping the same records writes the same deletes,
+        // IDs and clocks included, even though it received the creates
+        // later and its copy of s1 went through a reconcile
+        replica.expire(11_000).unwrap();
+        replica.expire(20_000).unwrap();
+        let replica_ops: Vec<_> = replica
+            .pending_ops()
+            .iter()
+            .map(|p| &p.operation)
+            .filter(|op| op.op_id().starts_with("expire:"))
+            .collect();
+        assert_eq!(replica_ops, ops);
+    }
+
+    #[test]
+    fn pending_ops_tracking() {
+        let mut store = test_store();
+
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        let clock2 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-2",
+                    "user-2",
+                    "users",
+                    json!({"name": "Bob"}),
+                    2000,
+                    clock2,
+                )),
+                2000,
+            )
+            .unwrap();
+
+        assert_eq!(store.pending_count(), 2);
+
+        // Acknowledge one
+        store.acknowledge(&["op-1".to_string()]);
+        assert_eq!(store.pending_count(), 1);
+        assert_eq!(store.pending_ops()[0].operation.op_id(), "op-2");
+    }
+
+    #[test]
+    fn query_all() {
+        let mut store = test_store();
+
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        let clock2 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-2",
+                    "user-2",
+                    "users",
+                    json!({"name": "Bob"}),
+                    2000,
+                    clock2,
+                )),
+                2000,
+            )
+            .unwrap();
+
+        let results = store.query("users").unwrap().all();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn query_filter() {
+        let mut store = test_store();
+
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice", "age": 30}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        let clock2 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-2",
+                    "user-2",
+                    "users",
+                    json!({"name": "Bob", "age": 25}),
+                    2000,
+                    clock2,
+                )),
+                2000,
+            )
+            .unwrap();
+
+        let results = store.query("users").unwrap().filter(|payload| {
+            payload
+                .get("age")
+                .and_then(|v| v.as_i64())
+                .map(|age| age >= 30)
+                .unwrap_or(false)
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "user-1");
+    }
+
+    #[test]
+    fn query_include_deleted() {
+        let mut store = test_store();
+
+        // Create two users
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        let clock2 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-2",
+                    "user-2",
+                    "users",
+                    json!({"name": "Bob"}),
+                    2000,
+                    clock2,
+                )),
+                2000,
+            )
+            .unwrap();
+
+        // Delete one
+        let clock3 = store.tick();
+        store
+            .apply(
+                Operation::Delete(DeleteOp::new("op-3", "user-1", "users", 1, 3000, clock3)),
+                3000,
+            )
+            .unwrap();
+
+        // Without deleted
+        assert_eq!(store.query("users").unwrap().count(), 1);
+
+        // With deleted
+        assert_eq!(store.query("users").unwrap().include_deleted().count(), 2);
+    }
+
+    #[test]
+    fn collection_not_found() {
+        let mut store = test_store();
+        let clock = store.tick();
+
+        let op = Operation::Create(CreateOp::new(
+            "op-1",
+            "post-1",
+            "posts", // doesn't exist
+            json!({"title": "Hello"}),
+            1000,
+            clock,
+        ));
+
+        let result = store.apply(op, 1000);
+        assert!(matches!(result, Err(Error::CollectionNotFound(_))));
+    }
+
+    #[test]
+    fn record_not_found() {
+        let mut store = test_store();
+        let clock = store.tick();
+
+        let op = Operation::Update(UpdateOp::new(
+            "op-1",
+            "user-999",
+            "users",
+            json!({"name": "Ghost"}),
+            1,
+            1000,
+            clock,
+        ));
+
+        let result = store.apply(op, 1000);
+        assert!(matches!(result, Err(Error::RecordNotFound(_))));
+    }
+
+    #[test]
+    fn store_serialization() {
+        let mut store = test_store();
+
+        let clock = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-1",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        let json = serde_json::to_string(&store).unwrap();
+        let restored: Store = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.node_id(), store.node_id());
+        assert!(restored.get("users", "user-1").is_some());
+    }
+
+    #[test]
+    fn store_reconcile_with_remote() {
+        use crate::reconcile::MergeStrategy;
+        use crate::LogicalClock;
+
+        let mut store = test_store();
+
+        // Local creates user-1
+        let clock1 = store.tick();
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-local",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock1,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        assert_eq!(store.pending_count(), 1);
+
+        // Remote has user-1 with higher clock (wins) and user-2
+        let remote_ops = vec![
+            Operation::Create(CreateOp::new(
+                "op-remote-1",
+                "user-1",
+                "users",
+                json!({"name": "Bob"}),
+                1000,
+                LogicalClock::with_counter("remote", 10), // higher clock wins
+            )),
+            Operation::Create(CreateOp::new(
+                "op-remote-2",
+                "user-2",
+                "users",
+                json!({"name": "Charlie"}),
+                1000,
+                LogicalClock::with_counter("remote", 5),
+            )),
+        ];
+
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+
+        // Local op was rejected (remote had higher clock)
+        assert_eq!(result.rejected_local.len(), 1);
+        assert_eq!(result.conflicts.len(), 1);
+
+        // Pending should be cleared (local op rejected or accepted)
+        assert_eq!(store.pending_count(), 0);
+
+        // user-1 should have Bob (remote won)
+        let user1 = store.get("users", "user-1").unwrap();
+        assert_eq!(user1.payload, json!({"name": "Bob"}));
+
+        // user-2 should exist
+        let user2 = store.get("users", "user-2").unwrap();
+        assert_eq!(user2.payload, json!({"name": "Charlie"}));
+    }
+
+    #[test]
+    fn store_reconcile_conflicts_with_last_operation() {
+        use crate::reconcile::MergeStrategy;
+        use crate::{IncrementOp, LogicalClock};
+
+        let mut store = test_store();
+        store
+            .apply(
+                create_op("op-create", "user-1", json!({"name": "Alice"})),
+                1000,
+            )
+            .unwrap();
+        let update = Operation::Update(UpdateOp::new(
+            "op-update",
+            "user-1",
+            "users",
+            json!({"name": "Alicia"}),
+            1,
+            2000,
+            LogicalClock::with_counter("test-node", 5),
+        ));
+        store.apply(update, 2000).unwrap();
+
+        // Increments commute, so they leave the last operation in place
+        let increment = Operation::Increment(IncrementOp::new(
+            "op-increment",
+            "user-1",
+            "users",
+            "age",
+            1,
+            3000,
+            LogicalClock::with_counter("test-node", 6),
+        ));
+        store.apply(increment, 3000).unwrap();
+        let last_op = store
+            .get("users", "user-1")
+            .unwrap()
+            .metadata
+            .last_op
+            .clone()
+            .unwrap();
+        assert_eq!(last_op.op_id, "op-update");
+        assert_eq!(last_op.timestamp, 2000);
+
+        // Once pushed, a concurrent remote edit conflicts with the update
+        store.clear_pending();
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Bob"}),
+            2,
+            2500,
+            LogicalClock::with_counter("remote", 3),
+        ))];
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.local_op.op_id(), "op-update");
+        assert_eq!(conflict.local_op.timestamp(), 2000);
+        assert_eq!(
+            conflict.local_op.clock(),
+            &LogicalClock::with_counter("test-node", 5)
+        );
+        assert_eq!(conflict.winner_op_id, "op-update");
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload["name"],
+            "Alicia"
+        );
+    }
+
+    #[test]
+    fn store_reconcile_local_wins() {
+        use crate::reconcile::MergeStrategy;
+        use crate::LogicalClock;
+
+        let mut store = test_store();
+
+        // Local creates user-1 with high clock
+        let mut clock = store.tick();
+        clock.counter = 20; // simulate high counter
+        store
+            .apply(
+                Operation::Create(CreateOp::new(
+                    "op-local",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice"}),
+                    1000,
+                    clock,
+                )),
+                1000,
+            )
+            .unwrap();
+
+        // Remote has user-1 with lower clock
+        let remote_ops = vec![Operation::Create(CreateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Bob"}),
+            1000,
+            LogicalClock::with_counter("remote", 5), // lower clock
+        ))];
+
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+
+        // Remote was rejected
+        assert_eq!(result.rejected_remote.len(), 1);
+        assert!(result.rejected_local.is_empty());
+
+        // user-1 should have Alice (local won)
+        let user1 = store.get("users", "user-1").unwrap();
+        assert_eq!(user1.payload, json!({"name": "Alice"}));
+    }
+
+    #[test]
+    fn store_reconcile_concurrent_list_inserts() {
+        use crate::reconcile::MergeStrategy;
+
+        let mut left = crdt_store("left");
+        let mut right = crdt_store("right");
+        let first = "origin:1:0".to_string();
+
+        // Both nodes insert after the same element
+        let left_op = list_op(
+            &mut left,
+            "op-left",
+            "tags",
+            ListAction::Insert {
+                after: Some(first.clone()),
+                value: json!("l"),
+            },
+        );
+        left.apply(left_op.clone(), 2000).unwrap();
+        let right_op = list_op(
+            &mut right,
+            "op-right",
+            "tags",
+            ListAction::Insert {
+                after: Some(first),
+                value: json!("r"),
+            },
+        );
+        right.apply(right_op.clone(), 2000).unwrap();
+
+        let result = left.reconcile(vec![right_op.clone()], MergeStrategy::ClockWins);
+        assert!(result.conflicts.is_empty());
+        let result = right.reconcile(vec![left_op], MergeStrategy::ClockWins);
+        assert!(result.conflicts.is_empty());
+
+        let tags = |store: &Store| store.get("tasks", "task-1").unwrap().payload["tags"].clone();
+        assert_eq!(tags(&left), tags(&right));
+        assert_eq!(tags(&left).as_array().unwrap().len(), 3);
+
+        // Redelivered operations are not applied again
+        left.reconcile(vec![right_op], MergeStrategy::ClockWins);
+        assert_eq!(tags(&left), tags(&right));
+    }
+
+    #[test]
+    fn store_reconcile_rejects_list_op_on_replaced_list() {
+        use crate::reconcile::MergeStrategy;
+
+        let mut left = crdt_store("left");
+        let mut right = crdt_store("right");
+
+        // One node replaces the whole list while the other inserts after an
+        // element the replacement drops
+        let clock = left.tick();
+        let replace = Operation::Update(UpdateOp::new(
+            "op-replace",
+            "task-1",
+            "tasks",
+            json!({"title": "Ship", "tags": ["z"], "notes": "ab", "labels": ["x"]}),
+            1,
+            2000,
+            clock,
+        ));
+        left.apply(replace, 2000).unwrap();
+        let replace = left.pending_ops()[0].operation.clone();
+        let insert = list_op(
+            &mut right,
+            "op-insert",
+            "tags",
+            ListAction::Insert {
+                after: Some("origin:1:0".to_string()),
+                value: json!("b"),
+            },
+        );
+        right.apply(insert, 2000).unwrap();
+
+        let result = right.reconcile(vec![replace], MergeStrategy::ClockWins);
+        assert_eq!(result.applied_remote, vec!["op-replace".to_string()]);
+        assert!(result.rejected_local.contains(&"op-insert".to_string()));
+        assert!(!result.accepted_local.contains(&"op-insert".to_string()));
+        assert_eq!(right.pending_count(), 0);
+        assert_eq!(
+            right.get("tasks", "task-1").unwrap().payload["tags"],
+            json!(["z"])
+        );
+    }
+
+    #[test]
+    fn store_reconcile_concurrent_text_splices() {
+        use crate::reconcile::MergeStrategy;
+
+        let mut left = crdt_store("left");
+        let mut right = crdt_store("right");
+
+        let splice = |store: &mut Store, op_id: &str, index, delete, insert: &str| {
+            let op = text_op(store, op_id, index, delete, insert);
+            store.apply(op.clone(), 2000).unwrap();
+            op
+        };
+
+        // One node appends while the other replaces the first character
+        let left_op = splice(&mut left, "op-left", 2, 0, "c");
+        let right_op = splice(&mut right, "op-right", 0, 1, "A");
+
+        let result = left.reconcile(vec![right_op], MergeStrategy::ClockWins);
+        assert!(result.conflicts.is_empty());
+        right.reconcile(vec![left_op], MergeStrategy::ClockWins);
+
+        let notes = |store: &Store| store.get("tasks", "task-1").unwrap().payload["notes"].clone();
+        assert_eq!(notes(&left), "Abc");
+        assert_eq!(notes(&right), "Abc");
+    }
+
+    #[test]
+    fn store_reconcile_rejects_text_splice_on_replaced_text() {
+        use crate::reconcile::MergeStrategy;
+
+        let mut left = crdt_store("left");
+        let mut right = crdt_store("right");
+
+        // One node replaces the whole text while the other edits the old one
+        let clock = left.tick();
+        left.apply(
+            Operation::Update(UpdateOp::new(
+                "op-replace",
+                "task-1",
+                "tasks",
+                json!({"title": "Ship", "tags": ["a"], "notes": "xyz", "labels": ["x"]}),
+                1,
+                2000,
+                clock,
+            )),
+            2000,
+        )
+        .unwrap();
+        let replace = left.pending_ops()[0].operation.clone();
+        let splice = text_op(&mut right, "op-splice", 1, 1, "B");
+        right.apply(splice, 2000).unwrap();
+
+        let result = right.reconcile(vec![replace], MergeStrategy::ClockWins);
+        assert_eq!(result.applied_remote, vec!["op-replace".to_string()]);
+        assert!(result.rejected_local.contains(&"op-splice".to_string()));
+        assert!(!result.accepted_local.contains(&"op-splice".to_string()));
+        assert_eq!(
+            right.get("tasks", "task-1").unwrap().payload["notes"],
+            json!("xyz")
+        );
+    }
+
+    #[test]
+    fn store_reconcile_set_add_wins() {
+        use crate::reconcile::MergeStrategy;
 
-        let record = store.get("users", "user-1").unwrap();
-        assert_eq!(record.payload, json!({"name": "Alice"}));
+        let mut left = crdt_store("left");
+        let mut right = crdt_store("right");
+
+        // One node removes "x" while the other adds it again
+        let remove = set_op(
+            &mut left,
+            "op-remove",
+            SetAction::Remove {
+                value: json!("x"),
+                observed: Vec::new(),
+            },
+        );
+        left.apply(remove, 2000).unwrap();
+        let remove = left.pending_ops()[0].operation.clone();
+        let add = set_op(&mut right, "op-add", SetAction::Add { value: json!("x") });
+        right.apply(add.clone(), 2000).unwrap();
+
+        let result = left.reconcile(vec![add], MergeStrategy::ClockWins);
+        assert!(result.conflicts.is_empty());
+        right.reconcile(vec![remove], MergeStrategy::ClockWins);
+
+        let labels =
+            |store: &Store| store.get("tasks", "task-1").unwrap().payload["labels"].clone();
+        assert_eq!(labels(&left), json!(["x"]));
+        assert_eq!(labels(&right), json!(["x"]));
     }
 
     #[test]
-    fn apply_create_duplicate() {
-        let mut store = test_store();
-        let clock = store.tick();
+    fn store_reconcile_keeps_base_versions_without_remote_changes() {
+        use crate::reconcile::MergeStrategy;
 
-        let op = Operation::Create(CreateOp::new(
-            "op-1",
-            "user-1",
-            "users",
-            json!({"name": "Alice"}),
-            1000,
-            clock,
-        ));
-        store.apply(op, 1000).unwrap();
+        let mut store = Store::new(test_schema(), "local");
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "A"})), 1000)
+            .unwrap();
+        store.clear_pending();
 
-        // Try to create same ID again
-        let clock2 = store.tick();
-        let op2 = Operation::Create(CreateOp::new(
+        let clock = store.tick();
+        let op = Operation::Update(UpdateOp::new(
             "op-2",
             "user-1",
             "users",
-            json!({"name": "Bob"}),
+            json!({"name": "B"}),
+            1,
             2000,
-            clock2,
+            clock,
         ));
-
-        let result = store.apply(op2, 2000);
-        assert!(matches!(result, Err(Error::RecordAlreadyExists(_))));
+        store.apply(op, 2000).unwrap();
+
+        // Replaying the pending update neither bumps the record's version
+        // nor moves the base the server will see
+        for _ in 0..2 {
+            store.reconcile(Vec::new(), MergeStrategy::ClockWins);
+            assert_eq!(store.get("users", "user-1").unwrap().version, 2);
+            assert_eq!(
+                store.pending_ops()[0].operation,
+                store.pending_ops()[0]
+                    .operation
+                    .clone()
+                    .with_base_version(1)
+            );
+        }
     }
 
     #[test]
-    fn apply_update() {
-        let mut store = test_store();
+    fn store_reconcile_rebases_pending_ops() {
+        use crate::reconcile::MergeStrategy;
+        use crate::IncrementOp;
 
-        // Create first
-        let clock1 = store.tick();
-        let create = Operation::Create(CreateOp::new(
-            "op-1",
-            "user-1",
-            "users",
-            json!({"name": "Alice"}),
-            1000,
-            clock1,
-        ));
-        store.apply(create, 1000).unwrap();
+        let mut store = Store::new(test_schema(), "local");
+        for (op_id, id) in [("op-1", "user-1"), ("op-2", "user-2")] {
+            store
+                .apply(create_op(op_id, id, json!({"name": "A"})), 1000)
+                .unwrap();
+        }
+        store.clear_pending();
+
+        // Pending local updates to both records
+        for (op_id, id) in [("op-3", "user-1"), ("op-4", "user-2")] {
+            let clock = store.tick();
+            let op = Operation::Update(UpdateOp::new(
+                op_id,
+                id,
+                "users",
+                json!({"name": "B"}),
+                1,
+                2000,
+                clock,
+            ));
+            store.apply(op, 2000).unwrap();
+        }
 
-        // Update
-        let clock2 = store.tick();
-        let update = Operation::Update(UpdateOp::new(
-            "op-2",
-            "user-1",
-            "users",
-            json!({"name": "Alice Smith", "age": 30}),
-            1, // base_version
-            2000,
-            clock2,
-        ));
-        let result = store.apply(update, 2000).unwrap();
-        assert_eq!(result.version, 2);
+        // Remotely, user-1 is incremented twice and user-2 is deleted by a
+        // node that had seen the local update
+        let mut seen = store
+            .get("users", "user-2")
+            .unwrap()
+            .metadata
+            .version_vector
+            .clone();
+        seen.observe(&LogicalClock::with_counter("remote", 1));
+        let remote_ops = vec![
+            Operation::Increment(IncrementOp::new(
+                "op-r1",
+                "user-1",
+                "users",
+                "age",
+                1,
+                1500,
+                LogicalClock::with_counter("remote", 1),
+            )),
+            Operation::Increment(IncrementOp::new(
+                "op-r2",
+                "user-1",
+                "users",
+                "age",
+                1,
+                1500,
+                LogicalClock::with_counter("remote", 2),
+            )),
+            Operation::Delete(DeleteOp::new(
+                "op-r3",
+                "user-2",
+                "users",
+                2,
+                3000,
+                LogicalClock::with_counter("remote", 9),
+            ))
+            .with_context(seen),
+        ];
+
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+        assert!(result.rejected_local.is_empty());
+        assert_eq!(result.invalidated_local, vec!["op-4".to_string()]);
 
+        // The surviving update now sits on the reconciled version
+        assert_eq!(store.pending_count(), 1);
         let record = store.get("users", "user-1").unwrap();
-        assert_eq!(record.payload, json!({"name": "Alice Smith", "age": 30}));
+        match &store.pending_ops()[0].operation {
+            Operation::Update(update) => {
+                assert_ne!(update.base_version, 1);
+                assert_eq!(update.base_version, record.version - 1);
+            }
+            other => panic!("unexpected operation: {:?}", other),
+        }
     }
 
     #[test]
-    fn apply_update_version_mismatch() {
-        let mut store = test_store();
+    fn preview_reconcile_leaves_store_unchanged() {
+        use crate::reconcile::MergeStrategy;
 
-        // Create
-        let clock1 = store.tick();
-        let create = Operation::Create(CreateOp::new(
-            "op-1",
-            "user-1",
-            "users",
-            json!({"name": "Alice"}),
-            1000,
-            clock1,
-        ));
-        store.apply(create, 1000).unwrap();
+        let mut store = Store::new(test_schema(), "local");
+        store
+            .apply(create_op("op-1", "user-1", json!({"name": "Alice"})), 1000)
+            .unwrap();
+        store.clear_pending();
+        let clock = store.tick();
+        store
+            .apply(
+                Operation::Update(UpdateOp::new(
+                    "op-2",
+                    "user-1",
+                    "users",
+                    json!({"name": "Local"}),
+                    1,
+                    2000,
+                    clock,
+                )),
+                2000,
+            )
+            .unwrap();
 
-        // Update with wrong base version
-        let clock2 = store.tick();
-        let update = Operation::Update(UpdateOp::new(
-            "op-2",
-            "user-1",
-            "users",
-            json!({"name": "Alice Smith"}),
-            5, // wrong base_version
-            2000,
-            clock2,
-        ));
+        // A concurrent remote update with a higher clock, and a new record
+        let remote_ops = vec![
+            Operation::Update(UpdateOp::new(
+                "op-r1",
+                "user-1",
+                "users",
+                json!({"name": "Remote"}),
+                1,
+                3000,
+                LogicalClock::with_counter("remote", 9),
+            )),
+            Operation::Create(CreateOp::new(
+                "op-r2",
+                "user-2",
+                "users",
+                json!({"name": "Bob"}),
+                3000,
+                LogicalClock::with_counter("remote", 10),
+            )),
+        ];
 
-        let result = store.apply(update, 2000);
-        assert!(matches!(
-            result,
-            Err(Error::VersionMismatch {
-                expected: 5,
-                actual: 1
-            })
-        ));
+        let before = store.export_state();
+        let preview = store.preview_reconcile(remote_ops.clone(), MergeStrategy::ClockWins);
+
+        // The local edit would be overwritten
+        assert_eq!(preview.result.rejected_local, vec!["op-2".to_string()]);
+        assert_eq!(preview.records.len(), 2);
+        let user_1 = &preview.records[0];
+        assert_eq!(user_1.id, "user-1");
+        assert_eq!(user_1.before.as_ref().unwrap().payload["name"], "Local");
+        assert_eq!(user_1.after.payload["name"], "Remote");
+        assert!(preview.records[1].before.is_none());
+
+        // Nothing changed
+        assert_eq!(store.export_state(), before);
+
+        // Reconciling for real gives the previewed outcome
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+        assert_eq!(result, preview.result);
+        assert_eq!(store.get("users", "user-1"), Some(&user_1.after));
     }
 
     #[test]
-    fn apply_delete() {
+    fn store_reconcile_reports_changes() {
+        use crate::reconcile::MergeStrategy;
+
         let mut store = test_store();
+        for (op_id, id) in [("op-1", "user-1"), ("op-2", "user-2"), ("op-3", "user-3")] {
+            store
+                .apply(create_op(op_id, id, json!({"name": "A"})), 1000)
+                .unwrap();
+        }
+        store.clear_pending();
 
-        // Create
-        let clock1 = store.tick();
-        let create = Operation::Create(CreateOp::new(
-            "op-1",
-            "user-1",
-            "users",
-            json!({"name": "Alice"}),
-            1000,
-            clock1,
-        ));
-        store.apply(create, 1000).unwrap();
+        let remote = |counter| LogicalClock::with_counter("remote", counter);
+        let remote_ops = vec![
+            Operation::Update(UpdateOp::new(
+                "op-r1",
+                "user-1",
+                "users",
+                json!({"name": "B"}),
+                1,
+                2000,
+                remote(5),
+            )),
+            Operation::Delete(DeleteOp::new(
+                "op-r2",
+                "user-2",
+                "users",
+                1,
+                2000,
+                remote(6),
+            )),
+            Operation::Create(CreateOp::new(
+                "op-r3",
+                "user-4",
+                "users",
+                json!({"name": "D"}),
+                2000,
+                remote(7),
+            )),
+        ];
 
-        // Delete
-        let clock2 = store.tick();
-        let delete = Operation::Delete(DeleteOp::new("op-2", "user-1", "users", 1, 2000, clock2));
-        let result = store.apply(delete, 2000).unwrap();
-        assert_eq!(result.version, 2);
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
 
-        // Should not be found via normal get
-        assert!(store.get("users", "user-1").is_none());
+        let changes = &result.changes["users"];
+        let ids = |list: &[crate::reconcile::RecordChange]| {
+            list.iter().map(|c| c.id.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&changes.inserted), vec!["user-4"]);
+        assert_eq!(ids(&changes.updated), vec!["user-1"]);
+        assert_eq!(ids(&changes.deleted), vec!["user-2"]);
+
+        assert_eq!(changes.inserted[0].old_version, None);
+        assert_eq!(changes.updated[0].old_version, Some(1));
+        assert_eq!(
+            changes.updated[0].new_version,
+            store.get("users", "user-1").unwrap().version
+        );
 
-        // But should be found including deleted
-        let record = store.get_including_deleted("users", "user-1").unwrap();
-        assert!(record.deleted);
+        // Nothing to report when nothing changes
+        let result = store.reconcile(Vec::new(), MergeStrategy::ClockWins);
+        assert!(result.changes.is_empty());
     }
 
     #[test]
-    fn apply_update_on_deleted() {
-        let mut store = test_store();
+    fn store_reconcile_does_not_replay_applied_patches() {
+        use crate::reconcile::MergeStrategy;
+        use crate::{JsonPatchOperation, Patch, PatchOp};
 
-        // Create and delete
-        let clock1 = store.tick();
+        let schema = Schema::new(1).with_collection(CollectionSchema::new(
+            "users",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("tags", FieldType::Json),
+            ],
+        ));
+        let mut store = Store::new(schema, "local");
+        let clock = store.tick();
         store
             .apply(
                 Operation::Create(CreateOp::new(
                     "op-1",
                     "user-1",
                     "users",
-                    json!({"name": "Alice"}),
+                    json!({"name": "Alice", "tags": []}),
                     1000,
-                    clock1,
+                    clock,
                 )),
                 1000,
             )
             .unwrap();
+        store.clear_pending();
 
-        let clock2 = store.tick();
+        let clock = store.tick();
         store
             .apply(
-                Operation::Delete(DeleteOp::new("op-2", "user-1", "users", 1, 2000, clock2)),
+                Operation::Patch(PatchOp::new(
+                    "op-2",
+                    "user-1",
+                    "users",
+                    Patch::JsonPatch(vec![JsonPatchOperation::Add {
+                        path: "/tags/-".into(),
+                        value: json!("x"),
+                    }]),
+                    1,
+                    2000,
+                    clock,
+                )),
                 2000,
             )
             .unwrap();
 
-        // Try to update deleted record
-        let clock3 = store.tick();
-        let update = Operation::Update(UpdateOp::new(
-            "op-3",
-            "user-1",
-            "users",
-            json!({"name": "Alice Smith"}),
-            2,
-            3000,
-            clock3,
-        ));
-
-        let result = store.apply(update, 3000);
-        assert!(matches!(result, Err(Error::OperationOnDeleted(_))));
+        // Reconciling replays the pending patch, which the record already
+        // reflects
+        for _ in 0..2 {
+            let result = store.reconcile(Vec::new(), MergeStrategy::ClockWins);
+            assert!(result.rejected_local.is_empty());
+            assert_eq!(
+                store.get("users", "user-1").unwrap().payload["tags"],
+                json!(["x"])
+            );
+        }
     }
 
     #[test]
-    fn pending_ops_tracking() {
-        let mut store = test_store();
+    fn store_reconcile_rejects_patch_failing_after_merge() {
+        use crate::reconcile::MergeStrategy;
+        use crate::{JsonPatchOperation, Patch, PatchOp};
 
-        let clock1 = store.tick();
+        let mut store = Store::new(test_schema(), "local");
+        let clock = store.tick();
         store
             .apply(
                 Operation::Create(CreateOp::new(
                     "op-1",
                     "user-1",
                     "users",
-                    json!({"name": "Alice"}),
+                    json!({"name": "Alice", "age": 1}),
                     1000,
-                    clock1,
+                    clock,
                 )),
                 1000,
             )
             .unwrap();
+        store.clear_pending();
 
-        let clock2 = store.tick();
+        // A local patch made against age 1
+        let clock = store.tick();
         store
             .apply(
-                Operation::Create(CreateOp::new(
-                    "op-2",
-                    "user-2",
+                Operation::Patch(PatchOp::new(
+                    "op-patch",
+                    "user-1",
                     "users",
-                    json!({"name": "Bob"}),
+                    Patch::JsonPatch(vec![
+                        JsonPatchOperation::Test {
+                            path: "/age".into(),
+                            value: json!(1),
+                        },
+                        JsonPatchOperation::Replace {
+                            path: "/age".into(),
+                            value: json!(2),
+                        },
+                    ]),
+                    1,
                     2000,
-                    clock2,
+                    clock,
                 )),
                 2000,
             )
             .unwrap();
 
-        assert_eq!(store.pending_count(), 2);
+        // A concurrent remote update ordered before it sets age 5, so the
+        // patch's test no longer holds
+        let remote_ops = vec![Operation::Update(UpdateOp::new(
+            "op-remote",
+            "user-1",
+            "users",
+            json!({"name": "Alice", "age": 5}),
+            1,
+            1500,
+            LogicalClock::with_counter("remote", 1),
+        ))];
 
-        // Acknowledge one
-        store.acknowledge(&["op-1".to_string()]);
-        assert_eq!(store.pending_count(), 1);
-        assert_eq!(store.pending_ops()[0].operation.op_id(), "op-2");
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+        assert_eq!(result.rejected_local, ["op-patch"]);
+        assert!(result.accepted_local.is_empty());
+        assert_eq!(result.applied_remote, ["op-remote"]);
+        assert!(result.rejected_remote.is_empty());
+        assert_eq!(store.get("users", "user-1").unwrap().payload["age"], 5);
+        assert_eq!(store.pending_count(), 0);
     }
 
     #[test]
-    fn query_all() {
-        let mut store = test_store();
+    fn store_reconcile_concurrent_increments() {
+        use crate::reconcile::MergeStrategy;
+        use crate::IncrementOp;
 
-        let clock1 = store.tick();
+        let mut store = Store::new(test_schema(), "local");
+        let clock = store.tick();
         store
             .apply(
                 Operation::Create(CreateOp::new(
                     "op-1",
                     "user-1",
                     "users",
-                    json!({"name": "Alice"}),
+                    json!({"name": "Alice", "age": 10}),
                     1000,
-                    clock1,
+                    clock,
                 )),
                 1000,
             )
             .unwrap();
+        store.clear_pending();
 
-        let clock2 = store.tick();
+        // A pending local increment
+        let clock = store.tick();
         store
             .apply(
-                Operation::Create(CreateOp::new(
-                    "op-2",
-                    "user-2",
-                    "users",
-                    json!({"name": "Bob"}),
-                    2000,
-                    clock2,
+                Operation::Increment(IncrementOp::new(
+                    "op-local", "user-1", "users", "age", 1, 2000, clock,
                 )),
                 2000,
             )
             .unwrap();
 
-        let results = store.query("users").unwrap().all();
-        assert_eq!(results.len(), 2);
+        // Two concurrent remote increments, one with a higher clock
+        let remote_ops = vec![
+            Operation::Increment(IncrementOp::new(
+                "op-remote-1",
+                "user-1",
+                "users",
+                "age",
+                5,
+                1500,
+                LogicalClock::with_counter("remote", 1),
+            )),
+            Operation::Increment(IncrementOp::new(
+                "op-remote-2",
+                "user-1",
+                "users",
+                "age",
+                -3,
+                2500,
+                LogicalClock::with_counter("remote", 9),
+            )),
+        ];
+
+        let result = store.reconcile(remote_ops.clone(), MergeStrategy::ClockWins);
+        assert!(result.conflicts.is_empty());
+        assert!(result.rejected_local.is_empty());
+
+        // Every increment applies once, the local one is not replayed twice
+        assert_eq!(store.get("users", "user-1").unwrap().payload["age"], 13);
+
+        // Redelivered increments are not applied again
+        store.reconcile(remote_ops, MergeStrategy::ClockWins);
+        assert_eq!(store.get("users", "user-1").unwrap().payload["age"], 13);
     }
 
     #[test]
-    fn query_filter() {
-        let mut store = test_store();
+    fn store_reconcile_rejects_increment_of_non_numeric_field() {
+        use crate::reconcile::MergeStrategy;
+        use crate::IncrementOp;
 
-        let clock1 = store.tick();
+        let mut store = Store::new(test_schema(), "local");
+        let clock = store.tick();
         store
             .apply(
                 Operation::Create(CreateOp::new(
                     "op-1",
                     "user-1",
                     "users",
-                    json!({"name": "Alice", "age": 30}),
+                    json!({"name": "Alice"}),
                     1000,
-                    clock1,
+                    clock,
                 )),
                 1000,
             )
             .unwrap();
+        store.clear_pending();
 
-        let clock2 = store.tick();
-        store
-            .apply(
-                Operation::Create(CreateOp::new(
-                    "op-2",
-                    "user-2",
-                    "users",
-                    json!({"name": "Bob", "age": 25}),
-                    2000,
-                    clock2,
-                )),
-                2000,
-            )
-            .unwrap();
-
-        let results = store.query("users").unwrap().filter(|payload| {
-            payload
-                .get("age")
-                .and_then(|v| v.as_i64())
-                .map(|age| age >= 30)
-                .unwrap_or(false)
-        });
+        // Remote increments of a field the schema does not declare and of
+        // one that is not numeric
+        let remote_ops = vec![
+            Operation::Increment(IncrementOp::new(
+                "op-missing",
+                "user-1",
+                "users",
+                "score",
+                5,
+                1500,
+                LogicalClock::with_counter("remote", 1),
+            )),
+            Operation::Increment(IncrementOp::new(
+                "op-string",
+                "user-1",
+                "users",
+                "name",
+                5,
+                1500,
+                LogicalClock::with_counter("remote", 2),
+            )),
+        ];
 
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].id, "user-1");
+        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+        assert_eq!(result.rejected_remote, ["op-missing", "op-string"]);
+        assert!(result.applied_remote.is_empty());
+        assert_eq!(
+            store.get("users", "user-1").unwrap().payload,
+            json!({"name": "Alice"})
+        );
     }
 
     #[test]
-    fn query_include_deleted() {
-        let mut store = test_store();
+    fn store_reconcile_causal_update() {
+        use crate::reconcile::MergeStrategy;
+        use crate::VersionVector;
 
-        // Create two users
-        let clock1 = store.tick();
-        store
+        let mut device_a = Store::new(test_schema(), "a");
+        let mut device_b = Store::new(test_schema(), "b");
+
+        let clock = device_a.tick();
+        device_a
             .apply(
                 Operation::Create(CreateOp::new(
                     "op-1",
@@ -902,86 +4193,73 @@ mod tests {
                     "users",
                     json!({"name": "Alice"}),
                     1000,
-                    clock1,
+                    clock,
                 )),
                 1000,
             )
             .unwrap();
+        let created: Vec<_> = device_a
+            .pending_ops()
+            .iter()
+            .map(|p| p.operation.clone())
+            .collect();
+        device_a.clear_pending();
+        device_b.reconcile(created, MergeStrategy::ClockWins);
 
-        let clock2 = store.tick();
-        store
+        // B edits after seeing A's create
+        let clock = device_b.tick();
+        device_b
             .apply(
-                Operation::Create(CreateOp::new(
+                Operation::Update(UpdateOp::new(
                     "op-2",
-                    "user-2",
+                    "user-1",
                     "users",
                     json!({"name": "Bob"}),
+                    1,
                     2000,
-                    clock2,
+                    clock,
                 )),
                 2000,
             )
             .unwrap();
+        let updated: Vec<_> = device_b
+            .pending_ops()
+            .iter()
+            .map(|p| p.operation.clone())
+            .collect();
+        assert_eq!(
+            updated[0].context(),
+            Some(&VersionVector::from_clock(&LogicalClock::with_counter(
+                "a", 1
+            )))
+        );
 
-        // Delete one
-        let clock3 = store.tick();
-        store
-            .apply(
-                Operation::Delete(DeleteOp::new("op-3", "user-1", "users", 1, 3000, clock3)),
-                3000,
-            )
-            .unwrap();
-
-        // Without deleted
-        assert_eq!(store.query("users").unwrap().count(), 1);
-
-        // With deleted
-        assert_eq!(store.query("users").unwrap().include_deleted().count(), 2);
-    }
-
-    #[test]
-    fn collection_not_found() {
-        let mut store = test_store();
-        let clock = store.tick();
-
-        let op = Operation::Create(CreateOp::new(
-            "op-1",
-            "post-1",
-            "posts", // doesn't exist
-            json!({"title": "Hello"}),
-            1000,
-            clock,
-        ));
-
-        let result = store.apply(op, 1000);
-        assert!(matches!(result, Err(Error::CollectionNotFound(_))));
-    }
-
-    #[test]
-    fn record_not_found() {
-        let mut store = test_store();
-        let clock = store.tick();
-
-        let op = Operation::Update(UpdateOp::new(
-            "op-1",
-            "user-999",
-            "users",
-            json!({"name": "Ghost"}),
-            1,
-            1000,
-            clock,
-        ));
-
-        let result = store.apply(op, 1000);
-        assert!(matches!(result, Err(Error::RecordNotFound(_))));
+        let result = device_a.reconcile(updated, MergeStrategy::ClockWins);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            device_a.get("users", "user-1").unwrap().payload["name"],
+            "Bob"
+        );
     }
 
     #[test]
-    fn store_serialization() {
-        let mut store = test_store();
+    fn store_reconcile_keep_both() {
+        use crate::reconcile::{ConflictResolution, MergeStrategy};
+
+        let mut device_a = Store::new(test_schema(), "a");
+        let mut device_b = Store::new(test_schema(), "b");
+        let pending = |store: &mut Store| -> Vec<Operation> {
+            let ops = store
+                .pending_ops()
+                .iter()
+                .map(|p| p.operation.clone())
+                .collect();
+            store.clear_pending();
+            ops
+        };
 
-        let clock = store.tick();
-        store
+        let clock = device_a.tick();
+        device_a
             .apply(
                 Operation::Create(CreateOp::new(
                     "op-1",
@@ -994,87 +4272,161 @@ mod tests {
                 1000,
             )
             .unwrap();
+        let created = pending(&mut device_a);
+        device_b.reconcile(created, MergeStrategy::KeepBoth);
+
+        // Both devices edit the record concurrently
+        for (store, op_id, name) in [
+            (&mut device_a, "op-a", "Alice A"),
+            (&mut device_b, "op-b", "Alice B"),
+        ] {
+            let clock = store.tick();
+            let op = Operation::Update(UpdateOp::new(
+                op_id,
+                "user-1",
+                "users",
+                json!({"name": name}),
+                1,
+                2000,
+                clock,
+            ));
+            store.apply(op, 2000).unwrap();
+        }
+        let from_a = pending(&mut device_a);
+        let from_b = pending(&mut device_b);
 
-        let json = serde_json::to_string(&store).unwrap();
-        let restored: Store = serde_json::from_str(&json).unwrap();
+        let result = device_a.reconcile(from_b, MergeStrategy::KeepBoth);
+        assert_eq!(result.conflicts[0].resolution, ConflictResolution::KeptBoth);
+        assert!(result.rejected_local.is_empty());
+        device_b.reconcile(from_a, MergeStrategy::KeepBoth);
+
+        // Neither edit is lost and both devices agree
+        let conflicted = device_a.conflicted_records();
+        assert_eq!(conflicted.len(), 1);
+        let record = conflicted[0];
+        let payloads: Vec<_> = record.siblings.iter().map(|s| s.payload.clone()).collect();
+        assert_eq!(
+            payloads,
+            vec![
+                Some(json!({"name": "Alice A"})),
+                Some(json!({"name": "Alice B"}))
+            ]
+        );
+        let mirrored = device_b.get("users", "user-1").unwrap();
+        assert_eq!(record.siblings, mirrored.siblings);
+        assert_eq!(record.payload, mirrored.payload);
 
-        assert_eq!(restored.node_id(), store.node_id());
-        assert!(restored.get("users", "user-1").is_some());
+        // Resolving clears the siblings on both devices
+        device_a
+            .resolve_conflict("op-resolve", "users", "user-1", json!({"name": "Al"}), 3000)
+            .unwrap();
+        assert!(device_a.conflicted_records().is_empty());
+        let resolved = pending(&mut device_a);
+        let result = device_b.reconcile(resolved, MergeStrategy::KeepBoth);
+        assert!(result.conflicts.is_empty());
+        assert!(device_b.conflicted_records().is_empty());
+        assert_eq!(
+            device_b.get("users", "user-1").unwrap().payload["name"],
+            "Al"
+        );
+
+        assert_eq!(
+            device_b.resolve_conflict("op-again", "users", "user-1", json!({"name": "X"}), 4000),
+            Err(Error::RecordNotConflicted("user-1".to_string()))
+        );
     }
 
     #[test]
-    fn store_reconcile_with_remote() {
-        use crate::reconcile::MergeStrategy;
+    fn store_reconcile_field_level() {
+        use crate::reconcile::{ConflictResolution, MergeStrategy};
+        use crate::schema::{CollectionSchema, FieldDef, FieldType};
         use crate::LogicalClock;
 
-        let mut store = test_store();
+        let schema = Schema::new(1).with_collection(CollectionSchema::new(
+            "users",
+            vec![
+                FieldDef::required("name", FieldType::String),
+                FieldDef::optional("age", FieldType::Int),
+            ],
+        ));
+        let mut store = Store::new(schema, "local");
 
-        // Local creates user-1
         let clock1 = store.tick();
         store
             .apply(
                 Operation::Create(CreateOp::new(
-                    "op-local",
+                    "op-1",
                     "user-1",
                     "users",
-                    json!({"name": "Alice"}),
+                    json!({"name": "Alice", "age": 30}),
                     1000,
                     clock1,
                 )),
                 1000,
             )
             .unwrap();
+        store.clear_pending();
 
-        assert_eq!(store.pending_count(), 1);
+        // Local edits age with a high clock
+        let mut clock2 = store.tick();
+        clock2.counter = 10;
+        store
+            .apply(
+                Operation::Update(UpdateOp::new(
+                    "op-local",
+                    "user-1",
+                    "users",
+                    json!({"name": "Alice", "age": 31}),
+                    1,
+                    2000,
+                    clock2,
+                )),
+                2000,
+            )
+            .unwrap();
 
-        // Remote has user-1 with higher clock (wins) and user-2
-        let remote_ops = vec![
-            Operation::Create(CreateOp::new(
-                "op-remote-1",
+        // Another device renamed the user from the same base version
+        let remote_ops = vec![Operation::Update(
+            UpdateOp::new(
+                "op-remote",
                 "user-1",
                 "users",
-                json!({"name": "Bob"}),
-                1000,
-                LogicalClock::with_counter("remote", 10), // higher clock wins
-            )),
-            Operation::Create(CreateOp::new(
-                "op-remote-2",
-                "user-2",
-                "users",
-                json!({"name": "Charlie"}),
-                1000,
+                json!({"name": "Alicia", "age": 30}),
+                1,
+                1500,
                 LogicalClock::with_counter("remote", 5),
-            )),
-        ];
-
-        let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
-
-        // Local op was rejected (remote had higher clock)
-        assert_eq!(result.rejected_local.len(), 1);
-        assert_eq!(result.conflicts.len(), 1);
+            )
+            .with_changed_fields(["name".to_string()]),
+        )];
 
-        // Pending should be cleared (local op rejected or accepted)
-        assert_eq!(store.pending_count(), 0);
+        let result = store.reconcile(remote_ops, MergeStrategy::FieldLevel);
 
-        // user-1 should have Bob (remote won)
-        let user1 = store.get("users", "user-1").unwrap();
-        assert_eq!(user1.payload, json!({"name": "Bob"}));
+        assert!(result.rejected_local.is_empty());
+        assert!(result.rejected_remote.is_empty());
+        assert_eq!(
+            result.conflicts[0].field_outcomes.get("name"),
+            Some(&ConflictResolution::RemoteWins)
+        );
 
-        // user-2 should exist
-        let user2 = store.get("users", "user-2").unwrap();
-        assert_eq!(user2.payload, json!({"name": "Charlie"}));
+        // Neither edit is lost
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload, json!({"name": "Alicia", "age": 31}));
     }
 
     #[test]
-    fn store_reconcile_local_wins() {
-        use crate::reconcile::MergeStrategy;
-        use crate::LogicalClock;
+    fn store_reconcile_with_resolver() {
+        use crate::reconcile::{ConflictResolution, MergeStrategy};
+        use crate::{LogicalClock, Resolution};
 
         let mut store = test_store();
+        store.register_resolver(
+            "users",
+            Arc::new(|_: &Operation, _: &Operation, _: &Record| {
+                Resolution::Merged(json!({"name": "Alice & Bob"}))
+            }),
+        );
 
-        // Local creates user-1 with high clock
-        let mut clock = store.tick();
-        clock.counter = 20; // simulate high counter
+        let clock = store.tick();
         store
             .apply(
                 Operation::Create(CreateOp::new(
@@ -1089,25 +4441,25 @@ mod tests {
             )
             .unwrap();
 
-        // Remote has user-1 with lower clock
         let remote_ops = vec![Operation::Create(CreateOp::new(
             "op-remote",
             "user-1",
             "users",
             json!({"name": "Bob"}),
             1000,
-            LogicalClock::with_counter("remote", 5), // lower clock
+            LogicalClock::with_counter("remote", 5),
         ))];
 
         let result = store.reconcile(remote_ops, MergeStrategy::ClockWins);
+        assert_eq!(result.conflicts[0].resolution, ConflictResolution::Merged);
+        assert_eq!(store.pending_count(), 1);
 
-        // Remote was rejected
-        assert_eq!(result.rejected_remote.len(), 1);
-        assert!(result.rejected_local.is_empty());
+        let record = store.get("users", "user-1").unwrap();
+        assert_eq!(record.payload, json!({"name": "Alice & Bob"}));
 
-        // user-1 should have Alice (local won)
-        let user1 = store.get("users", "user-1").unwrap();
-        assert_eq!(user1.payload, json!({"name": "Alice"}));
+        // Resolvers are not part of the serialized store
+        let json = serde_json::to_string(&store).unwrap();
+        assert!(!json.contains("resolvers"));
     }
 
     #[test]
@@ -1196,6 +4548,28 @@ mod tests {
         assert_eq!(user.payload, json!({"name": "Alice"}));
     }
 
+    #[test]
+    fn list_state_survives_snapshot() {
+        let mut store = crdt_store("test-node");
+        let insert = list_op(
+            &mut store,
+            "op-1",
+            "tags",
+            ListAction::Insert {
+                after: None,
+                value: json!("z"),
+            },
+        );
+        store.apply(insert, 2000).unwrap();
+
+        let mut restored = crdt_store("test-node");
+        restored.import_state(store.export_state()).unwrap();
+
+        let record = restored.get("tasks", "task-1").unwrap();
+        assert_eq!(record.payload["tags"], json!(["z", "a"]));
+        assert_eq!(record, store.get("tasks", "task-1").unwrap());
+    }
+
     #[test]
     fn import_node_id_mismatch() {
         // Create snapshot with different node ID
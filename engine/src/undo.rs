@@ -0,0 +1,222 @@
+//! Local undo and redo.
+//!
+//! The [`UndoManager`] remembers the record state around each local
+//! [`Store::apply`](crate::Store::apply) and
+//! [`Store::apply_batch`](crate::Store::apply_batch). Undoing a step does not
+//! touch the pending operations: [`Store::undo`](crate::Store::undo) applies
+//! new operations that write the earlier state back, so it syncs like any
+//! other edit, even once the undone operations have been pushed and
+//! acknowledged. A batch is one step, undone and redone as a batch.
+//!
+//! Reconciliation drops the steps touching records whose state it changed,
+//! since undoing them would overwrite remote edits.
+
+use crate::{CollectionName, Record, RecordId};
+use std::collections::HashSet;
+
+/// Number of steps an [`UndoManager`] keeps by default.
+pub const DEFAULT_UNDO_LIMIT: usize = 100;
+
+/// The change a local operation or batch made to one record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoChange {
+    /// Collection of the record
+    pub collection: CollectionName,
+    /// Record ID
+    pub id: RecordId,
+    /// Payload before the change (`None` if the record was absent or
+    /// deleted)
+    pub before: Option<serde_json::Value>,
+    /// Payload after the change (`None` if it deleted the record)
+    pub after: Option<serde_json::Value>,
+}
+
+/// The changes one local operation or batch made, undone together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoStep {
+    /// Changed records, in the order the batch first touched them
+    pub changes: Vec<UndoChange>,
+}
+
+impl UndoStep {
+    /// Check if the step changed a record in `records`.
+    fn touches(&self, records: &HashSet<(CollectionName, RecordId)>) -> bool {
+        self.changes
+            .iter()
+            .any(|change| records.contains(&(change.collection.clone(), change.id.clone())))
+    }
+}
+
+/// Undo and redo stacks of local changes.
+#[derive(Debug, Clone)]
+pub struct UndoManager {
+    undo: Vec<UndoStep>,
+    redo: Vec<UndoStep>,
+    limit: usize,
+}
+
+impl Default for UndoManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNDO_LIMIT)
+    }
+}
+
+impl UndoManager {
+    /// Create an undo manager keeping at most `limit` steps.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Maximum number of steps kept.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Change the maximum number of steps kept, dropping the oldest ones.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        trim(&mut self.undo, limit);
+        trim(&mut self.redo, limit);
+    }
+
+    /// Check if there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Check if there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Steps that can be undone, oldest first.
+    pub fn undo_steps(&self) -> &[UndoStep] {
+        &self.undo
+    }
+
+    /// Steps that can be redone, oldest first.
+    pub fn redo_steps(&self) -> &[UndoStep] {
+        &self.redo
+    }
+
+    /// Forget every step.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Record a new local change. Steps that were undone can no longer be
+    /// redone.
+    pub(crate) fn record(&mut self, step: UndoStep) {
+        self.redo.clear();
+        self.push_undo(step);
+    }
+
+    pub(crate) fn push_undo(&mut self, step: UndoStep) {
+        self.undo.push(step);
+        trim(&mut self.undo, self.limit);
+    }
+
+    pub(crate) fn push_redo(&mut self, step: UndoStep) {
+        self.redo.push(step);
+        trim(&mut self.redo, self.limit);
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<UndoStep> {
+        self.undo.pop()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<UndoStep> {
+        self.redo.pop()
+    }
+
+    /// Drop the steps touching any of the given records.
+    pub(crate) fn invalidate(&mut self, records: &HashSet<(CollectionName, RecordId)>) {
+        if records.is_empty() {
+            return;
+        }
+        for steps in [&mut self.undo, &mut self.redo] {
+            steps.retain(|step| !step.touches(records));
+        }
+    }
+}
+
+/// Payload of a record as users see it: `None` if absent or deleted.
+pub(crate) fn visible_payload(record: Option<&Record>) -> Option<&serde_json::Value> {
+    record.filter(|r| r.is_active()).map(|r| &r.payload)
+}
+
+fn trim(steps: &mut Vec<UndoStep>, limit: usize) {
+    let excess = steps.len().saturating_sub(limit);
+    steps.drain(..excess);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(id: &str) -> UndoChange {
+        UndoChange {
+            collection: "users".into(),
+            id: id.into(),
+            before: None,
+            after: Some(json!({"name": id})),
+        }
+    }
+
+    fn step(id: &str) -> UndoStep {
+        UndoStep {
+            changes: vec![change(id)],
+        }
+    }
+
+    #[test]
+    fn record_clears_redo() {
+        let mut manager = UndoManager::default();
+        manager.record(step("a"));
+        let undone = manager.pop_undo().unwrap();
+        manager.push_redo(undone);
+        assert!(manager.can_redo());
+
+        manager.record(step("b"));
+        assert!(!manager.can_redo());
+        assert_eq!(manager.undo_steps(), &[step("b")]);
+    }
+
+    #[test]
+    fn limit_drops_oldest_steps() {
+        let mut manager = UndoManager::new(2);
+        for id in ["a", "b", "c"] {
+            manager.record(step(id));
+        }
+        assert_eq!(manager.undo_steps(), &[step("b"), step("c")]);
+
+        manager.set_limit(1);
+        assert_eq!(manager.undo_steps(), &[step("c")]);
+    }
+
+    #[test]
+    fn invalidate_drops_record_steps() {
+        let mut manager = UndoManager::default();
+        manager.record(step("a"));
+        manager.record(step("b"));
+        manager.push_redo(step("a"));
+
+        let records = HashSet::from([("users".to_string(), "a".to_string())]);
+        manager.invalidate(&records);
+        assert_eq!(manager.undo_steps(), &[step("b")]);
+        assert!(!manager.can_redo());
+
+        // A batch touching the record is dropped as a whole
+        manager.record(UndoStep {
+            changes: vec![change("a"), change("c")],
+        });
+        manager.invalidate(&records);
+        assert_eq!(manager.undo_steps(), &[step("b")]);
+    }
+}
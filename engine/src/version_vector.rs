@@ -0,0 +1,154 @@
+//! Version vectors for causality tracking.
+//!
+//! A [`LogicalClock`] totally orders operations but cannot tell whether one
+//! operation saw another. A [`VersionVector`] records, per node, the highest
+//! clock counter whose effects a record (or an operation's author) has seen,
+//! which separates causally ordered writes from genuinely concurrent ones.
+
+use crate::{LogicalClock, NodeId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Highest observed clock counter per node.
+///
+/// Serializes as a JSON object of node ID to counter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VersionVector(BTreeMap<NodeId, u64>);
+
+impl VersionVector {
+    /// Create an empty version vector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a version vector containing a single clock.
+    pub fn from_clock(clock: &LogicalClock) -> Self {
+        let mut vector = Self::new();
+        vector.observe(clock);
+        vector
+    }
+
+    /// Get the highest counter seen from a node (0 if none).
+    pub fn get(&self, node_id: &str) -> u64 {
+        self.0.get(node_id).copied().unwrap_or(0)
+    }
+
+    /// Record that an event at `clock` has been seen.
+    pub fn observe(&mut self, clock: &LogicalClock) {
+        let counter = self.0.entry(clock.node_id.clone()).or_insert(0);
+        *counter = (*counter).max(clock.counter);
+    }
+
+    /// Check if an event at `clock` has been seen.
+    pub fn contains(&self, clock: &LogicalClock) -> bool {
+        self.get(&clock.node_id) >= clock.counter
+    }
+
+    /// Merge another vector into this one, taking the maximum per node.
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (node_id, &counter) in &other.0 {
+            let entry = self.0.entry(node_id.clone()).or_insert(0);
+            *entry = (*entry).max(counter);
+        }
+    }
+
+    /// Intersect with another vector, taking the minimum per node.
+    ///
+    /// The result holds what both vectors have seen; meeting the vectors of
+    /// every node gives the writes all of them have seen.
+    pub fn meet(&mut self, other: &VersionVector) {
+        self.0.retain(|node_id, counter| {
+            *counter = (*counter).min(other.get(node_id));
+            *counter > 0
+        });
+    }
+
+    /// Check if this vector has seen everything `other` has.
+    pub fn dominates(&self, other: &VersionVector) -> bool {
+        other
+            .0
+            .iter()
+            .all(|(node_id, &counter)| self.get(node_id) >= counter)
+    }
+
+    /// Check if neither vector has seen everything the other has.
+    pub fn is_concurrent_with(&self, other: &VersionVector) -> bool {
+        !self.dominates(other) && !other.dominates(self)
+    }
+
+    /// Check if the vector has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over (node ID, counter) entries in node ID order.
+    pub fn iter(&self) -> impl Iterator<Item = (&NodeId, &u64)> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vv(entries: &[(&str, u64)]) -> VersionVector {
+        let mut vector = VersionVector::new();
+        for (node_id, counter) in entries {
+            vector.observe(&LogicalClock::with_counter(*node_id, *counter));
+        }
+        vector
+    }
+
+    #[test]
+    fn observe_keeps_maximum() {
+        let mut vector = vv(&[("a", 3)]);
+        vector.observe(&LogicalClock::with_counter("a", 1));
+        assert_eq!(vector.get("a"), 3);
+        assert_eq!(vector.get("b"), 0);
+        assert!(vector.contains(&LogicalClock::with_counter("a", 2)));
+        assert!(!vector.contains(&LogicalClock::with_counter("a", 4)));
+    }
+
+    #[test]
+    fn dominance_and_concurrency() {
+        let base = vv(&[("a", 1)]);
+        let after = vv(&[("a", 1), ("b", 2)]);
+        let sibling = vv(&[("a", 2)]);
+
+        assert!(after.dominates(&base));
+        assert!(!base.dominates(&after));
+        assert!(base.dominates(&VersionVector::new()));
+        assert!(base.dominates(&base));
+
+        assert!(after.is_concurrent_with(&sibling));
+        assert!(!after.is_concurrent_with(&base));
+    }
+
+    #[test]
+    fn merge_takes_maximum_per_node() {
+        let mut left = vv(&[("a", 3), ("b", 1)]);
+        left.merge(&vv(&[("b", 4), ("c", 2)]));
+        assert_eq!(left, vv(&[("a", 3), ("b", 4), ("c", 2)]));
+    }
+
+    #[test]
+    fn meet_takes_minimum_per_node() {
+        let mut left = vv(&[("a", 3), ("b", 1)]);
+        left.meet(&vv(&[("b", 4), ("a", 2), ("c", 2)]));
+        assert_eq!(left, vv(&[("a", 2), ("b", 1)]));
+
+        left.meet(&vv(&[("b", 5)]));
+        assert_eq!(left, vv(&[("b", 1)]));
+    }
+
+    #[test]
+    fn serializes_as_object() {
+        let vector = vv(&[("b", 2), ("a", 1)]);
+        let json = serde_json::to_string(&vector).unwrap();
+        assert_eq!(json, r#"{"a":1,"b":2}"#);
+
+        let parsed: VersionVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, vector);
+    }
+}
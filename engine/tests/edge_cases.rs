@@ -2,9 +2,11 @@
 //!
 //! These tests cover boundary conditions and unusual inputs.
 
+#![allow(clippy::approx_constant, clippy::useless_vec)]
+
 use carry_engine::{
-    CollectionSchema, CreateOp, DeleteOp, FieldDef, FieldType, LogicalClock, MergeStrategy,
-    Operation, Schema, Store, StoreSnapshot, UpdateOp,
+    CollectionSchema, ConflictRule, CreateOp, DeleteOp, DeletePolicy, FieldDef, FieldType,
+    LogicalClock, MergeStrategy, Operation, Schema, Store, StoreSnapshot, UpdateOp,
 };
 use serde_json::json;
 
@@ -346,12 +348,63 @@ fn reconcile_delete_vs_update() {
         LogicalClock::with_counter("remote", 5), // Lower counter
     ));
 
-    let _result = store.reconcile(vec![update_op], MergeStrategy::ClockWins);
+    let result = store.reconcile(vec![update_op], MergeStrategy::ClockWins);
 
     // Delete should win because it has higher clock counter
     // Use get_including_deleted since deleted records are filtered by default
     let record = store.get_including_deleted("items", "item1").unwrap();
     assert!(record.deleted);
+    assert_eq!(result.conflicts[0].rule, ConflictRule::ClockWins);
+}
+
+#[test]
+fn reconcile_delete_vs_update_policy() {
+    let mut schema = Schema::new(1);
+    let fields = vec![FieldDef::required("name", FieldType::String)];
+    schema.add_collection(
+        CollectionSchema::new("items", fields).with_delete_policy(DeletePolicy::UpdateWins),
+    );
+    let mut store = Store::new(schema, "local".to_string());
+
+    let create_op = Operation::Create(CreateOp::new(
+        "create",
+        "item1",
+        "items",
+        json!({"name": "initial"}),
+        1000,
+        LogicalClock::with_counter("local", 1),
+    ));
+    store.apply(create_op, 1000).unwrap();
+    store.clear_pending();
+
+    let delete_op = Operation::Delete(DeleteOp::new(
+        "delete_local",
+        "item1",
+        "items",
+        1,
+        2000,
+        LogicalClock::with_counter("local", 10), // Higher counter
+    ));
+    store.apply(delete_op, 2000).unwrap();
+
+    let update_op = Operation::Update(UpdateOp::new(
+        "update_remote",
+        "item1",
+        "items",
+        json!({"name": "updated"}),
+        1,
+        2500,
+        LogicalClock::with_counter("remote", 5), // Lower counter
+    ));
+
+    let result = store.reconcile(vec![update_op], MergeStrategy::ClockWins);
+
+    // The collection's policy resurrects the record despite the clocks
+    let record = store.get("items", "item1").unwrap();
+    assert_eq!(record.payload, json!({"name": "updated"}));
+    assert_eq!(result.conflicts[0].winner_op_id, "update_remote");
+    assert_eq!(result.conflicts[0].rule, ConflictRule::UpdateWins);
+    assert!(result.rejected_local.contains(&"delete_local".to_string()));
 }
 
 // ============================================================================
@@ -517,6 +570,52 @@ fn many_pending_operations() {
     assert_eq!(store.pending_ops().len(), 500);
 }
 
+#[test]
+fn many_pending_updates_compact() {
+    let schema = create_test_schema();
+    let mut store = Store::new(schema, "node1".to_string());
+
+    let create = Operation::Create(CreateOp::new(
+        "op_0",
+        "item_0",
+        "items",
+        json!({"name": ""}),
+        1000,
+        LogicalClock::with_counter("node1", 0),
+    ));
+    store.apply(create, 1000).unwrap();
+
+    // One update per keystroke
+    for i in 1..1000u64 {
+        let name: String = "x".repeat(i as usize);
+        let op = Operation::Update(UpdateOp::new(
+            format!("op_{}", i),
+            "item_0",
+            "items",
+            json!({"name": name}),
+            i,
+            1000 + i,
+            LogicalClock::with_counter("node1", i),
+        ));
+        store.apply(op, 1000 + i).unwrap();
+    }
+    assert_eq!(store.pending_ops().len(), 1000);
+
+    assert!(store.compact_pending().is_empty());
+
+    // A single create carries the final state and every folded ID
+    let pending = &store.pending_ops()[0];
+    assert_eq!(store.pending_ops().len(), 1);
+    assert_eq!(pending.operation.op_id(), "op_999");
+    assert_eq!(pending.op_ids().count(), 1000);
+    assert!(
+        matches!(&pending.operation, Operation::Create(c) if c.payload["name"].as_str().unwrap().len() == 999)
+    );
+
+    store.acknowledge(&["op_999".to_string()]);
+    assert_eq!(store.pending_ops().len(), 0);
+}
+
 // ============================================================================
 // ID Edge Cases
 // ============================================================================
@@ -0,0 +1,7 @@
+-- Per-field clocks for field-level merging
+
+-- Clock of the last write to each top-level payload field
+ALTER TABLE records ADD COLUMN IF NOT EXISTS field_clocks JSONB NOT NULL DEFAULT '{}';
+
+-- Top-level fields an update changed relative to its base version
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS changed_fields JSONB;
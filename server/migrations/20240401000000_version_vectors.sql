@@ -0,0 +1,7 @@
+-- Version vectors for causality tracking
+
+-- Clocks of every write the record state reflects
+ALTER TABLE records ADD COLUMN IF NOT EXISTS version_vector JSONB NOT NULL DEFAULT '{}';
+
+-- Version vector of the record an operation was made against
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS context JSONB;
@@ -0,0 +1,4 @@
+-- Atomic operation groups
+
+-- Transaction an operation was pushed in; its operations are accepted together
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS transaction_id TEXT;
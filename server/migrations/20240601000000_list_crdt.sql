@@ -0,0 +1,4 @@
+-- Replicated state of CRDT fields
+
+-- List state per field, so concurrent list edits keep merging across pushes
+ALTER TABLE records ADD COLUMN IF NOT EXISTS crdt JSONB NOT NULL DEFAULT '{}';
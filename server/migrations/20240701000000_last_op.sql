@@ -0,0 +1,4 @@
+-- Last operation per record
+
+-- The operation that last wrote the record, so conflicts report it
+ALTER TABLE records ADD COLUMN IF NOT EXISTS last_op JSONB;
@@ -0,0 +1,4 @@
+-- Conflicting versions kept for manual resolution
+
+-- Siblings of records in collections that keep both sides of a conflict
+ALTER TABLE records ADD COLUMN IF NOT EXISTS siblings JSONB NOT NULL DEFAULT '[]';
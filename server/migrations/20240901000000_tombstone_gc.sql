@@ -0,0 +1,7 @@
+-- Tombstone garbage collection
+
+-- Writes each node has acknowledged by pushing or pulling them, so
+-- tombstones every node has seen can be purged
+ALTER TABLE nodes ADD COLUMN IF NOT EXISTS acked_vector JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_records_tombstones ON records(updated_at) WHERE deleted;
@@ -0,0 +1,6 @@
+-- Per-record expiry
+
+-- When the record expires (milliseconds since epoch), overriding the
+-- collection's TTL; written by create and upsert operations
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS expires_at BIGINT;
+ALTER TABLE records ADD COLUMN IF NOT EXISTS expires_at BIGINT;
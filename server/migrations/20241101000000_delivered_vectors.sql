@@ -0,0 +1,5 @@
+-- Delivered vectors
+
+-- Pulls acknowledge, per origin node, the writes below the first one not yet
+-- delivered, looked up by clock
+CREATE INDEX IF NOT EXISTS idx_ops_clock ON operations(clock_node_id, clock_counter);
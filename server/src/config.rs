@@ -1,6 +1,8 @@
 //! Configuration management for the server.
 
+use carry_engine::{CollectionSchema, FieldDef, FieldType, Schema};
 use std::env;
+use std::time::Duration;
 
 /// Server configuration loaded from environment variables.
 #[derive(Debug, Clone)]
@@ -13,6 +15,15 @@ pub struct Config {
     pub database_url: String,
     /// Secret key for token validation (placeholder for auth)
     pub auth_secret: Option<String>,
+    /// Path to the schema JSON shared with clients
+    pub schema_path: Option<String>,
+    /// How long tombstones are kept at least before they may be purged
+    pub tombstone_retention: Duration,
+    /// How often tombstones are purged (`None` disables purging)
+    pub tombstone_gc_interval: Option<Duration>,
+    /// Milliseconds a pushed operation's clock may run ahead of server time
+    /// (`None` accepts any clock)
+    pub max_clock_drift: Option<u64>,
 }
 
 impl Config {
@@ -29,13 +40,89 @@ impl Config {
 
         let auth_secret = env::var("AUTH_SECRET").ok();
 
+        let schema_path = env::var("SCHEMA_PATH").ok();
+
+        let tombstone_retention = env::var("TOMBSTONE_RETENTION_SECS")
+            .unwrap_or_else(|_| "2592000".to_string())
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|_| ConfigError::InvalidTombstoneRetention)?;
+
+        let tombstone_gc_interval = env::var("TOMBSTONE_GC_INTERVAL_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse()
+            .map(|secs| (secs > 0).then(|| Duration::from_secs(secs)))
+            .map_err(|_| ConfigError::InvalidTombstoneGcInterval)?;
+
+        let max_clock_drift = env::var("MAX_CLOCK_DRIFT_MS")
+            .unwrap_or_else(|_| carry_engine::clock::DEFAULT_MAX_DRIFT.to_string())
+            .parse()
+            .map(|ms: u64| (ms > 0).then_some(ms))
+            .map_err(|_| ConfigError::InvalidMaxClockDrift)?;
+
         Ok(Self {
             host,
             port,
             database_url,
             auth_secret,
+            schema_path,
+            tombstone_retention,
+            tombstone_gc_interval,
+            max_clock_drift,
         })
     }
+
+    /// Load the schema from `schema_path`, or the default schema if unset.
+    ///
+    /// Sharing the schema JSON with clients keeps per-collection merge
+    /// strategies identical on both sides.
+    pub fn load_schema(&self) -> Result<Schema, ConfigError> {
+        let Some(path) = &self.schema_path else {
+            return Ok(default_schema());
+        };
+
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::InvalidSchema(format!("{}: {}", path, e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| ConfigError::InvalidSchema(format!("{}: {}", path, e)))
+    }
+}
+
+/// Get the default schema, used when no schema file is configured.
+pub fn default_schema() -> Schema {
+    // Permissive schema that accepts any collection with JSON payload
+    let mut schema = Schema::new(1);
+
+    // Add some common collections
+    // In production, this would be dynamically configured
+    schema = schema.with_collection(CollectionSchema::new(
+        "users",
+        vec![
+            FieldDef::optional("name", FieldType::String),
+            FieldDef::optional("email", FieldType::String),
+            FieldDef::optional("age", FieldType::Int),
+        ],
+    ));
+
+    schema = schema.with_collection(CollectionSchema::new(
+        "posts",
+        vec![
+            FieldDef::optional("title", FieldType::String),
+            FieldDef::optional("body", FieldType::String),
+            FieldDef::optional("createdAt", FieldType::Timestamp),
+        ],
+    ));
+
+    schema = schema.with_collection(CollectionSchema::new(
+        "todos",
+        vec![
+            FieldDef::optional("title", FieldType::String),
+            FieldDef::optional("completed", FieldType::Bool),
+            FieldDef::optional("createdAt", FieldType::Timestamp),
+        ],
+    ));
+
+    schema
 }
 
 /// Configuration errors.
@@ -46,4 +133,16 @@ pub enum ConfigError {
 
     #[error("Invalid PORT value")]
     InvalidPort,
+
+    #[error("Invalid TOMBSTONE_RETENTION_SECS value")]
+    InvalidTombstoneRetention,
+
+    #[error("Invalid TOMBSTONE_GC_INTERVAL_SECS value")]
+    InvalidTombstoneGcInterval,
+
+    #[error("Invalid MAX_CLOCK_DRIFT_MS value")]
+    InvalidMaxClockDrift,
+
+    #[error("Invalid schema file: {0}")]
+    InvalidSchema(String),
 }
@@ -1,9 +1,11 @@
 //! Database module for PostgreSQL persistence.
 
+mod nodes;
 mod operations;
 mod pool;
 mod records;
 
+pub use nodes::*;
 pub use operations::*;
 pub use pool::*;
 pub use records::*;
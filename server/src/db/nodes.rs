@@ -0,0 +1,60 @@
+//! Database operations for the nodes table.
+
+use carry_engine::VersionVector;
+use sqlx::PgPool;
+
+/// Record that a node has seen the given writes.
+///
+/// Registers the node if it is new and merges the writes into what it has
+/// acknowledged before.
+pub async fn acknowledge_node(
+    pool: &PgPool,
+    node_id: &str,
+    seen: &VersionVector,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let existing: Option<(serde_json::Value,)> =
+        sqlx::query_as(r#"SELECT acked_vector FROM nodes WHERE node_id = $1 FOR UPDATE"#)
+            .bind(node_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+    let mut acked: VersionVector = existing
+        .and_then(|(vector,)| serde_json::from_value(vector).ok())
+        .unwrap_or_default();
+    acked.merge(seen);
+
+    sqlx::query(
+        r#"
+        INSERT INTO nodes (node_id, last_sync_at, acked_vector)
+        VALUES ($1, NOW(), $2)
+        ON CONFLICT (node_id) DO UPDATE SET
+            last_sync_at = EXCLUDED.last_sync_at,
+            acked_vector = EXCLUDED.acked_vector
+        "#,
+    )
+    .bind(node_id)
+    .bind(serde_json::to_value(&acked).unwrap_or_default())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Get the writes every known node has acknowledged.
+///
+/// Returns `None` if no node is known yet.
+pub async fn get_stable_vector(pool: &PgPool) -> Result<Option<VersionVector>, sqlx::Error> {
+    let rows: Vec<(serde_json::Value,)> = sqlx::query_as(r#"SELECT acked_vector FROM nodes"#)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(vector,)| serde_json::from_value::<VersionVector>(vector).unwrap_or_default())
+        .reduce(|mut stable, acked| {
+            stable.meet(&acked);
+            stable
+        }))
+}
@@ -1,7 +1,10 @@
 //! Database operations for the operations table.
 
-use carry_engine::{CreateOp, DeleteOp, LogicalClock, Operation, UpdateOp};
-use sqlx::{PgPool, Row};
+use carry_engine::{
+    CreateOp, DeleteOp, IncrementOp, ListAction, ListOp, LogicalClock, Operation, Patch, PatchOp,
+    RestoreOp, SetAction, SetOp, TextOp, TextSplice, UpdateOp, UpsertOp, VersionVector,
+};
+use sqlx::{PgExecutor, PgPool, Row};
 
 /// A stored operation row from the database.
 #[derive(Debug)]
@@ -19,6 +22,10 @@ pub struct StoredOperation {
     pub clock_node_id: String,
     pub timestamp: i64,
     pub base_version: Option<i64>,
+    pub changed_fields: Option<serde_json::Value>,
+    pub context: Option<serde_json::Value>,
+    pub transaction_id: Option<String>,
+    pub expires_at: Option<i64>,
     #[allow(dead_code)]
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
@@ -37,6 +44,10 @@ impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for StoredOperation {
             clock_node_id: row.try_get("clock_node_id")?,
             timestamp: row.try_get("timestamp")?,
             base_version: row.try_get("base_version")?,
+            changed_fields: row.try_get("changed_fields")?,
+            context: row.try_get("context")?,
+            transaction_id: row.try_get("transaction_id")?,
+            expires_at: row.try_get("expires_at")?,
             created_at: row.try_get("created_at")?,
         })
     }
@@ -47,22 +58,24 @@ impl StoredOperation {
     pub fn to_operation(&self) -> Result<Operation, String> {
         let clock = LogicalClock::with_counter(&self.clock_node_id, self.clock_counter as u64);
 
-        match self.op_type.as_str() {
+        let op = match self.op_type.as_str() {
             "create" => {
                 let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
-                Ok(Operation::Create(CreateOp::new(
+                let mut create = CreateOp::new(
                     &self.op_id,
                     &self.record_id,
                     &self.collection,
                     payload,
                     self.timestamp as u64,
                     clock,
-                )))
+                );
+                create.expires_at = self.expires_at.map(|at| at as u64);
+                Ok(Operation::Create(create))
             }
             "update" => {
                 let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
                 let base_version = self.base_version.unwrap_or(0) as u64;
-                Ok(Operation::Update(UpdateOp::new(
+                let mut update = UpdateOp::new(
                     &self.op_id,
                     &self.record_id,
                     &self.collection,
@@ -70,6 +83,26 @@ impl StoredOperation {
                     base_version,
                     self.timestamp as u64,
                     clock,
+                );
+                if let Some(fields) = &self.changed_fields {
+                    update.changed_fields = serde_json::from_value(fields.clone())
+                        .map_err(|e| format!("Invalid changed fields: {}", e))?;
+                }
+                Ok(Operation::Update(update))
+            }
+            "patch" => {
+                let document = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let patch: Patch = serde_json::from_value(document)
+                    .map_err(|e| format!("Invalid patch document: {}", e))?;
+                let base_version = self.base_version.unwrap_or(0) as u64;
+                Ok(Operation::Patch(PatchOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    patch,
+                    base_version,
+                    self.timestamp as u64,
+                    clock,
                 )))
             }
             "delete" => {
@@ -83,17 +116,192 @@ impl StoredOperation {
                     clock,
                 )))
             }
+            "restore" => {
+                let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let base_version = self.base_version.unwrap_or(0) as u64;
+                Ok(Operation::Restore(RestoreOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    payload,
+                    base_version,
+                    self.timestamp as u64,
+                    clock,
+                )))
+            }
+            "upsert" => {
+                let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let mut upsert = UpsertOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    payload,
+                    self.timestamp as u64,
+                    clock,
+                );
+                upsert.expires_at = self.expires_at.map(|at| at as u64);
+                Ok(Operation::Upsert(upsert))
+            }
+            "increment" => {
+                // Stored as {"field": ..., "delta": ...}
+                let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let field = payload
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Invalid increment: missing field")?;
+                let delta = match payload.get("delta") {
+                    Some(serde_json::Value::Number(delta)) => delta.clone(),
+                    _ => return Err("Invalid increment: missing delta".to_string()),
+                };
+                Ok(Operation::Increment(IncrementOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    field,
+                    delta,
+                    self.timestamp as u64,
+                    clock,
+                )))
+            }
+            "list" => {
+                // Stored as {"field": ..., "action": ...}
+                let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let field = payload
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Invalid list operation: missing field")?;
+                let action: ListAction =
+                    serde_json::from_value(payload.get("action").cloned().unwrap_or_default())
+                        .map_err(|e| format!("Invalid list action: {}", e))?;
+                Ok(Operation::List(ListOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    field,
+                    action,
+                    self.timestamp as u64,
+                    clock,
+                )))
+            }
+            "set" => {
+                // Stored as {"field": ..., "action": ...}
+                let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let field = payload
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Invalid set operation: missing field")?;
+                let action: SetAction =
+                    serde_json::from_value(payload.get("action").cloned().unwrap_or_default())
+                        .map_err(|e| format!("Invalid set action: {}", e))?;
+                Ok(Operation::Set(SetOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    field,
+                    action,
+                    self.timestamp as u64,
+                    clock,
+                )))
+            }
+            "text" => {
+                // Stored as {"field": ..., "splice": ...}
+                let payload = self.payload.clone().unwrap_or(serde_json::Value::Null);
+                let field = payload
+                    .get("field")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Invalid text operation: missing field")?;
+                let splice: TextSplice =
+                    serde_json::from_value(payload.get("splice").cloned().unwrap_or_default())
+                        .map_err(|e| format!("Invalid text splice: {}", e))?;
+                Ok(Operation::Text(TextOp::new(
+                    &self.op_id,
+                    &self.record_id,
+                    &self.collection,
+                    field,
+                    splice,
+                    self.timestamp as u64,
+                    clock,
+                )))
+            }
             other => Err(format!("Unknown operation type: {}", other)),
+        }?;
+
+        let op = match &self.context {
+            Some(context) => {
+                let context: VersionVector = serde_json::from_value(context.clone())
+                    .map_err(|e| format!("Invalid context: {}", e))?;
+                op.with_context(context)
+            }
+            None => op,
+        };
+
+        match &self.transaction_id {
+            Some(transaction_id) => Ok(op.with_transaction_id(transaction_id)),
+            None => Ok(op),
         }
     }
 }
 
 /// Insert an operation into the database.
-pub async fn insert_operation(pool: &PgPool, op: &Operation) -> Result<i32, sqlx::Error> {
+pub async fn insert_operation(
+    executor: impl PgExecutor<'_>,
+    op: &Operation,
+) -> Result<i32, sqlx::Error> {
+    let changed_fields = match op {
+        Operation::Update(u) => u
+            .changed_fields
+            .as_ref()
+            .and_then(|fields| serde_json::to_value(fields).ok()),
+        _ => None,
+    };
+    let context = op
+        .context()
+        .and_then(|context| serde_json::to_value(context).ok());
+    let expires_at = match op {
+        Operation::Create(c) => c.expires_at,
+        Operation::Upsert(u) => u.expires_at,
+        _ => None,
+    };
+
     let (op_type, payload, base_version) = match op {
-        Operation::Create(c) => ("create", Some(&c.payload), None),
-        Operation::Update(u) => ("update", Some(&u.payload), Some(u.base_version as i64)),
+        Operation::Create(c) => ("create", Some(c.payload.clone()), None),
+        Operation::Update(u) => (
+            "update",
+            Some(u.payload.clone()),
+            Some(u.base_version as i64),
+        ),
+        Operation::Patch(p) => (
+            "patch",
+            Some(serde_json::to_value(&p.patch).map_err(|e| sqlx::Error::Encode(Box::new(e)))?),
+            Some(p.base_version as i64),
+        ),
         Operation::Delete(d) => ("delete", None, Some(d.base_version as i64)),
+        Operation::Restore(r) => (
+            "restore",
+            Some(r.payload.clone()),
+            Some(r.base_version as i64),
+        ),
+        Operation::Upsert(u) => ("upsert", Some(u.payload.clone()), None),
+        Operation::Increment(i) => (
+            "increment",
+            Some(serde_json::json!({"field": i.field, "delta": i.delta})),
+            None,
+        ),
+        Operation::List(l) => (
+            "list",
+            Some(serde_json::json!({"field": l.field, "action": l.action})),
+            None,
+        ),
+        Operation::Text(t) => (
+            "text",
+            Some(serde_json::json!({"field": t.field, "splice": t.splice})),
+            None,
+        ),
+        Operation::Set(s) => (
+            "set",
+            Some(serde_json::json!({"field": s.field, "action": s.action})),
+            None,
+        ),
     };
 
     let clock = op.clock();
@@ -102,9 +310,10 @@ pub async fn insert_operation(pool: &PgPool, op: &Operation) -> Result<i32, sqlx
         r#"
         INSERT INTO operations (
             op_id, node_id, collection, record_id, op_type,
-            payload, clock_counter, clock_node_id, timestamp, base_version
+            payload, clock_counter, clock_node_id, timestamp, base_version,
+            changed_fields, context, transaction_id, expires_at
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
         RETURNING id
         "#,
     )
@@ -118,7 +327,11 @@ pub async fn insert_operation(pool: &PgPool, op: &Operation) -> Result<i32, sqlx
     .bind(&clock.node_id)
     .bind(op.timestamp() as i64)
     .bind(base_version)
-    .fetch_one(pool)
+    .bind(changed_fields)
+    .bind(context)
+    .bind(op.transaction_id())
+    .bind(expires_at.map(|at| at as i64))
+    .fetch_one(executor)
     .await?;
 
     Ok(result.0)
@@ -148,7 +361,8 @@ pub async fn get_operations_since(
                 r#"
                 SELECT id, op_id, node_id, collection, record_id, op_type,
                        payload, clock_counter, clock_node_id, timestamp,
-                       base_version, created_at
+                       base_version, changed_fields, context, transaction_id,
+                       expires_at, created_at
                 FROM operations
                 WHERE (timestamp, op_id) > ($1, $2)
                 ORDER BY timestamp ASC, op_id ASC
@@ -174,7 +388,8 @@ async fn get_all_operations(
         r#"
         SELECT id, op_id, node_id, collection, record_id, op_type,
                payload, clock_counter, clock_node_id, timestamp,
-               base_version, created_at
+               base_version, changed_fields, context, transaction_id,
+               expires_at, created_at
         FROM operations
         ORDER BY timestamp ASC, op_id ASC
         LIMIT $1
@@ -186,11 +401,14 @@ async fn get_all_operations(
 }
 
 /// Check if an operation with the given op_id already exists.
-pub async fn operation_exists(pool: &PgPool, op_id: &str) -> Result<bool, sqlx::Error> {
+pub async fn operation_exists(
+    executor: impl PgExecutor<'_>,
+    op_id: &str,
+) -> Result<bool, sqlx::Error> {
     let result: (bool,) =
         sqlx::query_as(r#"SELECT EXISTS(SELECT 1 FROM operations WHERE op_id = $1)"#)
             .bind(op_id)
-            .fetch_one(pool)
+            .fetch_one(executor)
             .await?;
 
     Ok(result.0)
@@ -205,3 +423,41 @@ pub async fn get_server_clock(pool: &PgPool) -> Result<u64, sqlx::Error> {
 
     Ok(result.0 as u64)
 }
+
+/// Get the writes a node has been delivered by pulling up to and including
+/// the operation at (`timestamp`, `op_id`).
+///
+/// Pulls run in timestamp order, so a node's later writes can be delivered
+/// before its earlier ones. Each origin node's entry only covers the writes
+/// below the first one not yet delivered, so the vector never claims an
+/// undelivered write.
+pub async fn get_delivered_vector(
+    pool: &PgPool,
+    timestamp: i64,
+    op_id: &str,
+) -> Result<VersionVector, sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT delivered.clock_node_id, MAX(delivered.clock_counter)
+        FROM operations delivered
+        WHERE (delivered.timestamp, delivered.op_id) <= ($1, $2)
+          AND NOT EXISTS (
+              SELECT 1 FROM operations pending
+              WHERE pending.clock_node_id = delivered.clock_node_id
+                AND pending.clock_counter <= delivered.clock_counter
+                AND (pending.timestamp, pending.op_id) > ($1, $2)
+          )
+        GROUP BY delivered.clock_node_id
+        "#,
+    )
+    .bind(timestamp)
+    .bind(op_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut delivered = VersionVector::new();
+    for (node_id, counter) in rows {
+        delivered.observe(&LogicalClock::with_counter(node_id, counter as u64));
+    }
+    Ok(delivered)
+}
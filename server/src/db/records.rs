@@ -1,7 +1,7 @@
 //! Database operations for the records table.
 
 use carry_engine::{LogicalClock, Metadata, Origin, Record};
-use sqlx::{PgPool, Row};
+use sqlx::{PgExecutor, PgPool, Row};
 
 /// A stored record row from the database.
 #[derive(Debug)]
@@ -15,6 +15,12 @@ pub struct StoredRecord {
     pub clock_node_id: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub field_clocks: serde_json::Value,
+    pub version_vector: serde_json::Value,
+    pub crdt: serde_json::Value,
+    pub last_op: Option<serde_json::Value>,
+    pub siblings: serde_json::Value,
+    pub expires_at: Option<i64>,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for StoredRecord {
@@ -29,6 +35,12 @@ impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for StoredRecord {
             clock_node_id: row.try_get("clock_node_id")?,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
+            field_clocks: row.try_get("field_clocks")?,
+            version_vector: row.try_get("version_vector")?,
+            crdt: row.try_get("crdt")?,
+            last_op: row.try_get("last_op")?,
+            siblings: row.try_get("siblings")?,
+            expires_at: row.try_get("expires_at")?,
         })
     }
 }
@@ -46,28 +58,48 @@ impl StoredRecord {
                 updated_at: self.updated_at as u64,
                 origin: Origin::Remote,
                 clock: LogicalClock::with_counter(&self.clock_node_id, self.clock_counter as u64),
+                field_clocks: serde_json::from_value(self.field_clocks.clone()).unwrap_or_default(),
+                version_vector: serde_json::from_value(self.version_vector.clone())
+                    .unwrap_or_default(),
+                last_op: self
+                    .last_op
+                    .clone()
+                    .and_then(|last_op| serde_json::from_value(last_op).ok()),
+                expires_at: self.expires_at.map(|at| at as u64),
             },
             deleted: self.deleted,
+            crdt: serde_json::from_value(self.crdt.clone()).unwrap_or_default(),
+            siblings: serde_json::from_value(self.siblings.clone()).unwrap_or_default(),
         }
     }
 }
 
 /// Upsert a record (insert or update).
-pub async fn upsert_record(pool: &PgPool, record: &Record) -> Result<(), sqlx::Error> {
+pub async fn upsert_record(
+    executor: impl PgExecutor<'_>,
+    record: &Record,
+) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         INSERT INTO records (
             collection, record_id, version, payload, deleted,
-            clock_counter, clock_node_id, created_at, updated_at
+            clock_counter, clock_node_id, created_at, updated_at, field_clocks,
+            version_vector, crdt, last_op, siblings, expires_at
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
         ON CONFLICT (collection, record_id) DO UPDATE SET
             version = EXCLUDED.version,
             payload = EXCLUDED.payload,
             deleted = EXCLUDED.deleted,
             clock_counter = EXCLUDED.clock_counter,
             clock_node_id = EXCLUDED.clock_node_id,
-            updated_at = EXCLUDED.updated_at
+            updated_at = EXCLUDED.updated_at,
+            field_clocks = EXCLUDED.field_clocks,
+            version_vector = EXCLUDED.version_vector,
+            crdt = EXCLUDED.crdt,
+            last_op = EXCLUDED.last_op,
+            siblings = EXCLUDED.siblings,
+            expires_at = EXCLUDED.expires_at
         "#,
     )
     .bind(&record.collection)
@@ -79,7 +111,19 @@ pub async fn upsert_record(pool: &PgPool, record: &Record) -> Result<(), sqlx::E
     .bind(&record.metadata.clock.node_id)
     .bind(record.metadata.created_at as i64)
     .bind(record.metadata.updated_at as i64)
-    .execute(pool)
+    .bind(serde_json::to_value(&record.metadata.field_clocks).unwrap_or_default())
+    .bind(serde_json::to_value(&record.metadata.version_vector).unwrap_or_default())
+    .bind(serde_json::to_value(&record.crdt).unwrap_or_default())
+    .bind(
+        record
+            .metadata
+            .last_op
+            .as_ref()
+            .and_then(|last_op| serde_json::to_value(last_op).ok()),
+    )
+    .bind(serde_json::to_value(&record.siblings).unwrap_or_default())
+    .bind(record.metadata.expires_at.map(|at| at as i64))
+    .execute(executor)
     .await?;
 
     Ok(())
@@ -87,21 +131,23 @@ pub async fn upsert_record(pool: &PgPool, record: &Record) -> Result<(), sqlx::E
 
 /// Get a record by collection and ID.
 pub async fn get_record(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     collection: &str,
     record_id: &str,
 ) -> Result<Option<StoredRecord>, sqlx::Error> {
     sqlx::query_as::<_, StoredRecord>(
         r#"
         SELECT collection, record_id, version, payload, deleted,
-               clock_counter, clock_node_id, created_at, updated_at
+               clock_counter, clock_node_id, created_at, updated_at,
+               field_clocks, version_vector, crdt, last_op, siblings,
+               expires_at
         FROM records
         WHERE collection = $1 AND record_id = $2
         "#,
     )
     .bind(collection)
     .bind(record_id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await
 }
 
@@ -114,7 +160,9 @@ pub async fn get_records_in_collection(
     sqlx::query_as::<_, StoredRecord>(
         r#"
         SELECT collection, record_id, version, payload, deleted,
-               clock_counter, clock_node_id, created_at, updated_at
+               clock_counter, clock_node_id, created_at, updated_at,
+               field_clocks, version_vector, crdt, last_op, siblings,
+               expires_at
         FROM records
         WHERE collection = $1
         "#,
@@ -130,7 +178,9 @@ pub async fn get_all_active_records(pool: &PgPool) -> Result<Vec<StoredRecord>,
     sqlx::query_as::<_, StoredRecord>(
         r#"
         SELECT collection, record_id, version, payload, deleted,
-               clock_counter, clock_node_id, created_at, updated_at
+               clock_counter, clock_node_id, created_at, updated_at,
+               field_clocks, version_vector, crdt, last_op, siblings,
+               expires_at
         FROM records
         WHERE deleted = false
         "#,
@@ -138,3 +188,48 @@ pub async fn get_all_active_records(pool: &PgPool) -> Result<Vec<StoredRecord>,
     .fetch_all(pool)
     .await
 }
+
+/// Get tombstones deleted before a timestamp (milliseconds since epoch).
+pub async fn get_tombstones_before(
+    pool: &PgPool,
+    older_than: i64,
+) -> Result<Vec<StoredRecord>, sqlx::Error> {
+    sqlx::query_as::<_, StoredRecord>(
+        r#"
+        SELECT collection, record_id, version, payload, deleted,
+               clock_counter, clock_node_id, created_at, updated_at,
+               field_clocks, version_vector, crdt, last_op, siblings,
+               expires_at
+        FROM records
+        WHERE deleted = true AND updated_at < $1
+        "#,
+    )
+    .bind(older_than)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete a tombstone, unless it was written since it was read at
+/// `version`.
+///
+/// Returns whether the tombstone was deleted.
+pub async fn delete_tombstone(
+    executor: impl PgExecutor<'_>,
+    collection: &str,
+    record_id: &str,
+    version: i64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM records
+        WHERE collection = $1 AND record_id = $2 AND version = $3 AND deleted = true
+        "#,
+    )
+    .bind(collection)
+    .bind(record_id)
+    .bind(version)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,47 @@
+//! Tombstone garbage collection.
+//!
+//! Deleted records stay in the records table so late writes lose to them.
+//! Once every known node has acknowledged the writes a tombstone reflects,
+//! no node can still send one it would have to win against, and the
+//! tombstone is purged.
+
+use crate::db::{self, Pool};
+use std::time::Duration;
+
+/// Purge tombstones deleted before `older_than` (milliseconds since epoch)
+/// that every known node has seen.
+///
+/// Returns the number of tombstones purged.
+pub async fn purge_tombstones(pool: &Pool, older_than: i64) -> Result<u64, sqlx::Error> {
+    let Some(stable) = db::get_stable_vector(pool).await? else {
+        return Ok(0);
+    };
+
+    let mut purged = 0;
+    for stored in db::get_tombstones_before(pool, older_than).await? {
+        let record = stored.to_record();
+        if stable.dominates(&record.metadata.version_vector)
+            && db::delete_tombstone(pool, &stored.collection, &stored.record_id, stored.version)
+                .await?
+        {
+            purged += 1;
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Purge tombstones older than `retention` every `interval`, forever.
+pub async fn run(pool: Pool, retention: Duration, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        let older_than = chrono::Utc::now().timestamp_millis() - retention.as_millis() as i64;
+        match purge_tombstones(&pool, older_than).await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!(purged, "Purged tombstones"),
+            Err(e) => tracing::warn!("Tombstone garbage collection failed: {}", e),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::db;
 use crate::error::Result;
-use carry_engine::Operation;
+use carry_engine::{Operation, VersionVector};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
@@ -14,6 +14,8 @@ pub struct PullQuery {
     pub since: Option<String>,
     /// Maximum number of operations to return
     pub limit: Option<i64>,
+    /// Client's node ID, recorded as having seen the returned operations
+    pub node_id: Option<String>,
 }
 
 /// Response for pull sync.
@@ -26,6 +28,9 @@ pub struct PullResponse {
     pub sync_token: String,
     /// Whether there are more operations to fetch
     pub has_more: bool,
+    /// Writes every known node has acknowledged, for purging tombstones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stable_vector: Option<VersionVector>,
 }
 
 /// Default limit for pull operations.
@@ -66,9 +71,23 @@ pub async fn handle_pull(pool: &PgPool, query: PullQuery) -> Result<PullResponse
         query.since.unwrap_or_default()
     };
 
+    // The node has now seen every operation up to the sync token
+    if let Some(node_id) = &query.node_id {
+        let seen = match sync_token.split_once('_') {
+            Some((timestamp, op_id)) => {
+                db::get_delivered_vector(pool, timestamp.parse().unwrap_or(0), op_id).await?
+            }
+            None => VersionVector::new(),
+        };
+        db::acknowledge_node(pool, node_id, &seen).await?;
+    }
+
+    let stable_vector = db::get_stable_vector(pool).await?;
+
     Ok(PullResponse {
         operations,
         sync_token,
         has_more,
+        stable_vector,
     })
 }
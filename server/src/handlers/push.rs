@@ -3,17 +3,17 @@
 use crate::db;
 use crate::error::{AppError, Result};
 use carry_engine::{
-    CollectionSchema, FieldDef, FieldType, MergeStrategy, Operation, Reconciler, Schema,
+    HybridClock, LastOp, MergeStrategy, Operation, Reconciler, Schema, Timestamp, VersionVector,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
 
 /// Request body for push sync.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PushRequest {
     /// Client's node ID
-    #[allow(dead_code)]
     pub node_id: String,
     /// Operations to push
     pub operations: Vec<Operation>,
@@ -42,7 +42,22 @@ pub struct RejectedOp {
 }
 
 /// Process a push request from a client.
-pub async fn handle_push(pool: &PgPool, request: PushRequest) -> Result<PushResponse> {
+///
+/// Conflicts are resolved with each collection's schema merge strategy,
+/// falling back to clock-wins, the same way clients resolve them.
+///
+/// Operations sharing a transaction ID are applied in one database
+/// transaction: if any of them is rejected, all of them are.
+///
+/// Operations whose clock runs more than `max_drift` milliseconds ahead of
+/// server time are rejected, so one device with a far-future clock cannot
+/// push every clock forward.
+pub async fn handle_push(
+    pool: &PgPool,
+    schema: &Schema,
+    max_drift: Option<u64>,
+    request: PushRequest,
+) -> Result<PushResponse> {
     if request.operations.is_empty() {
         let server_clock = db::get_server_clock(pool).await?;
         return Ok(PushResponse {
@@ -52,85 +67,69 @@ pub async fn handle_push(pool: &PgPool, request: PushRequest) -> Result<PushResp
         });
     }
 
-    // Get the default schema (in a real app, this would be loaded from config)
-    let schema = get_default_schema();
-
     let mut accepted = Vec::new();
     let mut rejected = Vec::new();
+    let now = chrono::Utc::now().timestamp_millis() as Timestamp;
 
-    for op in &request.operations {
-        // Check if operation already exists (idempotency)
-        if db::operation_exists(pool, op.op_id()).await? {
-            // Already processed, treat as accepted
-            accepted.push(op.op_id().clone());
+    for group in group_transactions(&request.operations) {
+        if group.iter().any(|op| clock_drifts(op, max_drift, now)) {
+            rejected.extend(group.iter().map(|op| RejectedOp {
+                op_id: op.op_id().clone(),
+                reason: if clock_drifts(op, max_drift, now) {
+                    "clock_drift".to_string()
+                } else {
+                    "transaction".to_string()
+                },
+                winner: None,
+            }));
             continue;
         }
 
-        // Get existing record if any
-        let existing_record = db::get_record(pool, op.collection(), op.record_id()).await?;
-
-        // Check for conflicts
-        if let Some(stored) = existing_record {
-            let existing = stored.to_record();
-
-            // Use reconciler to determine if this operation wins
-            let mut reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
-
-            // Load existing record state
-            let existing_op = create_synthetic_op(&existing);
-            reconciler.load_records(std::iter::once((
-                existing.clone(),
-                existing_op.clone(),
-                carry_engine::OpSource::Remote,
-            )));
-
-            // Run reconciliation with the incoming operation
-            let (result, final_records) = reconciler.reconcile(vec![op.clone()], vec![]);
-
-            // Check if incoming op was accepted or rejected
-            if result.accepted_local.contains(op.op_id()) {
-                // Operation wins - store it
-                if let Err(e) = db::insert_operation(pool, op).await {
-                    // Handle unique constraint violation (race condition)
-                    if is_unique_violation(&e) {
-                        accepted.push(op.op_id().clone());
-                        continue;
-                    }
-                    return Err(e.into());
-                }
+        let mut tx = pool.begin().await?;
+        let mut group_accepted = Vec::new();
+        let mut group_rejected = Vec::new();
+        let mut duplicate = false;
 
-                // Update record state
-                let key = (op.collection().clone(), op.record_id().clone());
-                if let Some(record) = final_records.get(&key) {
-                    db::upsert_record(pool, record).await?;
-                }
-
-                accepted.push(op.op_id().clone());
-            } else {
-                // Operation loses to existing
-                rejected.push(RejectedOp {
-                    op_id: op.op_id().clone(),
-                    reason: "conflict".to_string(),
-                    winner: Some(existing_op.op_id().clone()),
-                });
-            }
-        } else {
-            // No conflict - just insert
-            if let Err(e) = db::insert_operation(pool, op).await {
-                if is_unique_violation(&e) {
-                    accepted.push(op.op_id().clone());
-                    continue;
+        for op in &group {
+            match push_operation(&mut tx, schema, op).await? {
+                PushOutcome::Accepted => group_accepted.push(op.op_id().clone()),
+                PushOutcome::Rejected(rejected_op) => group_rejected.push(rejected_op),
+                PushOutcome::Duplicate => {
+                    duplicate = true;
+                    break;
                 }
-                return Err(e.into());
             }
+        }
 
-            // Create/update record state
-            let record = operation_to_record(op)?;
-            db::upsert_record(pool, &record).await?;
+        if duplicate {
+            // A concurrent push committed the same operations first
+            tx.rollback().await?;
+            accepted.extend(group.iter().map(|op| op.op_id().clone()));
+        } else if group_rejected.is_empty() {
+            tx.commit().await?;
+            accepted.extend(group_accepted);
+        } else {
+            // The transaction loses as a whole
+            tx.rollback().await?;
+            rejected.extend(group_accepted.into_iter().map(|op_id| RejectedOp {
+                op_id,
+                reason: "transaction".to_string(),
+                winner: None,
+            }));
+            rejected.extend(group_rejected);
+        }
+    }
 
-            accepted.push(op.op_id().clone());
+    // The node has seen its own operations. Operations it pushes under
+    // another clock, such as expiry deletes, only count once pulled, since
+    // it may not have seen that clock's earlier writes.
+    let mut seen = VersionVector::new();
+    for op in &request.operations {
+        if op.clock().node_id == request.node_id && !clock_drifts(op, max_drift, now) {
+            seen.observe(op.clock());
         }
     }
+    db::acknowledge_node(pool, &request.node_id, &seen).await?;
 
     let server_clock = db::get_server_clock(pool).await?;
 
@@ -141,28 +140,153 @@ pub async fn handle_push(pool: &PgPool, request: PushRequest) -> Result<PushResp
     })
 }
 
-/// Create a synthetic operation representing current record state.
-fn create_synthetic_op(record: &carry_engine::Record) -> Operation {
-    Operation::Create(carry_engine::CreateOp::new(
-        format!("__existing__{}", record.id),
-        record.id.clone(),
-        record.collection.clone(),
-        record.payload.clone(),
-        record.metadata.created_at,
-        record.metadata.clock.clone(),
-    ))
+/// Check whether an operation's clock runs too far ahead of `now`.
+fn clock_drifts(op: &Operation, max_drift: Option<u64>, now: Timestamp) -> bool {
+    max_drift.is_some_and(|max_drift| {
+        HybridClock::new("server")
+            .with_max_drift(max_drift)
+            .receive(op.clock(), now)
+            .is_err()
+    })
 }
 
-/// Convert an operation to a record (for create operations).
+/// Outcome of pushing a single operation.
+enum PushOutcome {
+    Accepted,
+    Rejected(RejectedOp),
+    /// Another request inserted the operation concurrently
+    Duplicate,
+}
+
+/// Split operations into groups applied atomically.
+///
+/// Operations of one transaction are grouped at the position of its first
+/// operation; operations without a transaction form their own group.
+fn group_transactions(operations: &[Operation]) -> Vec<Vec<&Operation>> {
+    let mut groups: Vec<Vec<&Operation>> = Vec::new();
+    let mut by_transaction: HashMap<&str, usize> = HashMap::new();
+
+    for op in operations {
+        match op.transaction_id() {
+            Some(transaction_id) => match by_transaction.get(transaction_id.as_str()) {
+                Some(&index) => groups[index].push(op),
+                None => {
+                    by_transaction.insert(transaction_id, groups.len());
+                    groups.push(vec![op]);
+                }
+            },
+            None => groups.push(vec![op]),
+        }
+    }
+
+    groups
+}
+
+/// Reconcile and store a single operation.
+async fn push_operation(
+    conn: &mut PgConnection,
+    schema: &Schema,
+    op: &Operation,
+) -> Result<PushOutcome> {
+    // Check if operation already exists (idempotency)
+    if db::operation_exists(&mut *conn, op.op_id()).await? {
+        // Already processed, treat as accepted
+        return Ok(PushOutcome::Accepted);
+    }
+
+    // Get existing record if any
+    let existing_record = db::get_record(&mut *conn, op.collection(), op.record_id()).await?;
+
+    // Check for conflicts
+    let record = if let Some(stored) = existing_record {
+        let existing = stored.to_record();
+
+        // Use reconciler to determine if this operation wins
+        let mut reconciler = Reconciler::new(schema, MergeStrategy::default());
+
+        // Load existing record state, along with the operation that last
+        // wrote it
+        let winner = existing
+            .metadata
+            .last_op
+            .as_ref()
+            .map(|last_op| last_op.op_id.clone());
+        reconciler.load_records(std::iter::once(existing));
+
+        // Run reconciliation with the incoming operation
+        let (result, mut final_records) = reconciler.reconcile(vec![op.clone()], vec![]);
+
+        // Check if incoming op was accepted or rejected
+        if !result.accepted_local.contains(op.op_id()) {
+            // Operation loses to existing
+            return Ok(PushOutcome::Rejected(RejectedOp {
+                op_id: op.op_id().clone(),
+                reason: "conflict".to_string(),
+                winner,
+            }));
+        }
+
+        let key = (op.collection().clone(), op.record_id().clone());
+        final_records.remove(&key)
+    } else {
+        // No conflict - create record state
+        let mut record = operation_to_record(op)?;
+        record.metadata.last_op = Some(LastOp::of(op));
+        Some(record)
+    };
+
+    // Operation wins - store it
+    if let Err(e) = db::insert_operation(&mut *conn, op).await {
+        // Handle unique constraint violation (race condition)
+        if is_unique_violation(&e) {
+            return Ok(PushOutcome::Duplicate);
+        }
+        return Err(e.into());
+    }
+
+    // Update record state
+    if let Some(record) = record {
+        db::upsert_record(&mut *conn, &record).await?;
+    }
+
+    Ok(PushOutcome::Accepted)
+}
+
+/// Convert an operation to a record (for create, restore and upsert
+/// operations).
 fn operation_to_record(op: &Operation) -> Result<carry_engine::Record> {
     match op {
-        Operation::Create(create_op) => Ok(carry_engine::Record::new(
-            create_op.id.clone(),
-            create_op.collection.clone(),
-            create_op.payload.clone(),
-            create_op.timestamp,
-            create_op.clock.clone(),
+        Operation::Create(create_op) => {
+            let mut record = carry_engine::Record::new(
+                create_op.id.clone(),
+                create_op.collection.clone(),
+                create_op.payload.clone(),
+                create_op.timestamp,
+                create_op.clock.clone(),
+            );
+            record.metadata.expires_at = create_op.expires_at;
+            Ok(record)
+        }
+        // The server has no tombstone to revive, so the restored record
+        // starts its history here
+        Operation::Restore(restore_op) => Ok(carry_engine::Record::new(
+            restore_op.id.clone(),
+            restore_op.collection.clone(),
+            restore_op.payload.clone(),
+            restore_op.timestamp,
+            restore_op.clock.clone(),
         )),
+        Operation::Upsert(upsert_op) => {
+            let mut record = carry_engine::Record::new(
+                upsert_op.id.clone(),
+                upsert_op.collection.clone(),
+                upsert_op.payload.clone(),
+                upsert_op.timestamp,
+                upsert_op.clock.clone(),
+            );
+            record.metadata.expires_at = upsert_op.expires_at;
+            Ok(record)
+        }
         Operation::Update(_update_op) => {
             // For updates, we need the existing record - this shouldn't be called
             // for new records
@@ -170,9 +294,24 @@ fn operation_to_record(op: &Operation) -> Result<carry_engine::Record> {
                 "Cannot create record from update operation".to_string(),
             ))
         }
+        Operation::Patch(_) => Err(AppError::BadRequest(
+            "Cannot create record from patch operation".to_string(),
+        )),
         Operation::Delete(_) => Err(AppError::BadRequest(
             "Cannot create record from delete operation".to_string(),
         )),
+        Operation::Increment(_) => Err(AppError::BadRequest(
+            "Cannot create record from increment operation".to_string(),
+        )),
+        Operation::List(_) => Err(AppError::BadRequest(
+            "Cannot create record from list operation".to_string(),
+        )),
+        Operation::Text(_) => Err(AppError::BadRequest(
+            "Cannot create record from text operation".to_string(),
+        )),
+        Operation::Set(_) => Err(AppError::BadRequest(
+            "Cannot create record from set operation".to_string(),
+        )),
     }
 }
 
@@ -185,41 +324,3 @@ fn is_unique_violation(e: &sqlx::Error) -> bool {
         false
     }
 }
-
-/// Get the default schema.
-/// In a real application, this would be loaded from configuration or database.
-fn get_default_schema() -> Schema {
-    // Permissive schema that accepts any collection with JSON payload
-    let mut schema = Schema::new(1);
-
-    // Add some common collections
-    // In production, this would be dynamically configured
-    schema = schema.with_collection(CollectionSchema::new(
-        "users",
-        vec![
-            FieldDef::optional("name", FieldType::String),
-            FieldDef::optional("email", FieldType::String),
-            FieldDef::optional("age", FieldType::Int),
-        ],
-    ));
-
-    schema = schema.with_collection(CollectionSchema::new(
-        "posts",
-        vec![
-            FieldDef::optional("title", FieldType::String),
-            FieldDef::optional("body", FieldType::String),
-            FieldDef::optional("createdAt", FieldType::Timestamp),
-        ],
-    ));
-
-    schema = schema.with_collection(CollectionSchema::new(
-        "todos",
-        vec![
-            FieldDef::optional("title", FieldType::String),
-            FieldDef::optional("completed", FieldType::Bool),
-            FieldDef::optional("createdAt", FieldType::Timestamp),
-        ],
-    ));
-
-    schema
-}
@@ -6,6 +6,7 @@
 use std::sync::Arc;
 
 use axum::extract::ws::{Message, WebSocket};
+use carry_engine::Schema;
 use futures::{SinkExt, StreamExt};
 use sqlx::PgPool;
 use tokio::sync::mpsc;
@@ -24,6 +25,8 @@ use super::{handle_pull, handle_push, PullQuery, PushRequest};
 pub async fn handle_websocket_connection(
     socket: WebSocket,
     pool: Arc<PgPool>,
+    schema: Arc<Schema>,
+    max_drift: Option<u64>,
     conn_manager: Arc<ConnectionManager>,
     node_id: String,
 ) {
@@ -63,8 +66,16 @@ pub async fn handle_websocket_connection(
     while let Some(result) = ws_receiver.next().await {
         match result {
             Ok(Message::Text(text)) => {
-                let response =
-                    process_message(&text, &pool, &conn_manager, &conn_id, &node_id).await;
+                let response = process_message(
+                    &text,
+                    &pool,
+                    &schema,
+                    max_drift,
+                    &conn_manager,
+                    &conn_id,
+                    &node_id,
+                )
+                .await;
 
                 // Send response via the connection manager channel
                 conn_manager.send_to_internal(&conn_id, response);
@@ -106,6 +117,8 @@ pub async fn handle_websocket_connection(
 async fn process_message(
     text: &str,
     pool: &PgPool,
+    schema: &Schema,
+    max_drift: Option<u64>,
     conn_manager: &ConnectionManager,
     conn_id: &str,
     node_id: &str,
@@ -124,13 +137,18 @@ async fn process_message(
             limit,
             request_id,
         } => {
-            let query = PullQuery { since, limit };
+            let query = PullQuery {
+                since,
+                limit,
+                node_id: Some(node_id.to_string()),
+            };
 
             match handle_pull(pool, query).await {
                 Ok(response) => ServerMessage::PullResponse {
                     operations: response.operations,
                     sync_token: response.sync_token,
                     has_more: response.has_more,
+                    stable_vector: response.stable_vector,
                     request_id,
                 },
                 Err(e) => ServerMessage::error(e.to_string(), request_id),
@@ -156,7 +174,7 @@ async fn process_message(
                 operations: operations.clone(),
             };
 
-            match handle_push(pool, request).await {
+            match handle_push(pool, schema, max_drift, request).await {
                 Ok(response) => {
                     // Broadcast accepted operations to other clients
                     if !response.accepted.is_empty() {
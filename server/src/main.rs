@@ -7,6 +7,7 @@ mod auth;
 mod config;
 mod db;
 mod error;
+mod gc;
 mod handlers;
 mod routes;
 mod websocket;
@@ -15,6 +16,7 @@ use crate::config::Config;
 use crate::db::Pool;
 use crate::websocket::ConnectionManager;
 use axum::Router;
+use carry_engine::Schema;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -25,6 +27,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 pub struct AppState {
     pub pool: Pool,
     pub config: Arc<Config>,
+    pub schema: Arc<Schema>,
     pub conn_manager: Arc<ConnectionManager>,
 }
 
@@ -43,6 +46,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
     let config = Config::from_env()?;
 
+    let schema = config.load_schema()?;
+
     tracing::info!("Starting Carry Server on {}:{}", config.host, config.port);
 
     // Create database pool
@@ -52,11 +57,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Running database migrations...");
     db::run_migrations(&pool).await?;
 
+    // Purge tombstones every node has seen in the background
+    if let Some(interval) = config.tombstone_gc_interval {
+        tokio::spawn(gc::run(pool.clone(), config.tombstone_retention, interval));
+    }
+
     // Build application state
     let conn_manager = ConnectionManager::new_shared();
     let state = AppState {
         pool,
         config: Arc::new(config.clone()),
+        schema: Arc::new(schema),
         conn_manager,
     };
 
@@ -33,7 +33,13 @@ async fn push_handler(
     _auth: AuthUser,
     Json(request): Json<PushRequest>,
 ) -> Result<Json<PushResponse>> {
-    let response = handle_push(&state.pool, request).await?;
+    let response = handle_push(
+        &state.pool,
+        &state.schema,
+        state.config.max_clock_drift,
+        request,
+    )
+    .await?;
     Ok(Json(response))
 }
 
@@ -98,11 +104,13 @@ async fn websocket_handler(
     }
 
     let pool = Arc::new(state.pool.clone());
+    let schema = state.schema.clone();
+    let max_drift = state.config.max_clock_drift;
     let conn_manager = state.conn_manager.clone();
 
     tracing::info!(node_id = %node_id, "WebSocket upgrade requested");
 
     ws.on_upgrade(move |socket: WebSocket| {
-        handle_websocket_connection(socket, pool, conn_manager, node_id)
+        handle_websocket_connection(socket, pool, schema, max_drift, conn_manager, node_id)
     })
 }
@@ -95,6 +95,7 @@ impl ConnectionManager {
     /// Broadcast a message to all connections except the sender.
     ///
     /// Returns the number of connections that received the message.
+    #[allow(clippy::collapsible_if)]
     pub fn broadcast_except(&self, sender_conn_id: &str, message: ServerMessage) -> usize {
         let mut sent_count = 0;
 
@@ -169,11 +170,13 @@ impl Clone for ServerMessage {
                 operations,
                 sync_token,
                 has_more,
+                stable_vector,
                 request_id,
             } => ServerMessage::PullResponse {
                 operations: operations.clone(),
                 sync_token: sync_token.clone(),
                 has_more: *has_more,
+                stable_vector: stable_vector.clone(),
                 request_id: request_id.clone(),
             },
             ServerMessage::PushResponse {
@@ -2,7 +2,7 @@
 //!
 //! All messages are JSON-encoded and use snake_case for field names.
 
-use carry_engine::Operation;
+use carry_engine::{Operation, VersionVector};
 use serde::{Deserialize, Serialize};
 
 use crate::handlers::RejectedOp;
@@ -50,6 +50,9 @@ pub enum ServerMessage {
         sync_token: String,
         /// Whether there are more operations to fetch
         has_more: bool,
+        /// Writes every known node has acknowledged, for purging tombstones
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stable_vector: Option<VersionVector>,
         /// Request ID from the original request
         #[serde(skip_serializing_if = "Option::is_none")]
         request_id: Option<String>,
@@ -110,6 +110,69 @@ mod protocol_tests {
         assert_eq!(records.len(), 2);
     }
 
+    #[test]
+    fn test_schema_json_merge_strategy() {
+        use carry_engine::{LastOp, MergeStrategy, Reconciler, Record, Schema, UpdateOp};
+
+        // Schema as shared with clients
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "collections": {
+                    "todos": {
+                        "name": "todos",
+                        "fields": [{"name": "title", "fieldType": "string", "required": false}],
+                        "mergeStrategy": "deleteWins"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        // The server's default is overridden by the collection's strategy
+        let mut reconciler = Reconciler::new(&schema, MergeStrategy::ClockWins);
+        let deleted = {
+            let mut record = Record::new(
+                "todo-1",
+                "todos",
+                json!({"title": "Test todo"}),
+                1706745600000,
+                LogicalClock::with_counter("device-2", 1),
+            );
+            record.mark_deleted(
+                1706745601000,
+                LogicalClock::with_counter("device-2", 2),
+                carry_engine::Origin::Remote,
+            );
+            record.metadata.last_op = Some(LastOp {
+                op_id: "op-delete".to_string(),
+                clock: LogicalClock::with_counter("device-2", 2),
+                timestamp: 1706745601000,
+            });
+            record
+        };
+        reconciler.load_records(std::iter::once(deleted));
+
+        let update = Operation::Update(UpdateOp::new(
+            "op-update",
+            "todo-1",
+            "todos",
+            json!({"title": "Edited"}),
+            1,
+            1706745602000,
+            LogicalClock::with_counter("device-1", 5),
+        ));
+
+        let (result, records) = reconciler.reconcile(vec![update], vec![]);
+
+        assert!(result.rejected_local.contains(&"op-update".to_string()));
+        assert_eq!(result.conflicts[0].winner_op_id, "op-delete");
+        let record = records
+            .get(&("todos".to_string(), "todo-1".to_string()))
+            .unwrap();
+        assert!(record.deleted);
+    }
+
     #[test]
     fn test_sync_token_format() {
         // Sync token format: "timestamp_opId"
@@ -155,6 +218,22 @@ mod protocol_tests {
         assert_eq!(request.operations[0].op_id(), "op-1");
     }
 
+    #[test]
+    fn test_push_transaction_id() {
+        let op = create_test_op("op-1", "todo-1", "device-1", 1).with_transaction_id("op-1");
+
+        let json = serde_json::to_value(&op).unwrap();
+        assert_eq!(json["transactionId"], "op-1");
+
+        let parsed: Operation = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.transaction_id().map(String::as_str), Some("op-1"));
+
+        // Operations without a transaction omit the field
+        let single = create_test_op("op-2", "todo-2", "device-1", 2);
+        let json = serde_json::to_value(&single).unwrap();
+        assert!(json.get("transactionId").is_none());
+    }
+
     #[test]
     fn test_pull_response_serialization() {
         let ops = vec![create_test_op("op-1", "todo-1", "device-1", 1)];